@@ -38,13 +38,20 @@ extern crate toml;
 extern crate toml_query;
 
 extern crate libimagbookmark;
+extern crate libimagentrytag;
 #[macro_use] extern crate libimagrt;
 extern crate libimagerror;
 extern crate libimagutil;
 
 use std::io::Write;
 use std::process::exit;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io::Read;
+use std::path::Path;
+use std::time::Duration;
 
+use clap::Values;
 use toml_query::read::TomlValueReadTypeExt;
 
 use libimagrt::runtime::Runtime;
@@ -53,7 +60,12 @@ use libimagbookmark::collection::BookmarkCollection;
 use libimagbookmark::collection::BookmarkCollectionStore;
 use libimagbookmark::error::BookmarkError as BE;
 use libimagbookmark::link::Link as BookmarkLink;
-use libimagerror::trace::{MapErrTrace, trace_error};
+use libimagbookmark::import::{import_bookmarks, parse_firefox_json, parse_netscape_html, parse_url_list};
+use libimagbookmark::export::to_netscape_html;
+use libimagbookmark::metadata::{Bookmark, collect_bookmarks};
+use libimagbookmark::search::{matches, search as search_bookmarks};
+use libimagbookmark::check::{HttpLinkChecker, LinkStatus, check_bookmarks};
+use libimagerror::trace::MapErrTrace;
 use libimagerror::io::ToExitCode;
 use libimagerror::exit::ExitUnwrap;
 
@@ -77,6 +89,10 @@ fn main() {
                 "collection" => collection(&rt),
                 "list"       => list(&rt),
                 "remove"     => remove(&rt),
+                "search"     => search(&rt),
+                "check"      => check(&rt),
+                "import"     => import(&rt),
+                "export"     => export(&rt),
                 other        => {
                     debug!("Unknown command");
                     let _ = rt.handle_unknown_subcommand("imag-bookmark", other, rt.cli())
@@ -97,9 +113,19 @@ fn add(rt: &Runtime) {
         .ok_or(BE::from(format!("No bookmark collection '{}' found", coll)))
         .map_err_trace_exit_unwrap(1);
 
+    let tags = scmd
+        .values_of("tag")
+        .map(|tags| tags.map(String::from).collect::<Vec<_>>())
+        .unwrap_or_else(Vec::new);
+
     for url in scmd.values_of("urls").unwrap() { // unwrap saved by clap
+        let mut bookmark     = Bookmark::new(String::from(url));
+        bookmark.title       = scmd.value_of("title").map(String::from);
+        bookmark.description = scmd.value_of("description").map(String::from);
+        bookmark.tags        = tags.clone();
+
         let _ = collection
-            .add_link(rt.store(), BookmarkLink::from(url))
+            .add_bookmark(rt.store(), &bookmark)
             .map_err_trace_exit_unwrap(1);
     }
 
@@ -131,26 +157,82 @@ fn collection(rt: &Runtime) {
 }
 
 fn list(rt: &Runtime) {
+    let scmd = rt.cli().subcommand_matches("list").unwrap();
     let coll = get_collection_name(rt, "list", "collection");
+    let tags = scmd
+        .values_of("tag")
+        .map(|tags| tags.map(String::from).collect::<Vec<_>>())
+        .unwrap_or_else(Vec::new);
 
-    let collection = BookmarkCollectionStore::get(rt.store(), &coll)
-        .map_err_trace_exit_unwrap(1)
-        .ok_or(BE::from(format!("No bookmark collection '{}' found", coll)))
-        .map_err_trace_exit_unwrap(1);
-
-    let links   = collection.links(rt.store()).map_err_trace_exit_unwrap(1);
+    let bookmarks = collect_bookmarks(rt.store(), &[coll]).map_err_trace_exit_unwrap(1);
     debug!("Listing...");
-    for (i, link) in links.enumerate() {
-        match link {
-            Ok(link) => writeln!(rt.stdout(), "{: >3}: {}", i, link).to_exit_code().unwrap_or_exit(),
-            Err(e)   => trace_error(&e)
-        }
+    for (i, bookmark) in bookmarks.iter().filter(|bm| matches(bm, "", &tags)).enumerate() {
+        print_bookmark(rt, i, bookmark);
     };
     debug!("... ready with listing");
 
     info!("Ready");
 }
 
+fn search(rt: &Runtime) {
+    let scmd  = rt.cli().subcommand_matches("search").unwrap();
+    let query = scmd.value_of("query").unwrap(); // enforced by clap
+    let tags  = scmd
+        .values_of("tag")
+        .map(|tags| tags.map(String::from).collect::<Vec<_>>())
+        .unwrap_or_else(Vec::new);
+
+    let collections = get_collection_names(rt, scmd.values_of("collection"));
+    let bookmarks   = search_bookmarks(rt.store(), &collections, query, &tags)
+        .map_err_trace_exit_unwrap(1);
+
+    for (i, bookmark) in bookmarks.iter().enumerate() {
+        print_bookmark(rt, i, bookmark);
+    }
+}
+
+fn check(rt: &Runtime) {
+    let scmd    = rt.cli().subcommand_matches("check").unwrap();
+    let timeout = scmd
+        .value_of("timeout")
+        .unwrap() // default value by clap
+        .parse::<u64>()
+        .unwrap_or_else(|e| {
+            error!("Invalid timeout: {}", e);
+            exit(1)
+        });
+
+    let collections = get_collection_names(rt, scmd.values_of("collection"));
+    let bookmarks   = collect_bookmarks(rt.store(), &collections).map_err_trace_exit_unwrap(1);
+    let checker     = HttpLinkChecker::new(Duration::from_secs(timeout)).map_err_trace_exit_unwrap(1);
+
+    let mut dead = 0;
+    for (bookmark, status) in check_bookmarks(&checker, &bookmarks) {
+        let reason = match status {
+            LinkStatus::Alive(_)       => continue,
+            LinkStatus::Dead(code)     => format!("HTTP {}", code),
+            LinkStatus::Unreachable(e) => e,
+        };
+
+        dead += 1;
+        writeln!(rt.stdout(), "Dead: {} ({})", bookmark.url, reason)
+            .to_exit_code()
+            .unwrap_or_exit();
+    }
+
+    info!("Checked {} bookmarks, {} dead", bookmarks.len(), dead);
+    if dead != 0 {
+        exit(1)
+    }
+}
+
+fn print_bookmark(rt: &Runtime, i: usize, bookmark: &Bookmark) {
+    let _ = match bookmark.title {
+        Some(ref title) => writeln!(rt.stdout(), "{: >3}: {} - {}", i, bookmark.url, title),
+        None            => writeln!(rt.stdout(), "{: >3}: {}", i, bookmark.url),
+    }.to_exit_code().unwrap_or_exit();
+}
+
 fn remove(rt: &Runtime) {
     let scmd = rt.cli().subcommand_matches("remove").unwrap();
     let coll = get_collection_name(rt, "list", "collection");
@@ -169,6 +251,92 @@ fn remove(rt: &Runtime) {
     info!("Ready");
 }
 
+fn import(rt: &Runtime) {
+    let scmd = rt.cli().subcommand_matches("import").unwrap();
+    let path = scmd.value_of("file").unwrap(); // enforced by clap
+    let coll = get_collection_name(rt, "import", "collection");
+
+    let mut content = String::new();
+    let _ = File::open(path)
+        .and_then(|mut f| f.read_to_string(&mut content))
+        .map_err_trace_exit_unwrap(1);
+
+    let format = scmd
+        .value_of("format")
+        .map(String::from)
+        .unwrap_or_else(|| guess_format(path, &content));
+
+    let bookmarks = match format.as_str() {
+        "netscape" => parse_netscape_html(&content),
+        "firefox"  => parse_firefox_json(&content).map_err_trace_exit_unwrap(1),
+        "urls"     => parse_url_list(&content),
+        other      => {
+            error!("Unknown format: {}", other);
+            exit(1)
+        },
+    };
+
+    let report = import_bookmarks(rt.store(), bookmarks, &coll).map_err_trace_exit_unwrap(1);
+    for url in report.invalid.iter() {
+        warn!("Not imported, invalid URL: {}", url);
+    }
+
+    info!("Imported {} bookmarks, {} were already present, {} invalid",
+          report.imported,
+          report.skipped,
+          report.invalid.len());
+}
+
+/// Guess the format of a bookmark file from its extension and content
+fn guess_format(path: &str, content: &str) -> String {
+    let ext = Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase());
+
+    let format = match ext.as_ref().map(String::as_str) {
+        Some("html") | Some("htm") => "netscape",
+        Some("json")               => "firefox",
+        _ if content.trim_left().starts_with('{') => "firefox",
+        _ if content.trim_left().starts_with('<') => "netscape",
+        _                          => "urls",
+    };
+
+    String::from(format)
+}
+
+fn export(rt: &Runtime) {
+    let scmd = rt.cli().subcommand_matches("export").unwrap();
+
+    let collections = get_collection_names(rt, scmd.values_of("collection"));
+    let bookmarks   = collect_bookmarks(rt.store(), &collections).map_err_trace_exit_unwrap(1);
+    let html        = to_netscape_html(&bookmarks);
+
+    match scmd.value_of("output") {
+        Some(path) => {
+            let _ = OpenOptions::new()
+                .write(true)
+                .create(true)
+                .truncate(true)
+                .open(path)
+                .and_then(|mut f| f.write_all(html.as_bytes()))
+                .map_err_trace_exit_unwrap(1);
+
+            info!("Exported {} bookmarks to {}", bookmarks.len(), path);
+        },
+        None => {
+            let _ = write!(rt.stdout(), "{}", html).to_exit_code().unwrap_or_exit();
+        },
+    }
+}
+
+/// Get the collections passed on the commandline, or all collections if none were passed
+fn get_collection_names(rt: &Runtime, names: Option<Values>) -> Vec<String> {
+    match names {
+        Some(names) => names.map(String::from).collect(),
+        None        => rt.store().collection_names().map_err_trace_exit_unwrap(1),
+    }
+}
 
 fn get_collection_name(rt: &Runtime,
                        subcommand_name: &str,
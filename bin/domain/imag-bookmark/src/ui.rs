@@ -20,6 +20,7 @@
 use clap::{Arg, App, SubCommand};
 
 use libimagutil::cli_validators::*;
+use libimagentrytag::tag::is_tag;
 
 pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
     app
@@ -43,6 +44,31 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                         .value_name("URL")
                         .validator(is_url)
                         .help("Add this URL, multiple possible"))
+                   .arg(Arg::with_name("title")
+                        .long("title")
+                        .takes_value(true)
+                        .required(false)
+                        .multiple(false)
+                        .value_name("TITLE")
+                        .help("Set this title for the added bookmarks"))
+                   .arg(Arg::with_name("description")
+                        .long("description")
+                        .short("d")
+                        .takes_value(true)
+                        .required(false)
+                        .multiple(false)
+                        .value_name("DESCRIPTION")
+                        .help("Set this description for the added bookmarks"))
+                   .arg(Arg::with_name("tag")
+                        .long("tag")
+                        .short("t")
+                        .takes_value(true)
+                        .required(false)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .value_name("TAG")
+                        .validator(is_tag)
+                        .help("Tag the added bookmarks with this tag, multiple possible"))
                    )
 
         .subcommand(SubCommand::with_name("remove")
@@ -91,14 +117,67 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                         .multiple(false)
                         .value_name("COLLECTION")
                         .help("Select from this collection, if not specified default from config will be used"))
-                   .arg(Arg::with_name("tags")
-                        .long("tags")
+                   .arg(Arg::with_name("tag")
+                        .long("tag")
+                        .alias("tags")
                         .short("t")
                         .takes_value(true)
                         .required(false)
                         .multiple(true)
-                        .value_name("TAGS")
-                        .help("Filter links to contain these tags. When multiple tags are specified, all of them must be set for the link to match."))
+                        .number_of_values(1)
+                        .value_name("TAG")
+                        .help("Only list bookmarks with this tag, multiple possible (all must match)"))
+                   )
+
+        .subcommand(SubCommand::with_name("search")
+                   .about("Search bookmarks by URL, title and description")
+                   .version("0.1")
+                   .arg(Arg::with_name("query")
+                        .index(1)
+                        .takes_value(true)
+                        .required(true)
+                        .multiple(false)
+                        .value_name("QUERY")
+                        .help("Search for this text (case insensitive), use an empty string to match all bookmarks"))
+                   .arg(Arg::with_name("tag")
+                        .long("tag")
+                        .short("t")
+                        .takes_value(true)
+                        .required(false)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .value_name("TAG")
+                        .help("Only show bookmarks with this tag, multiple possible (all must match)"))
+                   .arg(Arg::with_name("collection")
+                        .long("collection")
+                        .short("c")
+                        .takes_value(true)
+                        .required(false)
+                        .multiple(true)
+                        .value_name("COLLECTION")
+                        .help("Search in these collections (default: all)"))
+                   )
+
+        .subcommand(SubCommand::with_name("check")
+                   .about("Check bookmarks for dead links")
+                   .version("0.1")
+                   .arg(Arg::with_name("collection")
+                        .long("collection")
+                        .short("c")
+                        .takes_value(true)
+                        .required(false)
+                        .multiple(true)
+                        .value_name("COLLECTION")
+                        .help("Check these collections (default: all)"))
+                   .arg(Arg::with_name("timeout")
+                        .long("timeout")
+                        .takes_value(true)
+                        .required(false)
+                        .multiple(false)
+                        .value_name("SECONDS")
+                        .default_value("10")
+                        .validator(is_integer)
+                        .help("Wait at most this many seconds for each link"))
                    )
 
         .subcommand(SubCommand::with_name("collection")
@@ -117,4 +196,54 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                         .value_name("NAME")
                         .help("Remove a collection with this name (and all links)"))
                    )
+
+        .subcommand(SubCommand::with_name("import")
+                   .about("Import bookmarks from a browser")
+                   .version("0.1")
+                   .arg(Arg::with_name("file")
+                        .index(1)
+                        .takes_value(true)
+                        .required(true)
+                        .multiple(false)
+                        .value_name("FILE")
+                        .help("The file to import bookmarks from"))
+                   .arg(Arg::with_name("format")
+                        .long("format")
+                        .short("f")
+                        .takes_value(true)
+                        .required(false)
+                        .multiple(false)
+                        .value_name("FORMAT")
+                        .possible_values(&["netscape", "firefox", "urls"])
+                        .help("The format of the file: 'netscape' for bookmark HTML exported by browsers, 'firefox' for Firefox JSON backups, 'urls' for one URL per line (default: guessed from the file)"))
+                   .arg(Arg::with_name("collection")
+                        .long("collection")
+                        .short("c")
+                        .takes_value(true)
+                        .required(false)
+                        .multiple(false)
+                        .value_name("COLLECTION")
+                        .help("Import bookmarks which are not in a folder into this collection, if not specified default from config will be used"))
+                   )
+
+        .subcommand(SubCommand::with_name("export")
+                   .about("Export bookmarks as Netscape bookmark HTML, which can be imported by browsers")
+                   .version("0.1")
+                   .arg(Arg::with_name("collection")
+                        .long("collection")
+                        .short("c")
+                        .takes_value(true)
+                        .required(false)
+                        .multiple(true)
+                        .value_name("COLLECTION")
+                        .help("Export these collections (default: all)"))
+                   .arg(Arg::with_name("output")
+                        .long("output")
+                        .short("o")
+                        .takes_value(true)
+                        .required(false)
+                        .multiple(false)
+                        .value_name("FILE")
+                        .help("Write to this file instead of stdout"))
+                   )
 }
@@ -0,0 +1,71 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+use std::process::exit;
+use std::io::Write;
+
+use chrono::Duration;
+use chrono::NaiveDate;
+
+use libimagcontact::birthday::contact_dates;
+use libimagcontact::birthday::events_between;
+use libimagcontact::birthday::to_ics;
+use libimagrt::runtime::Runtime;
+use libimagerror::trace::MapErrTrace;
+use libimagerror::io::ToExitCode;
+use libimagerror::exit::ExitUnwrap;
+use libimagutil::date::date_from_string;
+
+pub fn birthdays(rt: &Runtime) {
+    let scmd = rt.cli().subcommand_matches("birthdays").unwrap(); // secured by main
+
+    if scmd.is_present("birthdays-ics") {
+        let dates = contact_dates(rt.store()).map_err_trace_exit_unwrap(1);
+        let now   = ::chrono::offset::Utc::now().naive_utc();
+
+        let _ = write!(rt.stdout(), "{}", to_ics(&dates, &now))
+            .to_exit_code()
+            .unwrap_or_exit();
+        return;
+    }
+
+    let from = match scmd.value_of("birthdays-from") {
+        Some(s) => date_from_string(s).unwrap_or_else(|e| {
+            error!("Cannot parse '{}' as date: {}", s, e);
+            exit(1)
+        }),
+        None    => ::chrono::offset::Local::today().naive_local(),
+    };
+
+    let days = scmd
+        .value_of("birthdays-days")
+        .map(|d| d.parse::<i64>().unwrap_or_else(|_| {
+            error!("Cannot parse '{}' as number of days", d);
+            exit(1)
+        }))
+        .unwrap_or(30);
+
+    let until : NaiveDate = from + Duration::days(days);
+    let out               = rt.stdout();
+    let mut outlock       = out.lock();
+
+    for event in events_between(rt.store(), &from, &until).map_err_trace_exit_unwrap(1) {
+        let _ = writeln!(outlock, "{}", event).to_exit_code().unwrap_or_exit();
+    }
+}
@@ -72,16 +72,6 @@ mod test {
     }
 }
 
-macro_rules! ask_continue {
-    { yes => $yes:expr; no => $no:expr } => {
-        if ::libimaginteraction::ask::ask_bool("Edit tempfile", Some(true)) {
-            $yes
-        } else {
-            $no
-        }
-    };
-}
-
 pub fn create(rt: &Runtime) {
     let scmd         = rt.cli().subcommand_matches("create").unwrap();
     let mut template = String::from(TEMPLATE);
@@ -207,7 +197,7 @@ pub fn create(rt: &Runtime) {
     info!("Ready");
 }
 
-fn parse_toml_into_vcard(toml: Value, uuid: String) -> Option<Vcard> {
+pub fn parse_toml_into_vcard(toml: Value, uuid: String) -> Option<Vcard> {
     let mut vcard = Vcard::default().with_uid(uuid);
 
     { // parse name
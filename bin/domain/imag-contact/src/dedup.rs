@@ -0,0 +1,121 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+use std::collections::HashMap;
+use std::process::exit;
+use std::io::Write;
+use std::fs::remove_file;
+
+use libimagcontact::store::ContactStore;
+use libimagcontact::contact::Contact;
+use libimagcontact::error::ContactError as CE;
+use libimagrt::runtime::Runtime;
+use libimagerror::trace::MapErrTrace;
+use libimagerror::io::ToExitCode;
+use libimagerror::exit::ExitUnwrap;
+use libimagstore::storeid::StoreId;
+use libimaginteraction::ask::ask_bool;
+
+pub fn dedup(rt: &Runtime) {
+    let scmd         = rt.cli().subcommand_matches("dedup").unwrap(); // secured by main
+    let remove_files = scmd.is_present("dedup-remove-files");
+    let duplicates   = rt.store().find_duplicates().map_err_trace_exit_unwrap(1);
+
+    if duplicates.is_empty() {
+        info!("No duplicates found");
+        return;
+    }
+
+    // Contacts which were merged already, mapped to the contact they were merged into
+    let mut merged : HashMap<StoreId, StoreId> = HashMap::new();
+
+    for duplicate in duplicates {
+        let first  = resolve(&merged, duplicate.first);
+        let second = resolve(&merged, duplicate.second);
+
+        if first == second {
+            debug!("{} was merged already", second);
+            continue;
+        }
+
+        let reasons = duplicate
+            .reasons
+            .iter()
+            .map(|r| r.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let _ = writeln!(rt.stdout(),
+                         "{}\n{}\nPossible duplicate: {}",
+                         describe(rt, &first),
+                         describe(rt, &second),
+                         reasons)
+            .to_exit_code()
+            .unwrap_or_exit();
+
+        if !ask_bool(&format!("Merge {} into {}", second, first), Some(false)) {
+            continue;
+        }
+
+        let (_, path) = rt
+            .store()
+            .merge_contacts(first.clone(), second.clone())
+            .map_err_trace_exit_unwrap(1);
+
+        info!("Merged {} into {}", second, first);
+        merged.insert(second, first);
+
+        if let Some(path) = path {
+            if remove_files {
+                let _ = remove_file(&path).map_err(CE::from).map_err_trace_exit_unwrap(1);
+                info!("Removed {}", path.display());
+            } else {
+                info!("The vcard file of the merged contact is left at {}", path.display());
+            }
+        }
+    }
+}
+
+/// Follow the merges of `id` to the contact it ended up in
+fn resolve(merged: &HashMap<StoreId, StoreId>, id: StoreId) -> StoreId {
+    let mut id = id;
+    while let Some(next) = merged.get(&id) {
+        id = next.clone();
+    }
+    id
+}
+
+fn describe(rt: &Runtime, id: &StoreId) -> String {
+    let entry = rt
+        .store()
+        .get(id.clone())
+        .map_err_trace_exit_unwrap(1)
+        .unwrap_or_else(|| {
+            error!("Entry {} not found", id);
+            exit(1)
+        });
+
+    let card = entry.deser().map_err_trace_exit_unwrap(1);
+
+    format!("{id}\n  Name  : {name}\n  Email : {email}\n  Phone : {phone}",
+            id    = id,
+            name  = card.fullname().join(", "),
+            email = card.email().iter().map(|e| e.address.clone()).collect::<Vec<_>>().join(", "),
+            phone = card.tel().join(", "))
+}
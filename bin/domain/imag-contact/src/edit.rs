@@ -0,0 +1,285 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+use std::collections::BTreeMap;
+use std::process::exit;
+
+use toml::Value;
+use vobject::write_component;
+
+use libimagcontact::card::ContactCard;
+use libimagcontact::contact::Contact;
+use libimagcontact::error::ContactError as CE;
+use libimagrt::runtime::Runtime;
+use libimagerror::str::ErrFromStr;
+use libimagerror::trace::MapErrTrace;
+use libimagerror::trace::trace_error;
+use libimagutil::warn_result::WarnResult;
+
+use create::parse_toml_into_vcard;
+use util::find_contact_by_hash;
+
+const HEADER : &'static str = "# Edit the contact, using the format of the 'imag-contact create' template.
+# Properties of the vcard which are not listed here are kept as they are.
+
+";
+
+/// The vcard properties which can be edited with the template
+const TEMPLATE_PROPERTIES : &'static [&'static str] = &[
+    "N", "BDAY", "NICKNAME", "ORG", "TITLE", "ROLE", "TEL", "ADR", "EMAIL", "CATEGORIES", "URL", "NOTE",
+];
+
+/// The vcard properties of which the template only contains one value
+const SINGLE_VALUE_PROPERTIES : &'static [&'static str] = &["BDAY", "TITLE", "ROLE", "URL", "NOTE"];
+
+pub fn edit(rt: &Runtime) {
+    let scmd      = rt.cli().subcommand_matches("edit").unwrap(); // secured by main
+    let hash      = scmd.value_of("hash").unwrap(); // secured by clap
+    let mut entry = find_contact_by_hash(rt, hash);
+    let mut card  = entry.card().map_err_trace_exit_unwrap(1);
+    let uid       = card.uid().unwrap_or_else(|| {
+        error!("Contact has no UID");
+        exit(1)
+    });
+
+    let original     = render_card(&card);
+    let mut template = original.clone();
+
+    let edited = loop {
+        ::libimagentryedit::edit::edit_in_tmpfile(&rt, &mut template)
+            .map_warn_err_str("Editing failed.")
+            .map_err_trace_exit_unwrap(1);
+
+        if template == original {
+            info!("No changes, not doing anything.");
+            return;
+        }
+
+        match ::toml::de::from_str(&template)
+            .map(|toml| parse_toml_into_vcard(toml, uid.clone()))
+            .err_from_str()
+            .map_err(CE::from)
+        {
+            Err(e) => {
+                error!("Error parsing template");
+                trace_error(&e);
+                ask_continue! { yes => continue; no => exit(1) };
+            },
+
+            Ok(None)        => continue,
+            Ok(Some(vcard)) => break ContactCard::from_buf(&write_component(&vcard))
+                .map_err_trace_exit_unwrap(1),
+        }
+    };
+
+    apply_template(&mut card, &edited);
+    entry.write_card(&card).map_err_trace_exit_unwrap(1);
+    info!("Ready");
+}
+
+/// Take the properties which can be edited with the template from the `edited` card
+///
+/// The template only shows the first value of the `SINGLE_VALUE_PROPERTIES`, so these are kept as
+/// they are if the card has more than one value, instead of dropping the values which were not
+/// shown.
+fn apply_template(card: &mut ContactCard, edited: &ContactCard) {
+    let properties = TEMPLATE_PROPERTIES
+        .iter()
+        .cloned()
+        .filter(|name| !(SINGLE_VALUE_PROPERTIES.contains(name) && card.properties(name).len() > 1))
+        .collect::<Vec<_>>();
+
+    card.update_from(edited, &properties);
+
+    // The full name is only built from the template if first and last name are given
+    if !edited.properties("FN").is_empty() {
+        card.update_from(edited, &["FN"]);
+    }
+}
+
+/// Render a card in the format of the `create` template
+fn render_card(card: &ContactCard) -> String {
+    let string  = |s: String| Value::String(s);
+    let strings = |v: Vec<String>| Value::Array(v.into_iter().map(Value::String).collect());
+    let typed   = |v: Vec<(Option<String>, String)>, key: &str, default: &str| {
+        Value::Array(v.into_iter()
+            .map(|(ty, value)| {
+                let mut t = BTreeMap::new();
+                t.insert(String::from("type"), Value::String(ty.unwrap_or_else(|| String::from(default))));
+                t.insert(String::from(key), Value::String(value));
+                Value::Table(t)
+            })
+            .collect())
+    };
+
+    let mut toml = BTreeMap::new();
+
+    { // name
+        let mut name  = BTreeMap::new();
+        let mut parts = card.name_parts().into_iter();
+
+        for key in &["last", "first", "additional", "prefix", "suffix"] {
+            match parts.next() {
+                Some(part) => if !part.is_empty() || *key == "first" || *key == "last" {
+                    name.insert(String::from(*key), string(part));
+                },
+                None => if *key == "first" || *key == "last" {
+                    name.insert(String::from(*key), string(String::new()));
+                },
+            }
+        }
+
+        toml.insert(String::from("name"), Value::Table(name));
+    }
+
+    { // person
+        let mut person = BTreeMap::new();
+
+        if let Some(bday) = card.values("BDAY").into_iter().next() {
+            person.insert(String::from("birthday"), string(bday));
+        }
+
+        let phones = card.typed_values("TEL");
+        if !phones.is_empty() {
+            person.insert(String::from("phone"), typed(phones, "number", "voice"));
+        }
+
+        let emails = card.typed_values("EMAIL");
+        if !emails.is_empty() {
+            person.insert(String::from("email"), typed(emails, "addr", "internet"));
+        }
+
+        toml.insert(String::from("person"), Value::Table(person));
+    }
+
+    { // addresses
+        let addresses = card
+            .typed_values("ADR")
+            .into_iter()
+            .zip(card.addresses())
+            .map(|((ty, _), adr)| {
+                let ty = ty.unwrap_or_else(|| String::from("home"));
+
+                let mut t = BTreeMap::new();
+                t.insert(String::from("type")     , string(ty));
+                t.insert(String::from("box")      , string(adr.po_box));
+                t.insert(String::from("extended") , string(adr.extended));
+                t.insert(String::from("street")   , string(adr.street));
+                t.insert(String::from("code")     , string(adr.code));
+                t.insert(String::from("city")     , string(adr.city));
+                t.insert(String::from("region")   , string(adr.region));
+                t.insert(String::from("country")  , string(adr.country));
+                Value::Table(t)
+            })
+            .collect::<Vec<_>>();
+
+        if !addresses.is_empty() {
+            toml.insert(String::from("addresses"), Value::Array(addresses));
+        }
+    }
+
+    { // nicknames
+        let nicknames = card
+            .typed_values("NICKNAME")
+            .into_iter()
+            .map(|(ty, name)| {
+                let mut t = BTreeMap::new();
+                if let Some(ty) = ty {
+                    t.insert(String::from("type"), string(ty));
+                }
+                t.insert(String::from("name"), string(name));
+                Value::Table(t)
+            })
+            .collect::<Vec<_>>();
+
+        if !nicknames.is_empty() {
+            toml.insert(String::from("nickname"), Value::Array(nicknames));
+        }
+    }
+
+    { // organisation
+        let mut org = BTreeMap::new();
+
+        let names = card.values("ORG");
+        if !names.is_empty() {
+            org.insert(String::from("name"), strings(names));
+        }
+
+        // The template has only one title, role, webpage and note
+        for &(key, prop) in &[("title", "TITLE"), ("role", "ROLE")] {
+            if let Some(value) = card.values(prop).into_iter().next() {
+                org.insert(String::from(key), string(value));
+            }
+        }
+
+        toml.insert(String::from("organisation"), Value::Table(org));
+    }
+
+    { // other
+        let mut other = BTreeMap::new();
+
+        let categories = card.categories();
+        if !categories.is_empty() {
+            other.insert(String::from("categories"), strings(categories));
+        }
+
+        for &(key, prop) in &[("webpage", "URL"), ("note", "NOTE")] {
+            if let Some(value) = card.values(prop).into_iter().next() {
+                other.insert(String::from(key), string(value));
+            }
+        }
+
+        toml.insert(String::from("other"), Value::Table(other));
+    }
+
+    let rendered = ::toml::ser::to_string(&Value::Table(toml))
+        .err_from_str()
+        .map_err(CE::from)
+        .map_err_trace_exit_unwrap(1);
+
+    format!("{}{}", HEADER, rendered)
+}
+
+#[cfg(test)]
+mod test {
+    use vobject::write_component;
+
+    use libimagcontact::card::ContactCard;
+
+    use create::parse_toml_into_vcard;
+    use super::apply_template;
+    use super::render_card;
+
+    const VCARD : &'static str = "BEGIN:VCARD\r\nVERSION:4.0\r\nUID:uid\r\nFN:Foo Bar\r\nN:Bar;Foo;;;\r\nTITLE:Boss\r\nNOTE:first\r\nNOTE:second\r\nURL:https://a.example.com\r\nURL:https://b.example.com\r\nEND:VCARD\r\n";
+
+    #[test]
+    fn test_round_trip_keeps_multiple_values() {
+        let mut card = ContactCard::from_buf(VCARD).unwrap();
+        let template = render_card(&card).replace("Boss", "Chief");
+        let vcard    = parse_toml_into_vcard(::toml::de::from_str(&template).unwrap(), String::from("uid")).unwrap();
+        let edited   = ContactCard::from_buf(&write_component(&vcard)).unwrap();
+
+        apply_template(&mut card, &edited);
+
+        assert_eq!(card.values("TITLE"), vec!["Chief"]);
+        assert_eq!(card.values("NOTE"), vec!["first", "second"]);
+        assert_eq!(card.values("URL"), vec!["https://a.example.com", "https://b.example.com"]);
+    }
+
+}
@@ -0,0 +1,102 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+use std::process::exit;
+use std::io::Write;
+use std::path::PathBuf;
+use std::fs::OpenOptions;
+
+use libimagcontact::store::ContactStore;
+use libimagcontact::contact::Contact;
+use libimagcontact::error::ContactError as CE;
+use libimagrt::runtime::Runtime;
+use libimagerror::str::ErrFromStr;
+use libimagerror::trace::MapErrTrace;
+use libimagerror::io::ToExitCode;
+use libimagerror::exit::ExitUnwrap;
+use libimagerror::iter::TraceIterator;
+use libimagstore::iter::get::StoreIdGetIteratorExtension;
+use libimagutil::warn_result::WarnResult;
+
+/// Export contacts to one .vcf file containing all of them
+pub fn export(rt: &Runtime) {
+    let scmd   = rt.cli().subcommand_matches("export").unwrap(); // secured by main
+    let hashes = scmd
+        .values_of("export-hash")
+        .map(|v| v.map(String::from).collect::<Vec<String>>());
+
+    let mut dest : Box<Write> = match scmd.value_of("export-output").map(PathBuf::from) {
+        Some(path) => {
+            let file = OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(path)
+                .map_warn_err_str("Cannot create/open destination File. Stopping.")
+                .err_from_str()
+                .map_err(CE::from)
+                .map_err_trace_exit_unwrap(1);
+
+            Box::new(file)
+        },
+        None => Box::new(rt.stdout()),
+    };
+
+    let entries = rt
+        .store()
+        .all_contacts()
+        .map_err_trace_exit_unwrap(1)
+        .into_get_iter(rt.store())
+        .trace_unwrap_exit(1)
+        .map(|o| o.unwrap_or_else(|| {
+            error!("Failed to get entry");
+            exit(1)
+        }))
+        .filter(|entry| match hashes {
+            None             => true,
+            Some(ref hashes) => entry
+                .deser()
+                .map_err_trace_exit_unwrap(1)
+                .uid()
+                .map(|uid| hashes.iter().any(|h| uid.starts_with(h)))
+                .unwrap_or(false),
+        });
+
+    let mut count = 0;
+    for entry in entries {
+        let vcard = entry
+            .card()
+            .map_err_trace_exit_unwrap(1)
+            .to_vcard_string();
+
+        // make sure the next vcard starts on a new line
+        let vcard = if vcard.ends_with('\n') { vcard } else { vcard + "\r\n" };
+
+        dest.write_all(vcard.as_bytes())
+            .to_exit_code()
+            .unwrap_or_exit();
+
+        count += 1;
+    }
+
+    dest.flush()
+        .to_exit_code()
+        .unwrap_or_exit();
+
+    info!("Exported {} contacts", count);
+}
@@ -0,0 +1,69 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+use std::io::Write;
+
+use libimagmail::mail::Mail;
+use libimagmail::mail::linked_mails;
+use libimagrt::runtime::Runtime;
+use libimagerror::trace::MapErrTrace;
+use libimagerror::trace::trace_error;
+use libimagerror::io::ToExitCode;
+use libimagerror::exit::ExitUnwrap;
+
+use util::find_contact_by_hash;
+
+pub fn mails(rt: &Runtime) {
+    let scmd    = rt.cli().subcommand_matches("mails").unwrap(); // secured by main
+    let hash    = scmd.value_of("mails-hash").unwrap(); // secured by clap
+    let contact = find_contact_by_hash(rt, hash);
+    let out     = rt.stdout();
+    let mut outlock = out.lock();
+
+    for id in linked_mails(&contact).map_err_trace_exit_unwrap(1) {
+        let line = match rt.store().get(id.clone()).map_err_trace_exit_unwrap(1) {
+            None      => {
+                warn!("Linked mail {} does not exist", id);
+                continue;
+            },
+            Some(fle) => match Mail::from_fle(fle) {
+                Ok(mail) => {
+                    let field = |name: &str| mail
+                        .get_decoded_field(name)
+                        .map_err_trace()
+                        .ok()
+                        .and_then(|v| v)
+                        .unwrap_or_else(|| String::from("<unknown>"));
+
+                    format!("{} | {} | {} | {}",
+                            field("Date"),
+                            field("From"),
+                            field("Subject"),
+                            id.local().display())
+                },
+                Err(e) => {
+                    trace_error(&e);
+                    format!("<could not read mail> | {}", id.local().display())
+                },
+            },
+        };
+
+        let _ = writeln!(outlock, "{}", line).to_exit_code().unwrap_or_exit();
+    }
+}
@@ -41,8 +41,10 @@ extern crate handlebars;
 extern crate walkdir;
 extern crate uuid;
 extern crate serde_json;
+extern crate chrono;
 
 extern crate libimagcontact;
+extern crate libimagmail;
 extern crate libimagstore;
 #[macro_use] extern crate libimagrt;
 extern crate libimagerror;
@@ -63,6 +65,7 @@ use libimagrt::runtime::Runtime;
 use libimagrt::setup::generate_runtime_setup;
 use libimagerror::str::ErrFromStr;
 use libimagerror::trace::MapErrTrace;
+use libimagerror::trace::trace_error;
 use libimagerror::io::ToExitCode;
 use libimagerror::exit::ExitUnwrap;
 use libimagerror::iter::TraceIterator;
@@ -70,15 +73,37 @@ use libimagcontact::store::ContactStore;
 use libimagcontact::error::ContactError as CE;
 use libimagcontact::contact::Contact;
 use libimagcontact::deser::DeserVcard;
+use libimagcontact::sync::sync_vdir;
+use libimagcontact::query::query as query_contacts;
 use libimagstore::iter::get::StoreIdGetIteratorExtension;
 
+macro_rules! ask_continue {
+    { yes => $yes:expr; no => $no:expr } => {
+        if ::libimaginteraction::ask::ask_bool("Edit tempfile", Some(true)) {
+            $yes
+        } else {
+            $no
+        }
+    };
+}
+
 mod ui;
 mod util;
 mod create;
+mod edit;
+mod export;
+mod dedup;
+mod birthdays;
+mod mails;
 
 use ui::build_ui;
 use util::build_data_object_for_handlebars;
 use create::create;
+use edit::edit;
+use export::export;
+use dedup::dedup;
+use birthdays::birthdays;
+use mails::mails;
 
 fn main() {
     let version = make_imag_version!();
@@ -93,12 +118,19 @@ fn main() {
         .map(|name| {
             debug!("Call {}", name);
             match name {
-                "list"   => list(&rt),
-                "import" => import(&rt),
-                "show"   => show(&rt),
-                "find"   => find(&rt),
-                "create" => create(&rt),
-                other    => {
+                "list"      => list(&rt),
+                "import"    => import(&rt),
+                "show"      => show(&rt),
+                "find"      => find(&rt),
+                "create"    => create(&rt),
+                "edit"      => edit(&rt),
+                "export"    => export(&rt),
+                "dedup"     => dedup(&rt),
+                "sync"      => sync(&rt),
+                "birthdays" => birthdays(&rt),
+                "query"     => query(&rt),
+                "mails"     => mails(&rt),
+                other       => {
                     debug!("Unknown command");
                     let _ = rt.handle_unknown_subcommand("imag-contact", other, rt.cli())
                         .map_err_trace_exit_unwrap(1)
@@ -195,6 +227,78 @@ fn import(rt: &Runtime) {
     }
 }
 
+fn sync(rt: &Runtime) {
+    let scmd = rt.cli().subcommand_matches("sync").unwrap(); // secured by main
+    let path = scmd.value_of("sync-dir").map(PathBuf::from).unwrap(); // secured by clap
+
+    if !path.is_dir() {
+        error!("{} is not a directory", path.display());
+        exit(1)
+    }
+
+    let report = sync_vdir(rt.store(), &path).map_err_trace_exit_unwrap(1);
+
+    for &(ref file, ref e) in report.failed.iter() {
+        error!("Failed to synchronize {}", file.display());
+        trace_error(e);
+    }
+
+    for id in report.imported.iter() {
+        info!("Imported: {}", id);
+    }
+
+    for id in report.updated.iter() {
+        info!("Updated: {}", id);
+    }
+
+    for id in report.removed.iter() {
+        info!("Removed: {}", id);
+    }
+
+    let _ = writeln!(rt.stdout(),
+                     "{} imported, {} updated, {} removed, {} unchanged, {} failed",
+                     report.imported.len(),
+                     report.updated.len(),
+                     report.removed.len(),
+                     report.unchanged,
+                     report.failed.len())
+        .to_exit_code()
+        .unwrap_or_exit();
+
+    if !report.failed.is_empty() {
+        exit(1)
+    }
+}
+
+/// Query contacts, printing the results in a format a mail client understands
+fn query(rt: &Runtime) {
+    let scmd    = rt.cli().subcommand_matches("query").unwrap(); // secured by main
+    let pattern = scmd.value_of("query-string").unwrap(); // secured by clap
+    let format  = scmd.value_of("query-format").unwrap_or("mutt");
+    let results = query_contacts(rt.store(), pattern).map_err_trace_exit_unwrap(1);
+    let out     = rt.stdout();
+    let mut out = out.lock();
+
+    if format == "mutt" {
+        // mutt expects a status line before the results
+        let _ = writeln!(out, "{} results from imag", results.len())
+            .to_exit_code()
+            .unwrap_or_exit();
+    }
+
+    for result in results {
+        let line = match format {
+            "aerc" => format!("{}\t{}", result.email, result.name),
+            _      => format!("{}\t{}\t{}",
+                              result.email,
+                              result.name,
+                              result.ty.unwrap_or_else(String::new)),
+        };
+
+        let _ = writeln!(out, "{}", line).to_exit_code().unwrap_or_exit();
+    }
+}
+
 fn show(rt: &Runtime) {
     let scmd        = rt.cli().subcommand_matches("show").unwrap();
     let hash        = scmd.value_of("hash").map(String::from).unwrap(); // safed by clap
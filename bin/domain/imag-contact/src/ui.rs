@@ -58,6 +58,18 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                         .help("Import from this file/directory"))
                    )
 
+        .subcommand(SubCommand::with_name("sync")
+                   .about("Synchronize with a directory containing one vcard file per contact (vdir)")
+                   .version("0.1")
+                   .arg(Arg::with_name("sync-dir")
+                        .index(1)
+                        .takes_value(true)
+                        .required(true)
+                        .multiple(false)
+                        .value_name("DIR")
+                        .help("Import new, update changed and remove vanished contacts from this directory"))
+                   )
+
         .subcommand(SubCommand::with_name("show")
                    .about("Show contact")
                    .version("0.1")
@@ -173,4 +185,109 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                         .multiple(false)
                         .help("Don't track the new vcf file if one is created."))
                    )
+
+        .subcommand(SubCommand::with_name("edit")
+                   .about("Edit a contact. Changes are written back to the vcard file.")
+                   .version("0.1")
+                   .arg(Arg::with_name("hash")
+                        .index(1)
+                        .takes_value(true)
+                        .required(true)
+                        .multiple(false)
+                        .value_name("HASH")
+                        .help("Edit the contact pointed to by this reference hash"))
+                   )
+
+        .subcommand(SubCommand::with_name("export")
+                   .about("Export contacts to a single vcard file")
+                   .version("0.1")
+                   .arg(Arg::with_name("export-hash")
+                        .index(1)
+                        .takes_value(true)
+                        .required(false)
+                        .multiple(true)
+                        .value_name("HASH")
+                        .help("Export only the contacts pointed to by these reference hashes. All contacts are exported if not passed."))
+                   .arg(Arg::with_name("export-output")
+                        .short("o")
+                        .long("output")
+                        .takes_value(true)
+                        .required(false)
+                        .multiple(false)
+                        .value_name("PATH")
+                        .help("Write to this file, which must not exist. The vcards are dumped to stdout if this is not passed."))
+                   )
+
+        .subcommand(SubCommand::with_name("dedup")
+                   .about("Find duplicated contacts and merge them, after asking")
+                   .version("0.1")
+                   .arg(Arg::with_name("dedup-remove-files")
+                        .long("remove-files")
+                        .takes_value(false)
+                        .required(false)
+                        .multiple(false)
+                        .help("Remove the vcard files of contacts which were merged into other contacts"))
+                   )
+
+        .subcommand(SubCommand::with_name("birthdays")
+                   .about("List upcoming birthdays and anniversaries")
+                   .version("0.1")
+                   .arg(Arg::with_name("birthdays-days")
+                        .short("d")
+                        .long("days")
+                        .takes_value(true)
+                        .required(false)
+                        .multiple(false)
+                        .value_name("DAYS")
+                        .help("List the birthdays and anniversaries in the next DAYS days (default: 30)"))
+                   .arg(Arg::with_name("birthdays-from")
+                        .short("f")
+                        .long("from")
+                        .takes_value(true)
+                        .required(false)
+                        .multiple(false)
+                        .value_name("DATE")
+                        .help("Start listing at DATE (default: today)"))
+                   .arg(Arg::with_name("birthdays-ics")
+                        .long("ics")
+                        .takes_value(false)
+                        .required(false)
+                        .multiple(false)
+                        .conflicts_with("birthdays-days")
+                        .conflicts_with("birthdays-from")
+                        .help("Print all birthdays and anniversaries as yearly recurring events in the iCalendar format"))
+                   )
+
+        .subcommand(SubCommand::with_name("query")
+                   .about("Query contacts by name, nickname or email address, for mail clients")
+                   .version("0.1")
+                   .arg(Arg::with_name("query-string")
+                        .index(1)
+                        .takes_value(true)
+                        .required(true)
+                        .multiple(false)
+                        .value_name("QUERY")
+                        .help("Fuzzy match this against names, nicknames and email addresses"))
+                   .arg(Arg::with_name("query-format")
+                        .long("format")
+                        .short("f")
+                        .takes_value(true)
+                        .required(false)
+                        .multiple(false)
+                        .value_name("FORMAT")
+                        .possible_values(&["mutt", "aerc", "khard"])
+                        .help("Output format: 'mutt' for the (neo)mutt query_command, 'aerc' for the aerc address-book-cmd, 'khard' like 'khard email --parsable' (default: mutt)"))
+                   )
+
+        .subcommand(SubCommand::with_name("mails")
+                   .about("List all mails exchanged with a contact (mails imported with 'imag-mail import-mail --link-contacts')")
+                   .version("0.1")
+                   .arg(Arg::with_name("mails-hash")
+                        .index(1)
+                        .takes_value(true)
+                        .required(true)
+                        .multiple(false)
+                        .value_name("HASH")
+                        .help("The contact to list the mails of (UID or a prefix of it)"))
+                   )
 }
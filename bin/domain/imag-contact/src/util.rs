@@ -18,7 +18,15 @@
 //
 
 use std::collections::BTreeMap;
+use std::process::exit;
 
+use libimagrt::runtime::Runtime;
+use libimagstore::store::FileLockEntry;
+use libimagstore::iter::get::StoreIdGetIteratorExtension;
+use libimagerror::trace::MapErrTrace;
+use libimagerror::iter::TraceIterator;
+use libimagcontact::store::ContactStore;
+use libimagcontact::contact::Contact;
 use libimagcontact::deser::DeserVcard;
 
 pub fn build_data_object_for_handlebars<'a>(i: usize, vcard: &DeserVcard) -> BTreeMap<&'static str, String> {
@@ -75,3 +83,38 @@ pub fn build_data_object_for_handlebars<'a>(i: usize, vcard: &DeserVcard) -> BTr
     data
 }
 
+/// Find the contact whose UID starts with `hash`
+///
+/// Exits if there is no such contact or if `hash` is ambiguous.
+pub fn find_contact_by_hash<'a>(rt: &'a Runtime, hash: &str) -> FileLockEntry<'a> {
+    let mut found = rt
+        .store()
+        .all_contacts()
+        .map_err_trace_exit_unwrap(1)
+        .into_get_iter(rt.store())
+        .trace_unwrap_exit(1)
+        .map(|o| o.unwrap_or_else(|| {
+            error!("Failed to get entry");
+            exit(1)
+        }))
+        .filter(|entry| {
+            entry.deser()
+                .map_err_trace_exit_unwrap(1)
+                .uid()
+                .map(|uid| uid.starts_with(hash))
+                .unwrap_or(false)
+        })
+        .collect::<Vec<_>>();
+
+    match found.len() {
+        0 => {
+            error!("No contact found for '{}'", hash);
+            exit(1)
+        },
+        1 => found.pop().unwrap(), // checked above
+        n => {
+            error!("'{}' is ambiguous, {} contacts found", hash, n);
+            exit(1)
+        },
+    }
+}
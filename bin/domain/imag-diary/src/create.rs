@@ -23,9 +23,11 @@ use libimagdiary::diary::Diary;
 use libimagdiary::diaryid::DiaryId;
 use libimagdiary::error::DiaryErrorKind as DEK;
 use libimagdiary::error::ResultExt;
+use libimagdiary::template::DiaryTemplate;
 use libimagentryedit::edit::Edit;
 use libimagrt::runtime::Runtime;
 use libimagerror::trace::MapErrTrace;
+use libimaginteraction::ask::ask_string;
 use libimagutil::warn_exit::warn_exit;
 use libimagstore::store::FileLockEntry;
 use libimagstore::store::Store;
@@ -40,6 +42,12 @@ pub fn create(rt: &Runtime) {
 
     let mut entry = create_entry(rt.store(), &diaryname, rt);
 
+    if rt.cli().subcommand_matches("create").unwrap().is_present("no-template") {
+        debug!("Not using template for new diary entry");
+    } else {
+        apply_template(rt, &diaryname, &mut entry);
+    }
+
     let res = if rt.cli().subcommand_matches("create").unwrap().is_present("no-edit") {
         debug!("Not editing new diary entry");
         Ok(())
@@ -53,6 +61,29 @@ pub fn create(rt: &Runtime) {
     info!("Ok!");
 }
 
+fn apply_template<'a>(rt: &'a Runtime, diaryname: &str, entry: &mut FileLockEntry<'a>) {
+    if !entry.get_content().is_empty() {
+        debug!("Entry has content already, not using template");
+        return;
+    }
+
+    let template = match DiaryTemplate::from_config(rt, diaryname).map_err_trace_exit_unwrap(1) {
+        Some(t) => t,
+        None    => {
+            debug!("No template configured for diary '{}'", diaryname);
+            return;
+        }
+    };
+
+    let answers = template
+        .prompts()
+        .iter()
+        .map(|q| String::from(ask_string(q, None, true, false, None, "> ").trim()))
+        .collect();
+
+    let _ = template.prefill(rt.store(), entry, answers).map_err_trace_exit_unwrap(1);
+}
+
 fn create_entry<'a>(diary: &'a Store, diaryname: &str, rt: &Runtime) -> FileLockEntry<'a> {
     use util::parse_timed_string;
 
@@ -0,0 +1,85 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::exit;
+
+use chrono::naive::NaiveDate;
+
+use libimagdiary::export::collect_entries;
+use libimagdiary::export::to_html_directory;
+use libimagdiary::export::to_html_document;
+use libimagdiary::export::to_markdown;
+use libimagrt::runtime::Runtime;
+use libimagerror::trace::MapErrTrace;
+use libimagerror::io::ToExitCode;
+use libimagerror::exit::ExitUnwrap;
+use libimagutil::date::date_from_string;
+use libimagutil::warn_exit::warn_exit;
+
+use util::get_diary_name;
+
+pub fn export(rt: &Runtime) {
+    let diaryname = get_diary_name(rt)
+        .unwrap_or_else(|| warn_exit("No diary selected. Use either the configuration file or the commandline option", 1));
+    let scmd      = rt.cli().subcommand_matches("export").unwrap();
+
+    let parse_date = |name: &str| -> Option<NaiveDate> {
+        scmd.value_of(name).map(|s| date_from_string(s).unwrap_or_else(|e| {
+            error!("Cannot parse '{}' as date: {}", s, e);
+            exit(1)
+        }))
+    };
+
+    let from    = parse_date("export-from");
+    let until   = parse_date("export-until");
+    let output  = scmd.value_of("export-output").map(PathBuf::from);
+    let entries = collect_entries(rt.store(), &diaryname, from, until).map_err_trace_exit_unwrap(1);
+
+    let document = match scmd.value_of("export-format").unwrap() { // default value
+        "markdown" => to_markdown(&diaryname, &entries),
+        "html"     => to_html_document(&diaryname, &entries).map_err_trace_exit_unwrap(1),
+        "html-dir" => {
+            let dir = output.unwrap_or_else(|| warn_exit("Exporting to a HTML directory needs --output", 1));
+            let _   = to_html_directory(&diaryname, &entries, &dir).map_err_trace_exit_unwrap(1);
+            info!("Exported {} entries to {}", entries.len(), dir.display());
+            return;
+        },
+        other      => unreachable!("Unknown export format: {}", other), // clap ensures possible values
+    };
+
+    match output {
+        None       => {
+            let _ = write!(rt.stdout(), "{}", document).to_exit_code().unwrap_or_exit();
+        },
+        Some(path) => {
+            let _ = OpenOptions::new()
+                .write(true)
+                .create(true)
+                .truncate(true)
+                .open(&path)
+                .and_then(|mut f| f.write_all(document.as_bytes()))
+                .to_exit_code()
+                .unwrap_or_exit();
+            info!("Exported {} entries to {}", entries.len(), path.display());
+        },
+    }
+}
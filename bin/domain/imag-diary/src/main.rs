@@ -38,6 +38,7 @@ extern crate chrono;
 extern crate toml;
 extern crate toml_query;
 extern crate itertools;
+extern crate prettytable;
 
 extern crate libimagdiary;
 extern crate libimagentryedit;
@@ -59,14 +60,20 @@ use itertools::Itertools;
 
 mod create;
 mod delete;
+mod export;
 mod list;
+mod onthisday;
+mod stats;
 mod ui;
 mod util;
 mod view;
 
 use create::create;
 use delete::delete;
+use export::export;
 use list::list;
+use onthisday::onthisday;
+use stats::stats;
 use view::view;
 
 fn main() {
@@ -84,7 +91,10 @@ fn main() {
                 "diaries" => diaries(&rt),
                 "create" => create(&rt),
                 "delete" => delete(&rt),
+                "export" => export(&rt),
                 "list" => list(&rt),
+                "onthisday" => onthisday(&rt),
+                "stats" => stats(&rt),
                 "view" => view(&rt),
                 other    => {
                     debug!("Unknown command");
@@ -0,0 +1,93 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+use std::io::Write;
+use std::process::exit;
+
+use chrono::Datelike;
+use chrono::naive::NaiveDateTime;
+use toml_query::read::TomlValueReadTypeExt;
+
+use libimagdiary::diary::Diary;
+use libimagrt::runtime::Runtime;
+use libimagerror::trace::MapErrTrace;
+use libimagerror::io::ToExitCode;
+use libimagerror::exit::ExitUnwrap;
+use libimagstore::storeid::IntoStoreId;
+use libimagutil::date::date_from_string;
+
+pub fn onthisday(rt: &Runtime) {
+    let scmd = rt.cli().subcommand_matches("onthisday").unwrap();
+
+    let date = match scmd.value_of("onthisday-date") {
+        Some(s) => date_from_string(s).unwrap_or_else(|e| {
+            error!("Cannot parse '{}' as date: {}", s, e);
+            exit(1)
+        }),
+        None    => ::chrono::offset::Local::today().naive_local(),
+    };
+
+    let without_logs = scmd.is_present("onthisday-without-logs");
+    let out          = rt.stdout();
+    let mut outlock  = out.lock();
+    let mut year     = None;
+
+    for id in rt.store().entries_on_this_day(&date).map_err_trace_exit_unwrap(1) {
+        let sid   = id.clone().into_storeid().map_err_trace_exit_unwrap(1);
+        let entry = match rt.store().get(sid).map_err_trace_exit_unwrap(1) {
+            Some(entry) => entry,
+            None        => {
+                warn!("Entry {} vanished", id);
+                continue
+            },
+        };
+
+        // imag-log entries are diary entries, flagged in the header
+        let is_log = entry
+            .get_header()
+            .read_bool("log.is_log")
+            .map_err_trace_exit_unwrap(1)
+            .unwrap_or(false);
+
+        if is_log && without_logs {
+            continue;
+        }
+
+        if year != Some(id.year()) {
+            let ago = date.year() - id.year();
+            let _   = writeln!(outlock,
+                               "# {} ({} year{} ago)\n",
+                               id.year(),
+                               ago,
+                               if ago == 1 { "" } else { "s" })
+                .to_exit_code()
+                .unwrap_or_exit();
+            year = Some(id.year());
+        }
+
+        let dt : NaiveDateTime = id.clone().into();
+        let res = if is_log {
+            writeln!(outlock, "{} [{}]: {}\n", dt.format("%H:%M:%S"), id.diary_name(), entry.get_content().trim())
+        } else {
+            writeln!(outlock, "## {} {}\n\n{}\n", id.diary_name(), dt.format("%H:%M:%S"), entry.get_content().trim())
+        };
+
+        let _ = res.to_exit_code().unwrap_or_exit();
+    }
+}
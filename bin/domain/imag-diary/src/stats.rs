@@ -0,0 +1,157 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+use std::io::Write;
+use std::process::exit;
+
+use chrono::Datelike;
+use chrono::Duration;
+use chrono::naive::NaiveDate;
+use prettytable::Table;
+use prettytable::row::Row;
+use prettytable::cell::Cell;
+
+use libimagdiary::stats::DateRange;
+use libimagdiary::stats::DiaryStats;
+use libimagrt::runtime::Runtime;
+use libimagerror::trace::MapErrTrace;
+use libimagerror::io::ToExitCode;
+use libimagerror::exit::ExitUnwrap;
+use libimagutil::warn_exit::warn_exit;
+
+use util::get_diary_name;
+
+pub fn stats(rt: &Runtime) {
+    let diaryname = get_diary_name(rt)
+        .unwrap_or_else(|| warn_exit("No diary selected. Use either the configuration file or the commandline option", 1));
+    let scmd      = rt.cli().subcommand_matches("stats").unwrap();
+
+    let min_gap = scmd
+        .value_of("stats-gaps")
+        .map(|d| d.parse::<i64>().unwrap_or_else(|_| {
+            error!("Cannot parse '{}' as number of days", d);
+            exit(1)
+        }))
+        .unwrap_or(7);
+
+    let today = ::chrono::offset::Local::today().naive_local();
+    let stats = DiaryStats::compute(rt.store(), &diaryname).map_err_trace_exit_unwrap(1);
+    let out   = rt.stdout();
+    let mut outlock = out.lock();
+
+    let range = match stats.range() {
+        Some(range) => range,
+        None        => {
+            info!("No entries in diary '{}'", diaryname);
+            return;
+        }
+    };
+
+    let fmt_range = |r: &DateRange| format!("{} days, {} - {}", r.days(), r.start, r.end);
+
+    let _ = writeln!(outlock, "Diary:          {}", diaryname)
+        .and_then(|_| writeln!(outlock, "Entries:        {}", stats.entries()))
+        .and_then(|_| writeln!(outlock, "Words:          {} ({} per entry)", stats.words(), stats.words() / stats.entries()))
+        .and_then(|_| writeln!(outlock, "Days written:   {} of {}", stats.per_day().len(), range.days()))
+        .and_then(|_| writeln!(outlock, "First entry:    {}", range.start))
+        .and_then(|_| writeln!(outlock, "Last entry:     {}", range.end))
+        .and_then(|_| match stats.longest_streak() {
+            Some(s) => writeln!(outlock, "Longest streak: {}", fmt_range(&s)),
+            None    => Ok(()),
+        })
+        .and_then(|_| match stats.current_streak(&today) {
+            Some(s) => writeln!(outlock, "Current streak: {}", fmt_range(&s)),
+            None    => writeln!(outlock, "Current streak: none"),
+        })
+        .to_exit_code()
+        .unwrap_or_exit();
+
+    let gaps = stats.gaps(min_gap);
+    if !gaps.is_empty() {
+        let _ = writeln!(outlock, "\nGaps of {} days or more:", min_gap).to_exit_code().unwrap_or_exit();
+        for gap in gaps {
+            let _ = writeln!(outlock, "  {}", fmt_range(&gap)).to_exit_code().unwrap_or_exit();
+        }
+    }
+
+    let _ = writeln!(outlock).to_exit_code().unwrap_or_exit();
+
+    let mut table = Table::new();
+    table.set_titles(Row::new(["Month", "Entries", "Words"]
+                              .into_iter()
+                              .map(|s| Cell::new(s))
+                              .collect()));
+
+    for (&(year, month), month_stats) in stats.per_month() {
+        let cells : Vec<Cell> = vec![
+            format!("{}-{:02}", year, month),
+            format!("{}", month_stats.entries),
+            format!("{}", month_stats.words),
+        ]
+        .into_iter()
+        .map(|s| Cell::new(&s))
+        .collect();
+
+        table.add_row(Row::new(cells));
+    }
+
+    let _ = table.print(&mut outlock).to_exit_code().unwrap_or_exit();
+
+    if !scmd.is_present("stats-no-heatmap") {
+        let _ = writeln!(outlock).to_exit_code().unwrap_or_exit();
+        print_heatmap(&mut outlock, &stats, &range);
+    }
+}
+
+/// Print one line per month, with one character per day, showing how much was written on that
+/// day, relative to the day with the most words
+fn print_heatmap<W: Write>(out: &mut W, stats: &DiaryStats, range: &DateRange) {
+    let max = stats.per_day().values().cloned().max().unwrap_or(0);
+
+    let level = |date: &NaiveDate| -> char {
+        match stats.per_day().get(date) {
+            None                          => '.',
+            Some(&w) if w * 3 <= max      => '-',
+            Some(&w) if w * 3 <= max * 2  => '+',
+            Some(_)                       => '#',
+        }
+    };
+
+    let _ = writeln!(out, "         1        10        20        30")
+        .to_exit_code()
+        .unwrap_or_exit();
+
+    let mut month_start = NaiveDate::from_ymd(range.start.year(), range.start.month(), 1);
+    while month_start <= range.end {
+        let mut line = format!("{}-{:02}  ", month_start.year(), month_start.month());
+        let mut day  = month_start;
+
+        while day.month() == month_start.month() {
+            line.push(level(&day));
+            day = day + Duration::days(1);
+        }
+
+        let _ = writeln!(out, "{}", line).to_exit_code().unwrap_or_exit();
+        month_start = day;
+    }
+
+    let _ = writeln!(out, "\n'.': no entry, '-', '+', '#': up to a third, two thirds, all of the most words per day ({})", max)
+        .to_exit_code()
+        .unwrap_or_exit();
+}
@@ -41,6 +41,11 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                         .takes_value(false)
                         .required(false)
                         .help("Do not edit after creating"))
+                   .arg(Arg::with_name("no-template")
+                        .long("no-template")
+                        .takes_value(false)
+                        .required(false)
+                        .help("Do not use the configured template and prompts for the new entry"))
 
                    .arg(Arg::with_name("timed")
                         .long("timed")
@@ -108,6 +113,24 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                         .help("Do not ask for confirmation."))
                 )
 
+        .subcommand(SubCommand::with_name("stats")
+                   .about("Show statistics about the diary: word counts, entries per month, streaks and gaps")
+                   .version("0.1")
+
+                   .arg(Arg::with_name("stats-gaps")
+                        .long("gaps")
+                        .takes_value(true)
+                        .required(false)
+                        .value_name("DAYS")
+                        .help("Show gaps of at least this many days without entry (default: 7)"))
+
+                   .arg(Arg::with_name("stats-no-heatmap")
+                        .long("no-heatmap")
+                        .takes_value(false)
+                        .required(false)
+                        .help("Do not print the calendar heatmap"))
+                )
+
         .subcommand(SubCommand::with_name("view")
                    .about("View entries, currently only supports plain viewing")
                    .version("0.1")
@@ -119,5 +142,60 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                         .help("Show the header when printing the entries"))
                 )
 
+        .subcommand(SubCommand::with_name("onthisday")
+                   .about("Show the entries of all diaries and logs written on this day in previous years")
+                   .version("0.1")
+
+                   .arg(Arg::with_name("onthisday-date")
+                        .long("date")
+                        .short("d")
+                        .takes_value(true)
+                        .required(false)
+                        .value_name("DATE")
+                        .help("Use this date instead of today. Format: YYYY-MM-DD"))
+
+                   .arg(Arg::with_name("onthisday-without-logs")
+                        .long("without-logs")
+                        .takes_value(false)
+                        .required(false)
+                        .help("Do not show imag-log entries"))
+                )
+
+        .subcommand(SubCommand::with_name("export")
+                   .about("Export the diary as Markdown or HTML")
+                   .version("0.1")
+
+                   .arg(Arg::with_name("export-format")
+                        .long("format")
+                        .short("f")
+                        .takes_value(true)
+                        .required(false)
+                        .possible_values(&["markdown", "html", "html-dir"])
+                        .default_value("markdown")
+                        .help("Export as one Markdown document, one HTML document or a directory of HTML pages"))
+
+                   .arg(Arg::with_name("export-output")
+                        .long("output")
+                        .short("o")
+                        .takes_value(true)
+                        .required(false)
+                        .value_name("PATH")
+                        .help("Write to this file (or directory, for 'html-dir') instead of stdout"))
+
+                   .arg(Arg::with_name("export-from")
+                        .long("from")
+                        .takes_value(true)
+                        .required(false)
+                        .value_name("DATE")
+                        .help("Export only entries from this date on. Format: YYYY-MM-DD"))
+
+                   .arg(Arg::with_name("export-until")
+                        .long("until")
+                        .takes_value(true)
+                        .required(false)
+                        .value_name("DATE")
+                        .help("Export only entries until this date (inclusive). Format: YYYY-MM-DD"))
+                )
+
 }
 
@@ -50,6 +50,8 @@ extern crate libimaginteraction;
 use std::io::Write;
 use std::process::exit;
 
+use chrono::NaiveDate;
+
 use prettytable::Table;
 use prettytable::cell::Cell;
 use prettytable::row::Row;
@@ -63,6 +65,7 @@ use libimagerror::io::ToExitCode;
 use libimaghabit::store::HabitStore;
 use libimaghabit::habit::builder::HabitBuilder;
 use libimaghabit::habit::HabitTemplate;
+use libimaghabit::stats::HabitStatistics;
 use libimagstore::store::FileLockEntry;
 use libimagstore::store::Store;
 use libimagstore::storeid::StoreId;
@@ -84,14 +87,16 @@ fn main() {
         .map(|name| {
             debug!("Call {}", name);
             match name {
-                "create" => create(&rt),
-                "delete" => delete(&rt),
-                "list"   => list(&rt),
-                "today"  => today(&rt, false),
-                "status" => today(&rt, true),
-                "show"   => show(&rt),
-                "done"   => done(&rt),
-                other    => {
+                "create"   => create(&rt),
+                "delete"   => delete(&rt),
+                "list"     => list(&rt),
+                "today"    => today(&rt, false),
+                "status"   => today(&rt, true),
+                "show"     => show(&rt),
+                "done"     => done(&rt),
+                "stats"    => stats(&rt),
+                "quantify" => quantify(&rt),
+                other      => {
                     debug!("Unknown command");
                     let _ = rt.handle_unknown_subcommand("imag-habit", other, rt.cli())
                         .map_err_trace_exit_unwrap(1)
@@ -151,6 +156,18 @@ fn create(rt: &Runtime) {
         hb
     };
 
+    let hb = if let Some(target) = scmd.value_of("create-target") {
+        hb.with_target(parse_amount(target))
+    } else {
+        hb
+    };
+
+    let hb = if let Some(unit) = scmd.value_of("create-unit") {
+        hb.with_unit(String::from(unit))
+    } else {
+        hb
+    };
+
     debug!("Builder = {:?}", hb);
 
     hb.build(rt.store()).map_err_trace_exit_unwrap(1);
@@ -319,22 +336,37 @@ fn today(rt: &Runtime, future: bool) {
             }
         }
     } else {
-        fn lister_fn(h: &FileLockEntry) -> Vec<String> {
+        fn lister_fn(store: &Store, h: &FileLockEntry) -> Vec<String> {
             debug!("Listing: {:?}", h);
             let name     = h.habit_name().map_err_trace_exit_unwrap(1);
             let basedate = h.habit_basedate().map_err_trace_exit_unwrap(1);
             let recur    = h.habit_recur_spec().map_err_trace_exit_unwrap(1);
-            let due      = h.next_instance_date().map_err_trace_exit_unwrap(1)
+            let due_date = h.next_instance_date().map_err_trace_exit_unwrap(1);
+            let due      = due_date
                 .map(date_to_string_helper)
                 .unwrap_or_else(|| String::from("<finished>"));
             let comm     = h.habit_comment().map_err_trace_exit_unwrap(1);
+            let progress = match (h.habit_target().map_err_trace_exit_unwrap(1), due_date) {
+                (Some(target), Some(due_date)) => {
+                    let done : f64 = h
+                        .instance_amounts(store)
+                        .map_err_trace_exit_unwrap(1)
+                        .into_iter()
+                        .filter(|&(date, _)| date == due_date)
+                        .map(|(_, amount)| amount.unwrap_or(target))
+                        .sum();
+                    let unit = h.habit_unit().map_err_trace_exit_unwrap(1);
+                    format_amount_of_target(done, target, unit)
+                },
+                _ => String::new(),
+            };
 
-            let v = vec![name, basedate, recur, due, comm];
+            let v = vec![name, basedate, recur, due, progress, comm];
             debug!(" -> {:?}", v);
             v
         }
 
-        let header = ["#", "Name", "Basedate", "Recurr", "Next Due", "Comment"]
+        let header = ["#", "Name", "Basedate", "Recurr", "Next Due", "Progress", "Comment"]
             .iter()
             .map(|s| Cell::new(s))
             .collect::<Vec<Cell>>();
@@ -359,7 +391,7 @@ fn today(rt: &Runtime, future: bool) {
             .enumerate()
         {
             let mut v = vec![format!("{}", i)];
-            let mut list = lister_fn(&e);
+            let mut list = lister_fn(rt.store(), &e);
             v.append(&mut list);
             table.add_row(v.iter().map(|s| Cell::new(s)).collect());
             empty = false;
@@ -504,6 +536,7 @@ fn show(rt: &Runtime) {
 fn done(rt: &Runtime) {
     let scmd = rt.cli().subcommand_matches("done").unwrap(); // safe by call from main()
     let names : Vec<_> = scmd.values_of("done-name").unwrap().map(String::from).collect();
+    let amount = scmd.value_of("done-amount").map(parse_amount);
 
     let today = ::chrono::offset::Local::today().naive_local();
 
@@ -533,13 +566,38 @@ fn done(rt: &Runtime) {
         let next_instance_name = r.habit_name().map_err_trace_exit_unwrap(1);
         let next_instance_date = r.next_instance_date().map_err_trace_exit_unwrap(1);
         if let Some(next) = next_instance_date {
-            debug!("Creating new instance on {:?}", next);
-            r.create_instance_with_date(rt.store(), &next)
-                .map_err_trace_exit_unwrap(1);
+            if let Some(amount) = amount {
+                use libimaghabit::instance::HabitInstance;
 
-            info!("Done on {date}: {name}",
-                  date = libimagutil::date::date_to_string(&next),
-                  name = next_instance_name);
+                debug!("Adding {} to instance on {:?}", amount, next);
+                let target   = r.habit_target().map_err_trace_exit_unwrap(1);
+                let unit     = r.habit_unit().map_err_trace_exit_unwrap(1);
+                let mut inst = r.retrieve_instance_with_date(rt.store(), &next)
+                    .map_err_trace_exit_unwrap(1);
+                let sum      = inst.get_amount().map_err_trace_exit_unwrap(1).unwrap_or(0.0) + amount;
+                inst.set_amount(sum).map_err_trace_exit_unwrap(1);
+
+                let progress = match target {
+                    Some(target) => format_amount_of_target(sum, target, unit),
+                    None         => {
+                        warn!("Habit {} has no target amount", next_instance_name);
+                        format!("{}", sum)
+                    },
+                };
+
+                info!("Done on {date}: {name} ({progress})",
+                      date     = libimagutil::date::date_to_string(&next),
+                      name     = next_instance_name,
+                      progress = progress);
+            } else {
+                debug!("Creating new instance on {:?}", next);
+                r.create_instance_with_date(rt.store(), &next)
+                    .map_err_trace_exit_unwrap(1);
+
+                info!("Done on {date}: {name}",
+                      date = libimagutil::date::date_to_string(&next),
+                      name = next_instance_name);
+            }
         } else {
             info!("Ignoring: {}, because there is no due date (the habit is finised)",
                 next_instance_name);
@@ -549,6 +607,165 @@ fn done(rt: &Runtime) {
     info!("Done.");
 }
 
+fn stats(rt: &Runtime) {
+    use chrono::Duration;
+    use libimagutil::date::date_from_string;
+
+    let scmd  = rt.cli().subcommand_matches("stats").unwrap(); // safe by call from main()
+    let names : Option<Vec<_>> = scmd.values_of("stats-name").map(|v| v.map(String::from).collect());
+    let today = ::chrono::offset::Local::today().naive_local();
+
+    let weeks = scmd
+        .value_of("stats-weeks")
+        .map(|w| w.parse::<i64>().unwrap_or_else(|_| {
+            error!("Cannot parse '{}' as number of weeks", w);
+            exit(1)
+        }))
+        .unwrap_or(12);
+
+    let window_start = match scmd.value_of("stats-from") {
+        Some(s) => date_from_string(s).map_err_trace_exit_unwrap(1),
+        None    => today - Duration::weeks(weeks),
+    };
+
+    let _ = rt
+        .store()
+        .all_habit_templates()
+        .map_err_trace_exit_unwrap(1)
+        .trace_unwrap_exit(1)
+        .filter_map(|id| get_from_store(rt.store(), id))
+        .filter(|h| {
+            let name = h.habit_name().map_err_trace_exit_unwrap(1);
+            names.as_ref().map(|names| names.contains(&name)).unwrap_or(true)
+        })
+        .for_each(|habit| {
+            let name     = habit.habit_name().map_err_trace_exit_unwrap(1);
+            let basedate = date_from_string(habit.habit_basedate().map_err_trace_exit_unwrap(1))
+                .map_err_trace_exit_unwrap(1);
+
+            // Streaks are computed over the whole history of the habit, the completion rate and
+            // the heatmap only over the window
+            let all    = habit.statistics(rt.store(), &basedate, &today).map_err_trace_exit_unwrap(1);
+            let window = habit.statistics(rt.store(), &window_start, &today).map_err_trace_exit_unwrap(1);
+
+            let rate = window
+                .completion_rate()
+                .map(|r| format!("{:.0}%", r * 100.0))
+                .unwrap_or_else(|| String::from("-"));
+
+            let missed = window
+                .missed()
+                .iter()
+                .map(date_to_string_helper_ref)
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            let quantity = match window.target() {
+                None         => String::new(),
+                Some(target) => {
+                    let unit    = habit.habit_unit().map_err_trace_exit_unwrap(1);
+                    let partial = window
+                        .partial()
+                        .into_iter()
+                        .map(|o| format!("{} ({})",
+                                         date_to_string_helper(o.date),
+                                         format_amount_of_target(o.amount, target, unit.clone())))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+
+                    format!("Total          : {total} {unit}\nPartially done : {partial}\n",
+                            total   = window.total(),
+                            unit    = unit.unwrap_or_else(String::new),
+                            partial = if partial.is_empty() { String::from("-") } else { partial })
+                },
+            };
+
+            let mut out = rt.stdout();
+            let _ = writeln!(out,
+                             "{name}\nCurrent streak : {cur}\nLongest streak : {long}\nCompletion     : {rate} (since {since})\nMissed         : {missed}\n{quantity}",
+                             name     = name,
+                             cur      = all.current_streak(),
+                             long     = all.longest_streak(),
+                             rate     = rate,
+                             since    = date_to_string_helper(window_start),
+                             missed   = if missed.is_empty() { String::from("-") } else { missed },
+                             quantity = quantity)
+                .and_then(|_| writeln!(out, "{}", heatmap(&window, window_start, today)))
+                .to_exit_code()
+                .unwrap_or_exit();
+        });
+}
+
+/// Render a calendar heatmap of the habit, one column per week, one row per weekday
+///
+/// Legend: '#' done, '+' partially done, '-' missed, '.' not due, ' ' not in range
+fn heatmap(stats: &HabitStatistics, start: NaiveDate, end: NaiveDate) -> String {
+    use chrono::Datelike;
+    use chrono::Duration;
+
+    let first  = start - Duration::days(start.weekday().num_days_from_monday() as i64);
+    let weeks  = (end.signed_duration_since(first).num_days() / 7) + 1;
+    let missed = stats.missed();
+
+    let mut s = String::new();
+    for (row, dayname) in ["Mo", "Tu", "We", "Th", "Fr", "Sa", "Su"].iter().enumerate() {
+        s.push_str(dayname);
+        s.push(' ');
+
+        for week in 0..weeks {
+            let date       = first + Duration::days(week * 7 + row as i64);
+            let occurrence = stats.occurrences().iter().find(|o| o.date == date);
+            let c = if date < start || date > end {
+                ' '
+            } else if occurrence.map(|o| o.fulfilled).unwrap_or(false) {
+                '#'
+            } else if occurrence.map(|o| o.amount > 0.0).unwrap_or(false) {
+                '+'
+            } else if missed.contains(&date) {
+                '-'
+            } else if stats.done().contains(&date) {
+                '#'
+            } else {
+                '.'
+            };
+            s.push(c);
+        }
+
+        s.push('\n');
+    }
+
+    s
+}
+
+fn quantify(rt: &Runtime) {
+    let scmd   = rt.cli().subcommand_matches("quantify").unwrap();                  // safe by call from main()
+    let name   = scmd.value_of("quantify-name").map(String::from).unwrap();         // safe by clap
+    let target = parse_amount(scmd.value_of("quantify-target").unwrap());           // safe by clap
+    let unit   = scmd.value_of("quantify-unit").map(String::from);
+
+    let mut found = false;
+    for mut habit in rt
+        .store()
+        .all_habit_templates()
+        .map_err_trace_exit_unwrap(1)
+        .trace_unwrap_exit(1)
+        .filter_map(|id| get_from_store(rt.store(), id))
+        .filter(|h| h.habit_name().map(|n| name == n).map_err_trace_exit_unwrap(1))
+    {
+        let migrated = habit
+            .set_habit_target(rt.store(), target, unit.clone())
+            .map_err_trace_exit_unwrap(1);
+
+        found = true;
+        info!("Set target of {} to {}, migrated {} existing instances", name, target, migrated);
+    }
+
+    if !found {
+        error!("No habit named '{}' found", name);
+        exit(1)
+    }
+}
+
 /// Helper function for `Iterator::filter_map()`ing `all_habit_templates()` and `Store::get` them.
 fn get_from_store<'a>(store: &'a Store, id: StoreId) -> Option<FileLockEntry<'a>> {
     match store.get(id.clone()) {
@@ -564,7 +781,25 @@ fn get_from_store<'a>(store: &'a Store, id: StoreId) -> Option<FileLockEntry<'a>
     }
 }
 
+fn parse_amount(s: &str) -> f64 {
+    s.parse::<f64>().unwrap_or_else(|_| {
+        error!("Cannot parse '{}' as amount", s);
+        exit(1)
+    })
+}
+
+fn format_amount_of_target(amount: f64, target: f64, unit: Option<String>) -> String {
+    match unit {
+        Some(unit) => format!("{}/{} {}", amount, target, unit),
+        None       => format!("{}/{}", amount, target),
+    }
+}
+
 fn date_to_string_helper(d: chrono::NaiveDate) -> String {
     libimagutil::date::date_to_string(&d)
 }
 
+fn date_to_string_helper_ref(d: &chrono::NaiveDate) -> String {
+    libimagutil::date::date_to_string(d)
+}
+
@@ -77,6 +77,24 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                         .takes_value(true)
                         .value_name("COMMENT")
                         .help("Comment for the habit"))
+
+                   .arg(Arg::with_name("create-target")
+                        .long("target")
+                        .short("t")
+                        .multiple(false)
+                        .required(false)
+                        .takes_value(true)
+                        .value_name("AMOUNT")
+                        .help("Amount which should be done each time, e.g. '5' for 'run 5 km'"))
+
+                   .arg(Arg::with_name("create-unit")
+                        .long("unit")
+                        .multiple(false)
+                        .required(false)
+                        .takes_value(true)
+                        .value_name("UNIT")
+                        .requires("create-target")
+                        .help("Unit of the target amount, e.g. 'km'"))
                    )
 
         .subcommand(SubCommand::with_name("delete")
@@ -155,6 +173,35 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                         .help("Show entries which are done."))
                    )
 
+        .subcommand(SubCommand::with_name("stats")
+                   .about("Show streaks, completion rate and a calendar of habits")
+                   .version("0.1")
+                   .arg(Arg::with_name("stats-name")
+                        .index(1)
+                        .multiple(true)
+                        .required(false)
+                        .takes_value(true)
+                        .value_name("NAME")
+                        .help("Names of the habits to show statistics for (default: all)"))
+                   .arg(Arg::with_name("stats-weeks")
+                        .long("weeks")
+                        .short("w")
+                        .multiple(false)
+                        .required(false)
+                        .takes_value(true)
+                        .value_name("N")
+                        .help("Compute the completion rate and show the calendar for the last N weeks. Default = 12"))
+                   .arg(Arg::with_name("stats-from")
+                        .long("from")
+                        .short("f")
+                        .multiple(false)
+                        .required(false)
+                        .takes_value(true)
+                        .value_name("DATE")
+                        .conflicts_with("stats-weeks")
+                        .help("Compute the completion rate and show the calendar from this date on (YYYY-MM-DD)"))
+                   )
+
         .subcommand(SubCommand::with_name("done")
                     .about("Mark one or more habits (which are pending) as done")
                     .version("0.1")
@@ -165,5 +212,39 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                         .takes_value(true)
                         .value_name("NAME")
                         .help("The names of the habits to be marked as done."))
+                    .arg(Arg::with_name("done-amount")
+                        .long("amount")
+                        .short("a")
+                        .multiple(false)
+                        .required(false)
+                        .takes_value(true)
+                        .value_name("AMOUNT")
+                        .help("The amount which was done, for habits with a target amount. Adds up if called more than once."))
+                    )
+
+        .subcommand(SubCommand::with_name("quantify")
+                    .about("Set a target amount for an existing habit")
+                    .version("0.1")
+                    .arg(Arg::with_name("quantify-name")
+                        .index(1)
+                        .multiple(false)
+                        .required(true)
+                        .takes_value(true)
+                        .value_name("NAME")
+                        .help("The name of the habit"))
+                    .arg(Arg::with_name("quantify-target")
+                        .index(2)
+                        .multiple(false)
+                        .required(true)
+                        .takes_value(true)
+                        .value_name("AMOUNT")
+                        .help("Amount which should be done each time"))
+                    .arg(Arg::with_name("quantify-unit")
+                        .long("unit")
+                        .multiple(false)
+                        .required(false)
+                        .takes_value(true)
+                        .value_name("UNIT")
+                        .help("Unit of the target amount"))
                     )
 }
@@ -0,0 +1,125 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Linking mails to contacts
+//!
+//! Mails are linked (with internal links) to the contacts from `libimagcontact` whose email
+//! addresses appear in the "From", "To" or "Cc" headers of the mail.
+
+use std::collections::HashMap;
+
+use libimagstore::store::Store;
+use libimagstore::storeid::StoreId;
+use libimagentrylink::internal::InternalLinker;
+use libimagcontact::contact::Contact;
+use libimagcontact::error::Result as ContactResult;
+use libimagcontact::iter::ContactIter;
+use libimagcontact::store::ContactStore;
+use libimagmail::mail::Mail;
+use libimagmail::error::Result as MailResult;
+
+/// An index from (lowercased) email addresses to the contacts which have them
+pub struct ContactIndex(HashMap<String, Vec<StoreId>>);
+
+impl ContactIndex {
+
+    /// Build the index from all contacts in the store
+    pub fn new(store: &Store) -> ContactResult<ContactIndex> {
+        let mut index : HashMap<String, Vec<StoreId>> = HashMap::new();
+
+        for contact in ContactIter::new(store.all_contacts()?, store) {
+            let contact = contact?;
+            for email in contact.deser()?.email() {
+                let address = normalize_address(&email.address);
+                let ids     = index.entry(address).or_insert_with(Vec::new);
+
+                if !ids.contains(contact.get_location()) {
+                    ids.push(contact.get_location().clone());
+                }
+            }
+        }
+
+        Ok(ContactIndex(index))
+    }
+
+    /// Get the contacts which have the email address `address`
+    pub fn contacts_for(&self, address: &str) -> &[StoreId] {
+        self.0
+            .get(&normalize_address(address))
+            .map(Vec::as_slice)
+            .unwrap_or(&[])
+    }
+
+    /// Link the mail to all contacts whose addresses appear in its headers
+    ///
+    /// Contacts which are already linked to the mail are not linked again. Returns the contacts
+    /// which were linked.
+    pub fn link_mail(&self, store: &Store, mail: &mut Mail) -> MailResult<Vec<StoreId>> {
+        let mut contacts : Vec<StoreId> = vec![];
+        for address in mail.get_addresses()? {
+            for id in self.contacts_for(&address) {
+                if !contacts.contains(id) {
+                    contacts.push(id.clone());
+                }
+            }
+        }
+
+        let linked : Vec<StoreId> = mail
+            .fle()
+            .get_internal_links()?
+            .map(|link| link.get_store_id().clone())
+            .collect();
+
+        let mut result = vec![];
+        for id in contacts.into_iter().filter(|id| !linked.contains(id)) {
+            if let Some(mut contact) = store.get(id.clone())? {
+                debug!("Linking {} to contact {}", mail.fle().get_location(), id);
+                let _ = mail.fle_mut().add_internal_link(&mut contact)?;
+                result.push(id);
+            }
+        }
+
+        Ok(result)
+    }
+
+}
+
+/// Normalize an email address for comparison
+fn normalize_address(address: &str) -> String {
+    let address = address.trim();
+    let address = if address.to_lowercase().starts_with("mailto:") {
+        &address[7..]
+    } else {
+        address
+    };
+
+    address.to_lowercase()
+}
+
+#[cfg(test)]
+mod test {
+    use super::normalize_address;
+
+    #[test]
+    fn test_normalize_address() {
+        assert_eq!(normalize_address(" John@Example.com "), "john@example.com");
+        assert_eq!(normalize_address("MAILTO:john@example.com"), "john@example.com");
+    }
+
+}
@@ -34,25 +34,44 @@
 
 extern crate clap;
 #[macro_use] extern crate log;
+extern crate toml;
+extern crate toml_query;
 
 #[macro_use] extern crate libimagrt;
 extern crate libimagmail;
 extern crate libimagerror;
 extern crate libimagutil;
+extern crate libimagstore;
+extern crate libimagcontact;
+extern crate libimagentrylink;
 
 use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+use toml::Value;
 
 use libimagerror::trace::{MapErrTrace, trace_error};
 use libimagerror::iter::TraceIterator;
 use libimagerror::exit::ExitUnwrap;
 use libimagerror::io::ToExitCode;
 use libimagmail::mail::Mail;
+use libimagmail::mime::Attachment;
+use libimagmail::import::import_maildir;
+use libimagmail::repair::rekey_mails;
+use libimagmail::repair::repair_refs;
+use libimagmail::thread::ThreadNode;
+use libimagmail::thread::link_threads;
+use libimagmail::thread::thread_of;
+use libimagstore::storeid::StoreId;
 use libimagrt::runtime::Runtime;
 use libimagrt::setup::generate_runtime_setup;
 use libimagutil::info_result::*;
 
+mod contacts;
 mod ui;
 
+use contacts::ContactIndex;
 use ui::build_ui;
 
 fn main() {
@@ -70,6 +89,9 @@ fn main() {
                 "import-mail" => import_mail(&rt),
                 "list"        => list(&rt),
                 "mail-store"  => mail_store(&rt),
+                "thread"      => thread(&rt),
+                "show"        => show(&rt),
+                "attachments" => attachments(&rt),
                 other         => {
                     debug!("Unknown command");
                     let _ = rt.handle_unknown_subcommand("imag-mail", other, rt.cli())
@@ -85,9 +107,48 @@ fn import_mail(rt: &Runtime) {
     let scmd = rt.cli().subcommand_matches("import-mail").unwrap();
     let path = scmd.value_of("path").unwrap(); // enforced by clap
 
-    let _ = Mail::import_from_path(rt.store(), path)
-        .map_err_trace()
-        .map_info_str("Ok");
+    let imported : Vec<StoreId> = if Path::new(path).is_dir() {
+        let report = import_maildir(rt.store(), path).map_err_trace_exit_unwrap(1);
+
+        for &(ref file, ref e) in report.failed.iter() {
+            warn!("Could not import {}", file.display());
+            trace_error(e);
+        }
+
+        info!("Imported {} mails, skipped {} already imported, {} failed",
+              report.imported.len(),
+              report.skipped,
+              report.failed.len());
+
+        report.imported
+    } else {
+        Mail::import_from_path(rt.store(), path)
+            .and_then(|mut mail| {
+                let _ = mail.record_headers()?;
+                Ok(mail.fle().get_location().clone())
+            })
+            .map_err_trace()
+            .map_info_str("Ok")
+            .ok()
+            .into_iter()
+            .collect()
+    };
+
+    if !scmd.is_present("no-threading") {
+        let linked = link_threads(rt.store()).map_err_trace_exit_unwrap(1);
+        info!("Linked {} mails to their threads", linked);
+    }
+
+    if scmd.is_present("link-contacts") {
+        let index = ContactIndex::new(rt.store()).map_err_trace_exit_unwrap(1);
+
+        for id in imported {
+            let mut mail = get_mail(rt, id);
+            for contact in index.link_mail(rt.store(), &mut mail).map_err_trace_exit_unwrap(1) {
+                info!("Linked {} to contact {}", mail.fle().get_location(), contact);
+            }
+        }
+    }
 }
 
 fn list(rt: &Runtime) {
@@ -141,25 +202,254 @@ fn list(rt: &Runtime) {
         ).to_exit_code().unwrap_or_exit()
     }
 
-    let _ = rt.store()
+    let scmd = rt.cli().subcommand_matches("list").unwrap();
+    let dead = if scmd.is_present("check-dead") {
+        check_dead(rt)
+    } else {
+        vec![]
+    };
+
+    let mails = rt.store()
         .entries()
         .map_err_trace_exit_unwrap(1)
         .trace_unwrap_exit(1)
-        .filter(|id| id.is_in_collection(&["mail"]))
-        .filter_map(|id| {
-            rt.store()
-                .get(id)
-                .chain_err(|| MEK::RefHandlingError)
-                .map_err_trace_exit_unwrap(1)
-                .map(|fle| Mail::from_fle(fle).map_err_trace().ok())
-        })
-        .filter_map(|e| e)
-        .for_each(|m| list_mail(&rt, m));
+        .filter(|id| id.is_in_collection(&["mail"]));
+
+    for id in mails {
+        if dead.contains(&id) {
+            writeln!(rt.stdout(), "Mail: {} is dead", id)
+                .to_exit_code()
+                .unwrap_or_exit();
+            continue;
+        }
+
+        let mail = rt.store()
+            .get(id)
+            .chain_err(|| MEK::RefHandlingError)
+            .map_err_trace_exit_unwrap(1)
+            .and_then(|fle| Mail::from_fle(fle).map_err_trace().ok());
+
+        if let Some(mail) = mail {
+            list_mail(rt, mail);
+        }
+    }
+}
+
+/// Relocate dangling mail references, returns the references which are still dangling
+fn check_dead(rt: &Runtime) -> Vec<StoreId> {
+    use toml_query::read::TomlValueReadExt;
+
+    let roots : Vec<PathBuf> = match rt.config() {
+        None      => vec![],
+        Some(cfg) => match cfg.read("mail.maildirs").map_err_trace_exit_unwrap(1) {
+            Some(&Value::Array(ref roots)) => roots
+                .iter()
+                .filter_map(|v| v.as_str())
+                .map(PathBuf::from)
+                .collect(),
+            Some(_) => {
+                error!("Configuration 'mail.maildirs' must be an array of paths");
+                ::std::process::exit(1)
+            },
+            None => vec![],
+        },
+    };
+
+    if roots.is_empty() {
+        warn!("No Maildirs configured in 'mail.maildirs', cannot relocate dead references");
+    }
+
+    let report = repair_refs(rt.store(), &roots).map_err_trace_exit_unwrap(1);
+    for &(ref id, ref path) in report.repaired.iter() {
+        info!("Relocated {} to {}", id, path.display());
+    }
+
+    report.unresolved
 }
 
 fn mail_store(rt: &Runtime) {
-    let _ = rt.cli().subcommand_matches("mail-store").unwrap();
-    error!("This feature is currently not implemented.");
-    unimplemented!()
+    let scmd = rt.cli().subcommand_matches("mail-store").unwrap();
+
+    match scmd.subcommand_name() {
+        Some("update-refs") => update_refs(rt),
+        _                   => {
+            error!("No command for 'mail-store' given");
+            ::std::process::exit(1)
+        },
+    }
 }
 
+fn update_refs(rt: &Runtime) {
+    use libimagmail::error::MailErrorKind as MEK;
+    use libimagmail::error::ResultExt;
+
+    // mails imported before they were identified by their Message-ID
+    let rekeyed = rekey_mails(rt.store()).map_err_trace_exit_unwrap(1);
+    for &(ref old, ref new) in rekeyed.conflicts.iter() {
+        warn!("Not moving {}, {} exists already", old, new);
+    }
+    for &(ref id, ref e) in rekeyed.failed.iter() {
+        warn!("Could not move {}", id);
+        trace_error(e);
+    }
+    info!("Moved {} mails to their new id", rekeyed.moved.len());
+
+    let mails : Vec<StoreId> = rt.store()
+        .entries()
+        .map_err_trace_exit_unwrap(1)
+        .trace_unwrap_exit(1)
+        .filter(|id| id.is_in_collection(&["mail"]))
+        .collect();
+
+    for id in mails {
+        let fle = rt.store()
+            .get(id.clone())
+            .chain_err(|| MEK::RefHandlingError)
+            .map_err_trace_exit_unwrap(1);
+
+        if let Some(fle) = fle {
+            if let Err(e) = Mail::from_fle(fle).and_then(|mut mail| mail.record_headers()) {
+                warn!("Could not update {}", id);
+                trace_error(&e);
+            }
+        }
+    }
+
+    let linked = link_threads(rt.store()).map_err_trace_exit_unwrap(1);
+    info!("Linked {} mails to their threads", linked);
+}
+
+fn thread(rt: &Runtime) {
+    let scmd = rt.cli().subcommand_matches("thread").unwrap();
+    let id   = mail_id(scmd.value_of("id").unwrap()); // enforced by clap
+
+    let root = match thread_of(rt.store(), &id).map_err_trace_exit_unwrap(1) {
+        Some(root) => root,
+        None       => {
+            error!("No mail with id {}", id);
+            ::std::process::exit(1)
+        },
+    };
+
+    fn print_node(rt: &Runtime, node: &ThreadNode, selected: &StoreId, depth: usize) {
+        let marker  = if node.id == *selected { "*" } else { "-" };
+        let subject = node.subject.as_ref().map(String::as_str).unwrap_or("<no subject>");
+        let from    = node.from.as_ref().map(String::as_str).unwrap_or("<no from>");
+        let date    = node.date.as_ref().map(String::as_str).unwrap_or("<no date>");
+
+        writeln!(rt.stdout(),
+                 "{indent}{marker} {subject} ({from}, {date}) [{id}]",
+                 indent  = "  ".repeat(depth),
+                 marker  = marker,
+                 subject = subject,
+                 from    = from,
+                 date    = date,
+                 id      = node.id.local().display())
+            .to_exit_code()
+            .unwrap_or_exit();
+
+        for child in node.children.iter() {
+            print_node(rt, child, selected, depth + 1);
+        }
+    }
+
+    print_node(rt, &root, &id, 0);
+}
+
+fn show(rt: &Runtime) {
+    let scmd = rt.cli().subcommand_matches("show").unwrap();
+    let id   = mail_id(scmd.value_of("id").unwrap()); // enforced by clap
+    let mail = get_mail(rt, id);
+    let mut out = rt.stdout();
+
+    for field in ["From", "To", "Cc", "Date", "Subject"].iter() {
+        if let Some(value) = mail.get_decoded_field(field).map_err_trace_exit_unwrap(1) {
+            writeln!(out, "{}: {}", field, value).to_exit_code().unwrap_or_exit();
+        }
+    }
+
+    let body = mail
+        .get_text_body()
+        .map_err_trace_exit_unwrap(1)
+        .unwrap_or_else(|| String::from("<no text body>"));
+
+    writeln!(out, "\n{}", body).to_exit_code().unwrap_or_exit();
+
+    let attachments = mail.get_attachments().map_err_trace_exit_unwrap(1);
+    if !attachments.is_empty() {
+        writeln!(out).to_exit_code().unwrap_or_exit();
+        for attachment in attachments {
+            print_attachment(&mut out, &attachment);
+        }
+    }
+}
+
+fn attachments(rt: &Runtime) {
+    let scmd     = rt.cli().subcommand_matches("attachments").unwrap();
+    let id       = mail_id(scmd.value_of("id").unwrap()); // enforced by clap
+    let mut mail = get_mail(rt, id);
+
+    match scmd.value_of("extract") {
+        None        => {
+            let mut out = rt.stdout();
+            for attachment in mail.get_attachments().map_err_trace_exit_unwrap(1) {
+                print_attachment(&mut out, &attachment);
+            }
+        },
+        Some(index) => {
+            let index = index.parse::<usize>().unwrap_or_else(|e| {
+                error!("Not a valid attachment number: {}: {}", index, e);
+                ::std::process::exit(1)
+            });
+
+            let dir = scmd
+                .value_of("output")
+                .map(PathBuf::from)
+                .unwrap_or_else(|| PathBuf::from("."));
+
+            let entry = mail
+                .save_attachment(rt.store(), index, dir)
+                .map_err_trace_exit_unwrap(1);
+
+            info!("Saved attachment {} as {}", index, entry.get_location());
+        },
+    }
+}
+
+fn print_attachment<W: Write>(out: &mut W, attachment: &Attachment) {
+    writeln!(out,
+             "[{index}] {name} ({ct}, {size} bytes)",
+             index = attachment.index,
+             name  = attachment.name.as_ref().map(String::as_str).unwrap_or("<unnamed>"),
+             ct    = attachment.content_type,
+             size  = attachment.size)
+        .to_exit_code()
+        .unwrap_or_exit();
+}
+
+/// Build the id of a mail from a store id ("mail/<hash>") or a plain hash
+fn mail_id(id: &str) -> StoreId {
+    let path = if id.starts_with("mail/") {
+        PathBuf::from(id)
+    } else {
+        PathBuf::from(format!("mail/{}", id))
+    };
+
+    StoreId::new_baseless(path).map_err_trace_exit_unwrap(1)
+}
+
+fn get_mail(rt: &Runtime, id: StoreId) -> Mail {
+    use libimagmail::error::MailErrorKind as MEK;
+    use libimagmail::error::ResultExt;
+
+    let fle = rt.store()
+        .get(id.clone())
+        .chain_err(|| MEK::FetchError)
+        .map_err_trace_exit_unwrap(1)
+        .unwrap_or_else(|| {
+            error!("No mail with id {}", id);
+            ::std::process::exit(1)
+        });
+
+    Mail::from_fle(fle).map_err_trace_exit_unwrap(1)
+}
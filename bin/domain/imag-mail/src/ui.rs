@@ -31,6 +31,16 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                          .required(true)
                          .help("Path to the mail file or a directory which is then searched recursively")
                          .value_name("PATH"))
+                    .arg(Arg::with_name("no-threading")
+                         .long("no-threading")
+                         .takes_value(false)
+                         .required(false)
+                         .help("Do not link the imported mails to their threads"))
+                    .arg(Arg::with_name("link-contacts")
+                         .long("link-contacts")
+                         .takes_value(false)
+                         .required(false)
+                         .help("Link the imported mails to the contacts from their From/To/Cc headers"))
                     )
 
         .subcommand(SubCommand::with_name("list")
@@ -43,7 +53,7 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                     .arg(Arg::with_name("check-dead")
                          .long("check-dead")
                          .short("d")
-                         .help("Check each reference whether it is dead"))
+                         .help("Check each reference whether it is dead, try to relocate dead references in the Maildirs from the 'mail.maildirs' configuration"))
 
                     .arg(Arg::with_name("check-changed")
                          .long("check-changed")
@@ -66,9 +76,57 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                     .about("Operations on (subsets of) all mails")
                     .version("0.1")
                     .subcommand(SubCommand::with_name("update-refs")
-                                .about("Create references based on Message-IDs for all loaded mails")
+                                .about("Record the Message-IDs of all imported mails and link them to their threads")
                                 .version("0.1"))
                     // TODO: We really should be able to filter here.
                     )
+
+        .subcommand(SubCommand::with_name("thread")
+                    .about("Show the thread a mail is part of")
+                    .version("0.1")
+                    .arg(Arg::with_name("id")
+                         .index(1)
+                         .takes_value(true)
+                         .required(true)
+                         .help("The mail to show the thread for (store id or hash)")
+                         .value_name("ID"))
+                    )
+
+        .subcommand(SubCommand::with_name("show")
+                    .about("Show a mail (decoded headers, text body and attachments)")
+                    .version("0.1")
+                    .arg(Arg::with_name("id")
+                         .index(1)
+                         .takes_value(true)
+                         .required(true)
+                         .help("The mail to show (store id or hash)")
+                         .value_name("ID"))
+                    )
+
+        .subcommand(SubCommand::with_name("attachments")
+                    .about("List or extract the attachments of a mail")
+                    .version("0.1")
+                    .arg(Arg::with_name("id")
+                         .index(1)
+                         .takes_value(true)
+                         .required(true)
+                         .help("The mail to list the attachments of (store id or hash)")
+                         .value_name("ID"))
+                    .arg(Arg::with_name("extract")
+                         .long("extract")
+                         .short("x")
+                         .takes_value(true)
+                         .required(false)
+                         .help("Save the attachment with this number and reference it in the store")
+                         .value_name("N"))
+                    .arg(Arg::with_name("output")
+                         .long("output")
+                         .short("o")
+                         .takes_value(true)
+                         .required(false)
+                         .requires("extract")
+                         .help("The directory to save the attachment to (default: current directory)")
+                         .value_name("DIR"))
+                    )
 }
 
@@ -0,0 +1,183 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+use std::str::FromStr;
+
+use chrono::Duration;
+use chrono::NaiveDateTime;
+use clap::ArgMatches;
+use prettytable::Table;
+use prettytable::row::Row;
+use prettytable::cell::Cell;
+
+use libimagerror::trace::trace_error;
+use libimagerror::trace::MapErrTrace;
+use libimagerror::iter::TraceIterator;
+use libimagnotification::notificator::Notificator;
+use libimagnotification::notificator::default::Notification;
+use libimagnotification::notificator::default::Urgency;
+use libimagrt::runtime::Runtime;
+use libimagtimetrack::budget::Budget;
+use libimagtimetrack::budget::BudgetPeriod;
+use libimagtimetrack::budget::BudgetReport;
+use libimagtimetrack::budget::budgets;
+use libimagtimetrack::budget::format_duration;
+use libimagtimetrack::budget::set_budget;
+use libimagtimetrack::error::TimeTrackError as TTE;
+use libimagtimetrack::tag::TimeTrackingTag;
+use libimagtimetrack::timetracking::TimeTracking;
+use libimagtimetrack::timetrackingstore::TimeTrackStore;
+
+pub fn budget(rt: &Runtime) -> i32 {
+    let (_, cmd) = rt.cli().subcommand();
+    let cmd = cmd.unwrap(); // checked in main()
+
+    match cmd.subcommand() {
+        ("set", Some(scmd)) => budget_set(rt, scmd),
+        _                   => budget_show(rt, cmd),
+    }
+}
+
+fn budget_set(rt: &Runtime, cmd: &ArgMatches) -> i32 {
+    let tag = TimeTrackingTag::from(String::from(cmd.value_of("tag").unwrap())); // enforced by clap
+
+    let hours = match f64::from_str(cmd.value_of("hours").unwrap()) { // enforced by clap
+        Ok(h)  => h,
+        Err(_) => {
+            error!("Cannot parse '{}' as hours", cmd.value_of("hours").unwrap());
+            return 1
+        }
+    };
+
+    let period = match BudgetPeriod::from_name(cmd.value_of("per").unwrap()) { // enforced by clap
+        Some(p) => p,
+        None    => {
+            error!("Unknown period: {}", cmd.value_of("per").unwrap());
+            return 1
+        }
+    };
+
+    let budget = Budget {
+        tag: tag,
+        duration: Duration::seconds((hours * 3600.0).round() as i64),
+        period: period,
+    };
+
+    set_budget(rt.store(), &budget).map_err_trace_exit_unwrap(1);
+    info!("Set budget {}", budget);
+    0
+}
+
+fn budget_show(rt: &Runtime, cmd: &ArgMatches) -> i32 {
+    let now = ::chrono::offset::Local::now().naive_local();
+
+    let gettime = |name: &str| -> Option<NaiveDateTime> {
+        cmd.value_of(name).map(|s| match NaiveDateTime::from_str(s).map_err(TTE::from) {
+            Ok(dt) => dt,
+            Err(e) => {
+                trace_error(&e);
+                ::std::process::exit(1)
+            }
+        })
+    };
+
+    let start = gettime("start");
+    let end   = gettime("end");
+
+    let tags : Option<Vec<TimeTrackingTag>> = cmd
+        .values_of("tags")
+        .map(|ts| ts.map(String::from).map(TimeTrackingTag::from).collect());
+
+    let reports = budgets(rt.store(), rt.config())
+        .map_err_trace_exit_unwrap(1)
+        .into_iter()
+        .filter(|b| tags.as_ref().map(|tags| tags.contains(&b.tag)).unwrap_or(true))
+        .map(|b| match (start, end) {
+            (None, None) => b.report_current(rt.store(), now),
+            (s, e)       => {
+                let (pstart, pend) = b.period.range_containing(&now);
+                b.report(rt.store(), s.unwrap_or(pstart), e.unwrap_or(pend), now)
+            },
+        })
+        .trace_unwrap_exit(1)
+        .collect::<Vec<BudgetReport>>();
+
+    if cmd.is_present("notify") {
+        notify_overruns(rt, &reports);
+    }
+
+    let mut table = Table::new();
+    table.set_titles(Row::new(["Tag", "From", "To", "Budget", "Used", "Remaining"]
+                              .into_iter()
+                              .map(|s| Cell::new(s))
+                              .collect()));
+
+    for report in reports.iter() {
+        let remaining = if report.is_overrun() {
+            format!("overrun by {}", format_duration(&(report.consumed - report.allowance)))
+        } else {
+            format_duration(&report.remaining())
+        };
+
+        let cells : Vec<Cell> = vec![
+            String::from(report.budget.tag.as_str()),
+            format!("{}", report.start),
+            format!("{}", report.end),
+            format_duration(&report.allowance),
+            format_duration(&report.consumed),
+            remaining,
+        ]
+        .into_iter()
+        .map(|s| Cell::new(&s))
+        .collect();
+
+        table.add_row(Row::new(cells));
+    }
+
+    table.print(&mut rt.stdout())
+        .map_err(|_| TTE::from(String::from("Failed printing table")))
+        .map(|_| 0)
+        .map_err_trace()
+        .unwrap_or(1)
+}
+
+/// Send a notification for each overrun budget of a tag which is currently tracked
+fn notify_overruns(rt: &Runtime, reports: &[BudgetReport]) {
+    let running : Vec<TimeTrackingTag> = rt.store()
+        .get_timetrackings()
+        .map_err_trace_exit_unwrap(1)
+        .trace_unwrap()
+        .filter(|e| match e.get_end_datetime() {
+            Ok(None) => true,
+            _        => false,
+        })
+        .filter_map(|e| e.get_timetrack_tag().map_err_trace().ok())
+        .collect();
+
+    let notification = Notification {
+        timeout: 0,
+        message: String::from("Budget exceeded"),
+        summary: String::from("imag-timetrack"),
+        urgency: Urgency::High,
+    };
+
+    for report in reports.iter().filter(|r| r.is_overrun() && running.contains(&r.budget.tag)) {
+        let _ = notification.notify(report).map_err_trace();
+    }
+}
@@ -0,0 +1,150 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::str::FromStr;
+
+use chrono::Duration;
+use chrono::NaiveDateTime;
+
+use libimagerror::trace::trace_error;
+use libimagerror::trace::MapErrTrace;
+use libimagerror::io::ToExitCode;
+use libimagerror::exit::ExitUnwrap;
+use libimagrt::runtime::Runtime;
+use libimagstore::storeid::StoreId;
+use libimagtimetrack::check::Anomaly;
+use libimagtimetrack::check::check_timetrackings;
+use libimagtimetrack::check::close_dangling;
+use libimagtimetrack::check::merge_trackings;
+use libimagtimetrack::error::TimeTrackError as TTE;
+
+pub fn check(rt: &Runtime) -> i32 {
+    let (_, cmd) = rt.cli().subcommand();
+    let cmd = cmd.unwrap(); // checked in main()
+
+    let now = ::chrono::offset::Local::now().naive_local();
+
+    let close_at = match cmd.value_of("close-at") {
+        None        => None,
+        Some("now") => Some(now),
+        Some(ndt)   => match NaiveDateTime::from_str(ndt).map_err(TTE::from) {
+            Ok(ndt) => Some(ndt),
+            Err(e) =>  {
+                trace_error(&e);
+                error!("Cannot continue, cannot parse time to close dangling trackings at");
+                return 1
+            },
+        }
+    };
+
+    let parse_minutes = |name: &str| -> Option<Duration> {
+        cmd.value_of(name).map(|s| match i64::from_str(s) {
+            Ok(m) => Duration::minutes(m),
+            Err(_) => {
+                error!("Cannot parse '{}' as minutes", s);
+                ::std::process::exit(1)
+            }
+        })
+    };
+
+    let max_duration = parse_minutes("max-duration");
+    let max_gap      = parse_minutes("max-gap");
+    let fix          = cmd.is_present("fix");
+
+    let anomalies = check_timetrackings(rt.store(), now, max_duration, max_gap)
+        .map_err_trace_exit_unwrap(1);
+
+    if anomalies.is_empty() {
+        info!("No anomalies found");
+        return 0
+    }
+
+    {
+        let mut out = rt.stdout();
+        for anomaly in anomalies.iter() {
+            writeln!(out, "{}", anomaly)
+                .and_then(|_| writeln!(out, "    -> {}", anomaly.suggestion()))
+                .to_exit_code()
+                .unwrap_or_exit();
+        }
+    }
+
+    if !fix {
+        return 1
+    }
+
+    // Trackings which were merged into another tracking are gone, so we have to remember which
+    // tracking they live on in.
+    let mut merged : HashMap<StoreId, StoreId> = HashMap::new();
+    let resolve = |merged: &HashMap<StoreId, StoreId>, id: &StoreId| -> StoreId {
+        let mut id = id.clone();
+        while let Some(next) = merged.get(&id) {
+            id = next.clone();
+        }
+        id
+    };
+
+    let mut unfixed = 0;
+    for anomaly in anomalies {
+        match anomaly {
+            Anomaly::Dangling(tracking, next_start) => {
+                match close_at.or(next_start) {
+                    Some(end) => {
+                        let id = resolve(&merged, &tracking.id);
+                        debug!("Closing {} at {}", id, end);
+                        if let Err(e) = close_dangling(rt.store(), id, end) {
+                            trace_error(&e);
+                            unfixed += 1;
+                        }
+                    },
+                    None => {
+                        warn!("Not closing {}, pass --close-at to close it", tracking);
+                        unfixed += 1;
+                    }
+                }
+            },
+
+            Anomaly::Overlap(a, b) | Anomaly::Gap(a, b) => {
+                let keep   = resolve(&merged, &a.id);
+                let remove = resolve(&merged, &b.id);
+                if keep == remove {
+                    continue
+                }
+
+                debug!("Merging {} into {}", remove, keep);
+                match merge_trackings(rt.store(), keep.clone(), remove.clone()) {
+                    Ok(_)  => { merged.insert(remove, keep); },
+                    Err(e) => {
+                        trace_error(&e);
+                        unfixed += 1;
+                    }
+                }
+            },
+
+            other => {
+                warn!("Cannot fix automatically: {}", other);
+                unfixed += 1;
+            },
+        }
+    }
+
+    if unfixed == 0 { 0 } else { 1 }
+}
@@ -43,28 +43,35 @@ extern crate prettytable;
 extern crate kairos;
 
 extern crate libimagerror;
+extern crate libimagnotification;
 extern crate libimagstore;
 #[macro_use] extern crate libimagrt;
 extern crate libimagtimetrack;
 extern crate libimagutil;
 
+mod budget;
+mod check;
 mod cont;
 mod day;
 mod list;
 mod month;
 mod start;
 mod stop;
+mod timer;
 mod track;
 mod ui;
 mod week;
 mod year;
 
+use budget::budget;
+use check::check;
 use cont::cont;
 use day::day;
 use list::{list, list_impl};
 use month::month;
 use start::start;
 use stop::stop;
+use timer::timer;
 use track::track;
 use ui::build_ui;
 use week::week;
@@ -84,12 +91,15 @@ fn main() {
     let retval  = if let Some(command) = command {
         debug!("Call: {}", command);
         match command {
+            "budget"   => budget(&rt),
+            "check"    => check(&rt),
             "continue" => cont(&rt),
             "day"      => day(&rt),
             "list"     => list(&rt),
             "month"    => month(&rt),
             "start"    => start(&rt),
             "stop"     => stop(&rt),
+            "timer"    => timer(&rt),
             "track"    => track(&rt),
             "week"     => week(&rt),
             "year"     => year(&rt),
@@ -0,0 +1,124 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+use std::io::Write;
+use std::str::FromStr;
+use std::thread::sleep;
+
+use chrono::Duration;
+use chrono::NaiveDateTime;
+
+use libimagerror::trace::MapErrTrace;
+use libimagerror::io::ToExitCode;
+use libimagerror::exit::ExitUnwrap;
+use libimagnotification::notificator::Notificator;
+use libimagnotification::notificator::default::Notification;
+use libimagnotification::notificator::default::Urgency;
+use libimagrt::runtime::Runtime;
+use libimagtimetrack::session::TimeTrackSessionStore;
+use libimagtimetrack::tag::TimeTrackingTag;
+use libimagtimetrack::timetracking::TimeTracking;
+
+pub fn timer(rt: &Runtime) -> i32 {
+    let (_, cmd) = rt.cli().subcommand();
+    let cmd = cmd.unwrap(); // checked in main()
+
+    let tag = TimeTrackingTag::from(String::from(cmd.value_of("tag").unwrap())); // enforced by clap
+
+    let get_number = |name: &str| -> i64 {
+        let s = cmd.value_of(name).unwrap(); // has default value
+        i64::from_str(s).unwrap_or_else(|_| {
+            error!("Cannot parse '{}' as number", s);
+            ::std::process::exit(1)
+        })
+    };
+
+    let work       = Duration::minutes(get_number("work"));
+    let pause      = Duration::minutes(get_number("break"));
+    let long_pause = Duration::minutes(get_number("long-break"));
+    let rounds     = get_number("rounds");
+
+    let notification = |msg: &str| Notification {
+        timeout: 0,
+        message: String::from(msg),
+        summary: String::from("imag-timetrack timer"),
+        urgency: Urgency::Normal,
+    };
+
+    let mut session = rt.store()
+        .create_session_at(&now(), &tag)
+        .map_err_trace_exit_unwrap(1);
+
+    for round in 1..(rounds + 1) {
+        {
+            let mut tracking = rt.store()
+                .start_session_tracking(&mut session, &now())
+                .map_err_trace_exit_unwrap(1);
+
+            countdown(rt, &format!("{} - work {}/{}", tag, round, rounds), work);
+
+            tracking.set_end_datetime(now()).map_err_trace_exit_unwrap(1);
+        }
+
+        if round == rounds {
+            break
+        }
+
+        let (msg, length) = if round % 4 == 0 {
+            ("Work interval finished, take a long break", long_pause)
+        } else {
+            ("Work interval finished, take a break", pause)
+        };
+
+        let _ = notification(msg).notify(&tag).map_err_trace();
+        countdown(rt, &format!("{} - break {}/{}", tag, round, rounds - 1), length);
+        let _ = notification("Break is over, back to work").notify(&tag).map_err_trace();
+    }
+
+    session.set_end_datetime(now()).map_err_trace_exit_unwrap(1);
+    let _ = notification("Session finished").notify(&tag).map_err_trace();
+    0
+}
+
+fn now() -> NaiveDateTime {
+    ::chrono::offset::Local::now().naive_local()
+}
+
+/// Count down `length`, printing the remaining time in-place every second
+fn countdown(rt: &Runtime, title: &str, length: Duration) {
+    let end     = now() + length;
+    let mut out = rt.stdout();
+
+    loop {
+        let remaining = end.signed_duration_since(now());
+        if remaining <= Duration::zero() {
+            break
+        }
+
+        let secs = remaining.num_seconds();
+        write!(out, "\r{}: {:02}:{:02} ", title, secs / 60, secs % 60)
+            .and_then(|_| out.flush())
+            .to_exit_code()
+            .unwrap_or_exit();
+
+        sleep(::std::time::Duration::from_secs(1));
+    }
+
+    writeln!(out, "\r{}: done    ", title).to_exit_code().unwrap_or_exit();
+}
@@ -94,11 +94,117 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                         .help("Tags to stop"))
                    )
 
+       .subcommand(SubCommand::with_name("timer")
+                   .about("Track time in work intervals with breaks in between (pomodoro technique)")
+                   .version("0.1")
+                   .arg(Arg::with_name("tag")
+                        .index(1)
+                        .required(true)
+                        .help("Tag to track"))
+                   .arg(Arg::with_name("work")
+                        .long("work")
+                        .short("w")
+                        .takes_value(true)
+                        .default_value("25")
+                        .help("Length of a work interval in minutes"))
+                   .arg(Arg::with_name("break")
+                        .long("break")
+                        .short("b")
+                        .takes_value(true)
+                        .default_value("5")
+                        .help("Length of a break in minutes"))
+                   .arg(Arg::with_name("long-break")
+                        .long("long-break")
+                        .short("l")
+                        .takes_value(true)
+                        .default_value("15")
+                        .help("Length of every fourth break in minutes"))
+                   .arg(Arg::with_name("rounds")
+                        .long("rounds")
+                        .short("r")
+                        .takes_value(true)
+                        .default_value("4")
+                        .help("Number of work intervals"))
+                   )
+
        .subcommand(SubCommand::with_name("continue")
                    .about("Continue last stopped time tracking")
                    .version("0.1")
                    )
 
+       .subcommand(SubCommand::with_name("budget")
+                   .about("Show time budgets per tag and how much of them is used")
+                   .version("0.1")
+                   .arg(Arg::with_name("start")
+                        .long("from")
+                        .short("f")
+                        .takes_value(true)
+                        .required(false)
+                        .help("Compute consumption from this time on (default: start of the current budget period)"))
+                   .arg(Arg::with_name("end")
+                        .long("to")
+                        .short("t")
+                        .takes_value(true)
+                        .required(false)
+                        .help("Compute consumption up to this time (default: end of the current budget period)"))
+                   .arg(Arg::with_name("tags")
+                        .long("tags")
+                        .required(false)
+                        .multiple(true)
+                        .help("Limit to certain tags"))
+                   .arg(Arg::with_name("notify")
+                        .long("notify")
+                        .takes_value(false)
+                        .required(false)
+                        .help("Send a desktop notification for running trackings which exceed their budget"))
+
+                   .subcommand(SubCommand::with_name("set")
+                               .about("Set the budget for a tag")
+                               .version("0.1")
+                               .arg(Arg::with_name("tag")
+                                    .index(1)
+                                    .required(true)
+                                    .help("The tag to set the budget for"))
+                               .arg(Arg::with_name("hours")
+                                    .index(2)
+                                    .required(true)
+                                    .help("The budget, in hours"))
+                               .arg(Arg::with_name("per")
+                                    .index(3)
+                                    .required(true)
+                                    .possible_values(&["day", "week", "month", "year"])
+                                    .help("The period the budget applies to"))
+                               )
+                   )
+
+       .subcommand(SubCommand::with_name("check")
+                   .about("Check time trackings for overlaps, dangling trackings and invalid entries")
+                   .version("0.1")
+                   .arg(Arg::with_name("fix")
+                        .long("fix")
+                        .takes_value(false)
+                        .required(false)
+                        .help("Close dangling trackings and merge overlapping trackings"))
+                   .arg(Arg::with_name("close-at")
+                        .long("close-at")
+                        .takes_value(true)
+                        .required(false)
+                        .value_name("TIME")
+                        .help("Close dangling trackings at this time (use 'now' for current time). Default: when the next tracking of the same tag started"))
+                   .arg(Arg::with_name("max-duration")
+                        .long("max-duration")
+                        .takes_value(true)
+                        .required(false)
+                        .value_name("MINUTES")
+                        .help("Report running trackings which are running for longer than this as dangling"))
+                   .arg(Arg::with_name("max-gap")
+                        .long("max-gap")
+                        .takes_value(true)
+                        .required(false)
+                        .value_name("MINUTES")
+                        .help("Report trackings of the same tag which are only separated by this many minutes (merged with --fix)"))
+                   )
+
        .subcommand(SubCommand::with_name("day")
                    .about("Print stats about day")
                    .version("0.1")
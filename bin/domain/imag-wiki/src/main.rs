@@ -59,6 +59,16 @@ fn main() {
         Some("create")      => create(&rt, wiki_name),
         Some("create-wiki") => create_wiki(&rt),
         Some("show")        => show(&rt, wiki_name),
+        Some("edit")        => edit(&rt, wiki_name),
+        Some("history")     => history(&rt, wiki_name),
+        Some("diff")        => diff(&rt, wiki_name),
+        Some("revert")      => revert(&rt, wiki_name),
+        Some("backlinks")   => backlinks(&rt, wiki_name),
+        Some("orphans")     => orphans(&rt, wiki_name),
+        Some("check")       => check(&rt, wiki_name),
+        Some("export")      => export(&rt, wiki_name),
+        Some("mv")          => mv(&rt, wiki_name),
+        Some("import")      => import(&rt, wiki_name),
         Some("delete")      => delete(&rt, wiki_name),
         Some(other)         => {
             debug!("Unknown command");
@@ -242,6 +252,294 @@ fn show(rt: &Runtime, wiki_name: &str) {
     }
 }
 
+fn edit(rt: &Runtime, wiki_name: &str) {
+    use libimagwiki::entry::WikiEntry;
+
+    let scmd = rt.cli().subcommand_matches("edit").unwrap(); // safed by clap
+    let name = String::from(scmd.value_of("edit-name").unwrap()); // safe by clap
+
+    let wiki = rt
+        .store()
+        .get_wiki(&wiki_name)
+        .map_err_trace_exit_unwrap(1)
+        .unwrap_or_else(|| {
+            error!("No wiki '{}' found", wiki_name);
+            ::std::process::exit(1)
+        });
+
+    let mut entry = wiki
+        .get_entry(&name)
+        .map_err_trace_exit_unwrap(1)
+        .unwrap_or_else(|| {
+            error!("No wiki entry '{}' found in wiki '{}'", name, wiki_name);
+            ::std::process::exit(1)
+        });
+
+    let old_content = entry.get_content().clone();
+
+    if scmd.is_present("edit-editheader") {
+        let _ = entry.edit_header_and_content(rt).map_err_trace_exit_unwrap(1);
+    } else {
+        let _ = entry.edit_content(rt).map_err_trace_exit_unwrap(1);
+    }
+
+    if *entry.get_content() == old_content {
+        info!("Content of '{}' not changed, not storing a revision", name);
+        return;
+    }
+
+    let revision = wiki.add_revision(&mut entry, old_content).map_err_trace_exit_unwrap(1);
+    debug!("Stored old content as {}", revision.get_location());
+
+    let _ = entry.autolink(rt.store()).map_err_trace_exit_unwrap(1);
+}
+
+fn history(rt: &Runtime, wiki_name: &str) {
+    let scmd = rt.cli().subcommand_matches("history").unwrap(); // safed by clap
+    let name = String::from(scmd.value_of("history-name").unwrap()); // safe by clap
+
+    let wiki = rt
+        .store()
+        .get_wiki(&wiki_name)
+        .map_err_trace_exit_unwrap(1)
+        .unwrap_or_else(|| {
+            error!("No wiki '{}' found", wiki_name);
+            ::std::process::exit(1)
+        });
+
+    let out         = rt.stdout();
+    let mut outlock = out.lock();
+
+    for revision in wiki.revisions(&name).map_err_trace_exit_unwrap(1) {
+        let date = revision
+            .date()
+            .map(|d| d.format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_else(|| String::from("unknown date"));
+
+        let _ = writeln!(outlock, "{:>4}  {}", revision.number(), date)
+            .to_exit_code()
+            .unwrap_or_exit();
+    }
+}
+
+fn diff(rt: &Runtime, wiki_name: &str) {
+    use libimagwiki::diff::diff as diff_lines;
+    use libimagwiki::diff::format_diff;
+
+    let scmd = rt.cli().subcommand_matches("diff").unwrap(); // safed by clap
+    let name = String::from(scmd.value_of("diff-name").unwrap()); // safe by clap
+
+    let parse_number = |arg: &str| -> Option<usize> {
+        scmd.value_of(arg).map(|s| s.parse::<usize>().unwrap_or_else(|_| {
+            error!("Cannot parse '{}' as number", s);
+            ::std::process::exit(1)
+        }))
+    };
+
+    let revision    = parse_number("diff-revision").unwrap(); // safe by clap
+    let to_revision = parse_number("diff-to-revision");
+    let context     = parse_number("diff-context").unwrap_or(3);
+
+    let wiki = rt
+        .store()
+        .get_wiki(&wiki_name)
+        .map_err_trace_exit_unwrap(1)
+        .unwrap_or_else(|| {
+            error!("No wiki '{}' found", wiki_name);
+            ::std::process::exit(1)
+        });
+
+    let get_revision_content = |number: usize| -> String {
+        wiki.get_revision(&name, number)
+            .map_err_trace_exit_unwrap(1)
+            .unwrap_or_else(|| {
+                error!("No revision {} of '{}' found", number, name);
+                ::std::process::exit(1)
+            })
+            .get_content()
+            .clone()
+    };
+
+    let old = get_revision_content(revision);
+    let new = match to_revision {
+        Some(number) => get_revision_content(number),
+        None         => wiki
+            .get_entry(&name)
+            .map_err_trace_exit_unwrap(1)
+            .unwrap_or_else(|| {
+                error!("No wiki entry '{}' found in wiki '{}'", name, wiki_name);
+                ::std::process::exit(1)
+            })
+            .get_content()
+            .clone(),
+    };
+
+    let _ = write!(rt.stdout(), "{}", format_diff(&diff_lines(&old, &new), context))
+        .to_exit_code()
+        .unwrap_or_exit();
+}
+
+fn revert(rt: &Runtime, wiki_name: &str) {
+    use libimagwiki::entry::WikiEntry;
+
+    let scmd     = rt.cli().subcommand_matches("revert").unwrap(); // safed by clap
+    let name     = String::from(scmd.value_of("revert-name").unwrap()); // safe by clap
+    let revision = scmd.value_of("revert-revision").unwrap(); // safe by clap
+    let revision = revision.parse::<usize>().unwrap_or_else(|_| {
+        error!("Cannot parse '{}' as number", revision);
+        ::std::process::exit(1)
+    });
+
+    let wiki = rt
+        .store()
+        .get_wiki(&wiki_name)
+        .map_err_trace_exit_unwrap(1)
+        .unwrap_or_else(|| {
+            error!("No wiki '{}' found", wiki_name);
+            ::std::process::exit(1)
+        });
+
+    let mut entry = wiki.revert(&name, revision).map_err_trace_exit_unwrap(1);
+    let _         = entry.autolink(rt.store()).map_err_trace_exit_unwrap(1);
+    info!("Reverted '{}' to revision {}", name, revision);
+}
+
+fn backlinks(rt: &Runtime, wiki_name: &str) {
+    let scmd = rt.cli().subcommand_matches("backlinks").unwrap(); // safed by clap
+    let name = String::from(scmd.value_of("backlinks-name").unwrap()); // safe by clap
+
+    let wiki = rt
+        .store()
+        .get_wiki(&wiki_name)
+        .map_err_trace_exit_unwrap(1)
+        .unwrap_or_else(|| {
+            error!("No wiki '{}' found", wiki_name);
+            ::std::process::exit(1)
+        });
+
+    let out         = rt.stdout();
+    let mut outlock = out.lock();
+
+    for page in wiki.backlinks(&name).map_err_trace_exit_unwrap(1) {
+        let _ = writeln!(outlock, "{}", page).to_exit_code().unwrap_or_exit();
+    }
+}
+
+fn orphans(rt: &Runtime, wiki_name: &str) {
+    let wiki = rt
+        .store()
+        .get_wiki(&wiki_name)
+        .map_err_trace_exit_unwrap(1)
+        .unwrap_or_else(|| {
+            error!("No wiki '{}' found", wiki_name);
+            ::std::process::exit(1)
+        });
+
+    let out         = rt.stdout();
+    let mut outlock = out.lock();
+
+    for page in wiki.orphans().map_err_trace_exit_unwrap(1) {
+        let _ = writeln!(outlock, "{}", page).to_exit_code().unwrap_or_exit();
+    }
+}
+
+fn check(rt: &Runtime, wiki_name: &str) {
+    let wiki = rt
+        .store()
+        .get_wiki(&wiki_name)
+        .map_err_trace_exit_unwrap(1)
+        .unwrap_or_else(|| {
+            error!("No wiki '{}' found", wiki_name);
+            ::std::process::exit(1)
+        });
+
+    let broken = wiki.broken_links().map_err_trace_exit_unwrap(1);
+
+    {
+        let out         = rt.stdout();
+        let mut outlock = out.lock();
+
+        for link in broken.iter() {
+            let _ = writeln!(outlock, "{}: [{}]({}) does not exist", link.page, link.title, link.target.display())
+                .to_exit_code()
+                .unwrap_or_exit();
+        }
+    }
+
+    if !broken.is_empty() {
+        ::std::process::exit(1)
+    }
+}
+
+fn export(rt: &Runtime, wiki_name: &str) {
+    use libimagwiki::export::export_html;
+
+    let scmd = rt.cli().subcommand_matches("export").unwrap(); // safed by clap
+    let dir  = ::std::path::PathBuf::from(scmd.value_of("export-dir").unwrap()); // safe by clap
+
+    let wiki = rt
+        .store()
+        .get_wiki(&wiki_name)
+        .map_err_trace_exit_unwrap(1)
+        .unwrap_or_else(|| {
+            error!("No wiki '{}' found", wiki_name);
+            ::std::process::exit(1)
+        });
+
+    let n = export_html(&wiki, &dir).map_err_trace_exit_unwrap(1);
+    info!("Exported {} pages to {}", n, dir.display());
+}
+
+fn mv(rt: &Runtime, wiki_name: &str) {
+    let scmd     = rt.cli().subcommand_matches("mv").unwrap(); // safed by clap
+    let name     = scmd.value_of("mv-name").unwrap(); // safe by clap
+    let new_name = scmd.value_of("mv-new-name").unwrap(); // safe by clap
+
+    let wiki = rt
+        .store()
+        .get_wiki(&wiki_name)
+        .map_err_trace_exit_unwrap(1)
+        .unwrap_or_else(|| {
+            error!("No wiki '{}' found", wiki_name);
+            ::std::process::exit(1)
+        });
+
+    let _ = wiki.rename_entry(name, new_name).map_err_trace_exit_unwrap(1);
+    info!("Renamed '{}' to '{}'", name, new_name);
+}
+
+fn import(rt: &Runtime, wiki_name: &str) {
+    use libimagerror::trace::trace_error;
+    use libimagwiki::import::import_directory;
+
+    let scmd = rt.cli().subcommand_matches("import").unwrap(); // safed by clap
+    let dir  = ::std::path::PathBuf::from(scmd.value_of("import-dir").unwrap()); // safe by clap
+
+    if !dir.is_dir() {
+        error!("Not a directory: {}", dir.display());
+        ::std::process::exit(1)
+    }
+
+    let wiki   = rt.store().retrieve_wiki(&wiki_name).map_err_trace_exit_unwrap(1);
+    let report = import_directory(&wiki, &dir).map_err_trace_exit_unwrap(1);
+
+    let out         = rt.stdout();
+    let mut outlock = out.lock();
+
+    for name in report.imported.iter() {
+        let _ = writeln!(outlock, "Imported: {}", name).to_exit_code().unwrap_or_exit();
+    }
+
+    for name in report.skipped.iter() {
+        let _ = writeln!(outlock, "Skipped (exists): {}", name).to_exit_code().unwrap_or_exit();
+    }
+
+    for &(ref file, ref e) in report.failed.iter() {
+        warn!("Could not import {}", file.display());
+        trace_error(e);
+    }
+}
+
 fn delete(rt: &Runtime, wiki_name: &str) {
     use libimagentrylink::internal::InternalLinker;
 
@@ -147,6 +147,165 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                         .help("Name of the entry/entries to show (if not passed, all are shown)."))
                    )
 
+        .subcommand(SubCommand::with_name("edit")
+                   .about("Edit wiki entry, storing the old content as revision")
+                   .version("0.1")
+
+                   .arg(Arg::with_name("edit-name")
+                        .index(1)
+                        .takes_value(true)
+                        .required(true)
+                        .multiple(false)
+                        .value_name("NAME")
+                        .help("Name of the page."))
+
+                   .arg(Arg::with_name("edit-editheader")
+                        .long("header")
+                        .takes_value(false)
+                        .required(false)
+                        .multiple(false)
+                        .help("Do edit header when editing entry."))
+                   )
+
+        .subcommand(SubCommand::with_name("history")
+                   .about("List the revisions of a wiki entry")
+                   .version("0.1")
+
+                   .arg(Arg::with_name("history-name")
+                        .index(1)
+                        .takes_value(true)
+                        .required(true)
+                        .multiple(false)
+                        .value_name("NAME")
+                        .help("Name of the page."))
+                   )
+
+        .subcommand(SubCommand::with_name("diff")
+                   .about("Show the changes between a revision of a wiki entry and the current content or another revision")
+                   .version("0.1")
+
+                   .arg(Arg::with_name("diff-name")
+                        .index(1)
+                        .takes_value(true)
+                        .required(true)
+                        .multiple(false)
+                        .value_name("NAME")
+                        .help("Name of the page."))
+
+                   .arg(Arg::with_name("diff-revision")
+                        .index(2)
+                        .takes_value(true)
+                        .required(true)
+                        .multiple(false)
+                        .value_name("REV")
+                        .help("Number of the revision to compare"))
+
+                   .arg(Arg::with_name("diff-to-revision")
+                        .index(3)
+                        .takes_value(true)
+                        .required(false)
+                        .multiple(false)
+                        .value_name("REV")
+                        .help("Number of the revision to compare to. If not passed, the current content is used."))
+
+                   .arg(Arg::with_name("diff-context")
+                        .long("context")
+                        .short("C")
+                        .takes_value(true)
+                        .required(false)
+                        .multiple(false)
+                        .value_name("LINES")
+                        .help("Number of unchanged lines to show around changes (default: 3)"))
+                   )
+
+        .subcommand(SubCommand::with_name("revert")
+                   .about("Revert a wiki entry to a revision. The current content is stored as new revision.")
+                   .version("0.1")
+
+                   .arg(Arg::with_name("revert-name")
+                        .index(1)
+                        .takes_value(true)
+                        .required(true)
+                        .multiple(false)
+                        .value_name("NAME")
+                        .help("Name of the page."))
+
+                   .arg(Arg::with_name("revert-revision")
+                        .index(2)
+                        .takes_value(true)
+                        .required(true)
+                        .multiple(false)
+                        .value_name("REV")
+                        .help("Number of the revision to revert to"))
+                   )
+
+        .subcommand(SubCommand::with_name("backlinks")
+                   .about("List the pages which link to a wiki entry")
+                   .version("0.1")
+
+                   .arg(Arg::with_name("backlinks-name")
+                        .index(1)
+                        .takes_value(true)
+                        .required(true)
+                        .multiple(false)
+                        .value_name("NAME")
+                        .help("Name of the page."))
+                   )
+
+        .subcommand(SubCommand::with_name("orphans")
+                   .about("List the pages which are not linked from any other page")
+                   .version("0.1"))
+
+        .subcommand(SubCommand::with_name("check")
+                   .about("List links to pages which do not exist. Exits with 1 if there are any.")
+                   .version("0.1"))
+
+        .subcommand(SubCommand::with_name("export")
+                   .about("Export the wiki as static HTML site")
+                   .version("0.1")
+
+                   .arg(Arg::with_name("export-dir")
+                        .index(1)
+                        .takes_value(true)
+                        .required(true)
+                        .multiple(false)
+                        .value_name("DIR")
+                        .help("Directory to write the HTML files to. Is created if it does not exist."))
+                   )
+
+        .subcommand(SubCommand::with_name("mv")
+                   .about("Rename a wiki entry, rewriting the links to it in all entries of the wiki")
+                   .version("0.1")
+
+                   .arg(Arg::with_name("mv-name")
+                        .index(1)
+                        .takes_value(true)
+                        .required(true)
+                        .multiple(false)
+                        .value_name("NAME")
+                        .help("Name of the page."))
+
+                   .arg(Arg::with_name("mv-new-name")
+                        .index(2)
+                        .takes_value(true)
+                        .required(true)
+                        .multiple(false)
+                        .value_name("NEW_NAME")
+                        .help("New name of the page. Namespaces ('foo/bar') are allowed."))
+                   )
+
+        .subcommand(SubCommand::with_name("import")
+                   .about("Import a directory of Markdown notes (for example an Obsidian vault) into the wiki. The wiki is created if it does not exist.")
+                   .version("0.1")
+
+                   .arg(Arg::with_name("import-dir")
+                        .index(1)
+                        .takes_value(true)
+                        .required(true)
+                        .multiple(false)
+                        .value_name("DIR")
+                        .help("Directory with the Markdown files to import"))
+                   )
 
         .subcommand(SubCommand::with_name("delete")
                    .about("Delete wiki entry")
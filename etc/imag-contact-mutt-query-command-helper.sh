@@ -8,16 +8,10 @@
 #
 # in mutt to search contacts from mutt with imag.
 #
+# Note: This is not needed anymore, as `imag contact query` prints the format
+# mutt wants by itself:
+#
+#     query_command = "imag contact query --format mutt '%s'"
+#
 
-# mutt wants a one-line status message before the list of contacts.
-echo "Searching with imag ..."
-imag contact find "$1" --json | \
-jq --raw-output '
-    map(.fullname[0] as $FN
-    | .email
-    | map({email: ., fullname: $FN}))
-    | flatten
-    | map([.email.address, .fullname, .email.properties.TYPE])
-    | .[]
-    | @tsv
-'
+exec imag contact query --format mutt "$1"
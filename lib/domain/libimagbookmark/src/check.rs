@@ -0,0 +1,233 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Checking bookmarks for dead links
+//!
+//! A link is checked with a `HEAD` request (falling back to `GET` if the server does not support
+//! `HEAD`). Redirects are followed. A link is dead if the server answers with an error status,
+//! it is unreachable if no answer could be received at all.
+//!
+//! The actual checking is abstracted by the `LinkChecker` trait, so the checking logic can be
+//! replaced (for example in tests). The HTTP implementation, `HttpLinkChecker`, is only available
+//! with the `http-check` feature.
+
+#[cfg(feature = "http-check")] use std::time::Duration;
+
+#[cfg(feature = "http-check")] use reqwest::Client;
+#[cfg(feature = "http-check")] use reqwest::RedirectPolicy;
+use url::Url;
+
+#[cfg(feature = "http-check")] use error::Result;
+use metadata::Bookmark;
+
+/// The result of checking a link
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinkStatus {
+    /// The server answered with a success status, possibly after redirects
+    Alive(u16),
+
+    /// The server answered with an error status
+    Dead(u16),
+
+    /// The server could not be reached
+    Unreachable(String),
+}
+
+impl LinkStatus {
+
+    pub fn is_dead(&self) -> bool {
+        match *self {
+            LinkStatus::Alive(_) => false,
+            _                    => true,
+        }
+    }
+
+}
+
+/// Something which can check whether a link is alive
+pub trait LinkChecker {
+    fn check(&self, url: &Url) -> LinkStatus;
+}
+
+/// A `LinkChecker` which checks links via HTTP
+#[cfg(feature = "http-check")]
+pub struct HttpLinkChecker {
+    client: Client,
+}
+
+#[cfg(feature = "http-check")]
+impl HttpLinkChecker {
+
+    /// Create a new checker, which waits at most `timeout` for an answer
+    pub fn new(timeout: Duration) -> Result<HttpLinkChecker> {
+        Client::builder()
+            .timeout(timeout)
+            .redirect(RedirectPolicy::limited(5))
+            .build()
+            .map(|client| HttpLinkChecker { client: client })
+            .map_err(From::from)
+    }
+
+}
+
+#[cfg(feature = "http-check")]
+impl LinkChecker for HttpLinkChecker {
+
+    fn check(&self, url: &Url) -> LinkStatus {
+        let status = match self.client.head(url.clone()).send() {
+            Ok(response) => match response.status().as_u16() {
+                // Method not allowed or not implemented: the server does not support HEAD
+                405 | 501 => self.client.get(url.clone()).send().map(|r| r.status()),
+                _         => Ok(response.status()),
+            },
+            Err(e) => Err(e),
+        };
+
+        match status {
+            Ok(s) => if s.is_success() || s.is_redirection() {
+                LinkStatus::Alive(s.as_u16())
+            } else {
+                LinkStatus::Dead(s.as_u16())
+            },
+            Err(e) => LinkStatus::Unreachable(format!("{}", e)),
+        }
+    }
+
+}
+
+/// Check all `bookmarks` with `checker`
+///
+/// Bookmarks with an URL which cannot be parsed are reported as unreachable.
+pub fn check_bookmarks<'a, C>(checker: &C, bookmarks: &'a [Bookmark]) -> Vec<(&'a Bookmark, LinkStatus)>
+    where C: LinkChecker
+{
+    bookmarks
+        .iter()
+        .map(|bookmark| {
+            let status = match Url::parse(&bookmark.url) {
+                Ok(url) => checker.check(&url),
+                Err(e)  => LinkStatus::Unreachable(format!("{}", e)),
+            };
+            (bookmark, status)
+        })
+        .collect()
+}
+
+#[cfg(all(test, feature = "http-check"))]
+mod test {
+    use std::io::BufRead;
+    use std::io::BufReader;
+    use std::io::Write;
+    use std::net::TcpListener;
+    use std::thread;
+    use std::time::Duration;
+
+    use url::Url;
+
+    use super::*;
+    use metadata::Bookmark;
+
+    /// Serve a few fixed paths on a local port, as a stand-in for a real web server
+    fn serve() -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port     = listener.local_addr().unwrap().port();
+
+        let _ = thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = match stream {
+                    Ok(s)  => s,
+                    Err(_) => continue,
+                };
+
+                let mut request_line = String::new();
+                {
+                    let mut reader = BufReader::new(&stream);
+                    let _ = reader.read_line(&mut request_line);
+
+                    // Skip the headers of the request
+                    let mut line = String::new();
+                    while reader.read_line(&mut line).map(|n| n > 2).unwrap_or(false) {
+                        line.clear();
+                    }
+                }
+
+                let path = request_line.split_whitespace().nth(1).unwrap_or("/");
+                let head = match path {
+                    "/ok"    => String::from("200 OK"),
+                    "/moved" => format!("301 Moved Permanently\r\nLocation: http://127.0.0.1:{}/ok", port),
+                    _        => String::from("404 Not Found"),
+                };
+
+                let _ = write!(stream,
+                               "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                               head);
+            }
+        });
+
+        port
+    }
+
+    fn checker() -> HttpLinkChecker {
+        HttpLinkChecker::new(Duration::from_secs(5)).unwrap()
+    }
+
+    fn url(port: u16, path: &str) -> Url {
+        Url::parse(&format!("http://127.0.0.1:{}{}", port, path)).unwrap()
+    }
+
+    #[test]
+    fn test_alive_and_dead() {
+        let port    = serve();
+        let checker = checker();
+
+        assert_eq!(checker.check(&url(port, "/ok")), LinkStatus::Alive(200));
+        assert_eq!(checker.check(&url(port, "/gone")), LinkStatus::Dead(404));
+        assert_eq!(checker.check(&url(port, "/moved")), LinkStatus::Alive(200));
+    }
+
+    #[test]
+    fn test_unreachable() {
+        // Bind and drop a listener to get a port nobody listens on
+        let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+
+        match checker().check(&url(port, "/ok")) {
+            LinkStatus::Unreachable(_) => {},
+            other                      => panic!("Expected unreachable, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_check_bookmarks() {
+        let port      = serve();
+        let bookmarks = vec![
+            Bookmark::new(url(port, "/ok").into_string()),
+            Bookmark::new(url(port, "/gone").into_string()),
+            Bookmark::new(String::from("not an url")),
+        ];
+
+        let dead = check_bookmarks(&checker(), &bookmarks)
+            .into_iter()
+            .filter(|&(_, ref status)| status.is_dead())
+            .map(|(bm, _)| bm.url.clone())
+            .collect::<Vec<_>>();
+
+        assert_eq!(dead, vec![bookmarks[1].url.clone(), bookmarks[2].url.clone()]);
+    }
+
+}
@@ -39,6 +39,8 @@ use libimagentrylink::internal::InternalLinker;
 use libimagentrylink::internal::Link as StoreLink;
 
 use link::Link;
+use metadata::Bookmark;
+use metadata::write_metadata;
 
 use self::iter::LinksMatchingRegexIter;
 
@@ -46,6 +48,7 @@ pub trait BookmarkCollectionStore<'a> {
     fn new(&'a self, name: &str)                     -> Result<FileLockEntry<'a>>;
     fn get(&'a self, name: &str)                     -> Result<Option<FileLockEntry<'a>>>;
     fn delete(&'a self, name: &str)                     -> Result<()>;
+    fn collection_names(&'a self)                       -> Result<Vec<String>>;
 }
 
 impl<'a> BookmarkCollectionStore<'a> for Store {
@@ -71,12 +74,29 @@ impl<'a> BookmarkCollectionStore<'a> for Store {
             .map_err(From::from)
     }
 
+    /// Get the names of all collections, sorted
+    fn collection_names(&'a self) -> Result<Vec<String>> {
+        let mut names = vec![];
+        for id in self.entries()? {
+            let id = id?;
+            if id.is_in_collection(&["bookmark"]) {
+                if let Ok(name) = id.local().strip_prefix("bookmark") {
+                    names.push(name.display().to_string());
+                }
+            }
+        }
+
+        names.sort();
+        Ok(names)
+    }
+
 }
 
 pub trait BookmarkCollection : Sized + InternalLinker + ExternalLinker {
     fn links<'a>(&self, store: &'a Store)                        -> Result<UrlIter<'a>>;
     fn link_entries(&self)                                   -> Result<Vec<StoreLink>>;
     fn add_link(&mut self, store: &Store, l: Link)           -> Result<()>;
+    fn add_bookmark(&mut self, store: &Store, b: &Bookmark)  -> Result<()>;
     fn get_links_matching<'a>(&self, store: &'a Store, r: Regex) -> Result<LinksMatchingRegexIter<'a>>;
     fn remove_link(&mut self, store: &Store, l: Link)        -> Result<()>;
 }
@@ -103,6 +123,15 @@ impl BookmarkCollection for Entry {
             .map_err(From::from)
     }
 
+    /// Add the link of `b` and write its metadata (title, description, tags, ...)
+    fn add_bookmark(&mut self, store: &Store, b: &Bookmark) -> Result<()> {
+        use link::IntoUrl;
+
+        let url = Link::from(b.url.as_str()).into_url()?;
+        let _   = self.add_external_link(store, url.clone())?;
+        write_metadata(store, &url, b)
+    }
+
     fn get_links_matching<'a>(&self, store: &'a Store, r: Regex) -> Result<LinksMatchingRegexIter<'a>> {
         use self::iter::IntoLinksMatchingRegexIter;
 
@@ -25,6 +25,14 @@ error_chain! {
     links {
         StoreError(::libimagstore::error::StoreError, ::libimagstore::error::StoreErrorKind);
         LinkError(::libimagentrylink::error::LinkError, ::libimagentrylink::error::LinkErrorKind);
+        TagError(::libimagentrytag::error::TagError, ::libimagentrytag::error::TagErrorKind);
+    }
+
+    foreign_links {
+        TomlQueryError(::toml_query::error::Error);
+        JsonError(::serde_json::Error);
+        ChronoParseError(::chrono::format::ParseError);
+        ReqwestError(::reqwest::Error) #[cfg(feature = "http-check")];
     }
 
     errors {
@@ -0,0 +1,142 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Exporting bookmarks
+//!
+//! Collections are exported to the Netscape bookmark file format, which can be imported by all
+//! browsers. Nested collections become nested folders.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use libimagutil::html::escape_html;
+
+use metadata::Bookmark;
+
+/// A folder in the exported bookmark file
+#[derive(Default)]
+struct Folder<'a> {
+    bookmarks: Vec<&'a Bookmark>,
+    children: BTreeMap<&'a str, Folder<'a>>,
+}
+
+/// Render bookmarks as Netscape bookmark file
+pub fn to_netscape_html(bookmarks: &[Bookmark]) -> String {
+    let mut root = Folder::default();
+
+    for bookmark in bookmarks {
+        let mut folder = &mut root;
+        for name in bookmark.folders.iter() {
+            folder = { folder }.children.entry(name.as_str()).or_insert_with(Folder::default);
+        }
+        folder.bookmarks.push(bookmark);
+    }
+
+    let mut html = String::from("<!DOCTYPE NETSCAPE-Bookmark-file-1>\n\
+        <!-- This is an automatically generated file. It will be read and overwritten. DO NOT EDIT! -->\n\
+        <META HTTP-EQUIV=\"Content-Type\" CONTENT=\"text/html; charset=UTF-8\">\n\
+        <TITLE>Bookmarks</TITLE>\n\
+        <H1>Bookmarks</H1>\n");
+
+    render_folder(&mut html, &root, 0);
+    html
+}
+
+fn render_folder(html: &mut String, folder: &Folder, depth: usize) {
+    let indent = "    ".repeat(depth);
+    let _ = writeln!(html, "{}<DL><p>", indent);
+
+    for bookmark in folder.bookmarks.iter() {
+        let tags = if bookmark.tags.is_empty() {
+            String::new()
+        } else {
+            format!(" TAGS=\"{}\"", escape_html(&bookmark.tags.join(",")))
+        };
+
+        let added = bookmark
+            .added
+            .map(|added| format!(" ADD_DATE=\"{}\"", added.timestamp()))
+            .unwrap_or_else(String::new);
+
+        let _ = writeln!(html,
+                         "{}    <DT><A HREF=\"{}\"{}{}>{}</A>",
+                         indent,
+                         escape_html(&bookmark.url),
+                         added,
+                         tags,
+                         escape_html(bookmark.title.as_ref().unwrap_or(&bookmark.url)));
+
+        if let Some(ref description) = bookmark.description {
+            let _ = writeln!(html, "{}    <DD>{}", indent, escape_html(description));
+        }
+    }
+
+    for (name, child) in folder.children.iter() {
+        let _ = writeln!(html, "{}    <DT><H3>{}</H3>", indent, escape_html(name));
+        render_folder(html, child, depth + 1);
+    }
+
+    let _ = writeln!(html, "{}</DL><p>", indent);
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::NaiveDateTime;
+
+    use super::to_netscape_html;
+    use metadata::Bookmark;
+    use import::parse_netscape_html;
+
+    #[test]
+    fn test_roundtrip() {
+        let bookmarks = vec![
+            Bookmark {
+                url: String::from("https://imag-pim.org/"),
+                title: Some(String::from("imag \"PIM\" & more")),
+                description: Some(String::from("The <imag> website")),
+                added: Some(NaiveDateTime::from_timestamp(1514764800, 0)),
+                tags: vec![String::from("pim"), String::from("rust")],
+                folders: vec![],
+            },
+            Bookmark {
+                url: String::from("https://crates.io/"),
+                title: Some(String::from("crates.io")),
+                description: None,
+                added: None,
+                tags: vec![],
+                folders: vec![String::from("dev"), String::from("rust")],
+            },
+            Bookmark {
+                url: String::from("https://doc.rust-lang.org/"),
+                title: Some(String::from("docs")),
+                description: None,
+                added: None,
+                tags: vec![],
+                folders: vec![String::from("dev")],
+            },
+        ];
+
+        let parsed = parse_netscape_html(&to_netscape_html(&bookmarks));
+        assert_eq!(parsed.len(), 3);
+        assert_eq!(parsed[0], bookmarks[0]);
+        assert_eq!(parsed[1], bookmarks[2]);
+        assert_eq!(parsed[2], bookmarks[1]);
+    }
+
+}
@@ -0,0 +1,493 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Importing bookmarks
+//!
+//! Bookmarks can be imported from Netscape bookmark files (the HTML format all browsers export
+//! to), Firefox JSON backups and plain lists of URLs. Folders become collections (nested folders
+//! nested collections), tags become imag tags on the bookmark.
+
+use std::collections::BTreeMap;
+
+use chrono::NaiveDateTime;
+use serde_json::Value as JsonValue;
+use url::Url;
+
+use libimagstore::store::Store;
+use libimagstore::storeid::IntoStoreId;
+use libimagentrylink::external::ExternalLinker;
+use libimagentrytag::tag::sanitize_tags;
+
+use collection::BookmarkCollection;
+use metadata::Bookmark;
+use metadata::write_metadata;
+use module_path::ModuleEntryPath;
+use error::Result;
+
+/// The result of an import
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    /// The number of bookmarks which were added to a collection
+    pub imported: usize,
+
+    /// The number of bookmarks which were already in their collection
+    pub skipped: usize,
+
+    /// The URLs which could not be parsed
+    pub invalid: Vec<String>,
+}
+
+/// Parse a Netscape bookmark file
+pub fn parse_netscape_html(html: &str) -> Vec<Bookmark> {
+    let mut bookmarks = vec![];
+    let mut folders : Vec<Option<String>> = vec![];
+    let mut pending : Option<String>      = None;
+    let mut in_link = false;
+    let mut rest    = html;
+
+    while let Some(start) = rest.find('<') {
+        let end = match rest[start..].find('>') {
+            Some(end) => start + end,
+            None      => break,
+        };
+
+        let tag = &rest[start + 1..end];
+        rest    = &rest[end + 1..];
+
+        match tag_name(tag).as_str() {
+            "h3" => {
+                let (text, r) = element_text(rest, "</h3");
+                pending = Some(text);
+                in_link = false;
+                rest    = r;
+            },
+            "dd" => {
+                // The description of the preceding link, up to the next tag. Descriptions of
+                // folders are ignored.
+                let end  = rest.find('<').unwrap_or_else(|| rest.len());
+                let text = decode_entities(rest[..end].trim());
+                rest     = &rest[end..];
+
+                if in_link && !text.is_empty() {
+                    if let Some(last) = bookmarks.last_mut() {
+                        last.description = Some(text);
+                    }
+                }
+            },
+            "dl"  => {
+                folders.push(pending.take());
+                in_link = false;
+            },
+            "/dl" => {
+                let _ = folders.pop();
+                in_link = false;
+            },
+            "a"   => {
+                let attrs     = parse_attributes(tag);
+                let (text, r) = element_text(rest, "</a");
+                rest          = r;
+
+                in_link = false;
+                if let Some(href) = attribute(&attrs, "href") {
+                    let added = attribute(&attrs, "add_date")
+                        .and_then(|d| d.parse::<i64>().ok())
+                        .and_then(|secs| NaiveDateTime::from_timestamp_opt(secs, 0));
+
+                    bookmarks.push(Bookmark {
+                        url: String::from(href),
+                        title: if text.is_empty() { None } else { Some(text) },
+                        description: None,
+                        added: added,
+                        tags: attribute(&attrs, "tags").map(split_tags).unwrap_or_else(Vec::new),
+                        folders: folders.iter().filter_map(Clone::clone).collect(),
+                    });
+                    in_link = true;
+                }
+            },
+            _ => {},
+        }
+    }
+
+    bookmarks
+}
+
+/// Parse a Firefox bookmark backup (JSON)
+///
+/// The "Tags" folder of Firefox is skipped, as it only contains the tagged bookmarks a second
+/// time. Queries ("place:" URIs) are skipped as well.
+pub fn parse_firefox_json(json: &str) -> Result<Vec<Bookmark>> {
+    let root : JsonValue = ::serde_json::from_str(json)?;
+    let mut bookmarks    = vec![];
+    walk_firefox_node(&root, &mut vec![], &mut bookmarks);
+    Ok(bookmarks)
+}
+
+fn walk_firefox_node(node: &JsonValue, folders: &mut Vec<String>, out: &mut Vec<Bookmark>) {
+    let get_str = |key: &str| node.get(key).and_then(JsonValue::as_str);
+
+    match get_str("type") {
+        Some("text/x-moz-place-container") => {
+            let root = get_str("root");
+            if root == Some("tagsFolder") {
+                return;
+            }
+
+            let title  = get_str("title").unwrap_or("").trim();
+            let pushed = !title.is_empty() && root != Some("placesRoot");
+            if pushed {
+                folders.push(String::from(title));
+            }
+
+            if let Some(children) = node.get("children").and_then(JsonValue::as_array) {
+                for child in children {
+                    walk_firefox_node(child, folders, out);
+                }
+            }
+
+            if pushed {
+                let _ = folders.pop();
+            }
+        },
+        Some("text/x-moz-place") => {
+            if let Some(uri) = get_str("uri") {
+                if uri.starts_with("place:") {
+                    return;
+                }
+
+                let title = get_str("title").map(str::trim).unwrap_or("");
+                let added = node
+                    .get("dateAdded")
+                    .and_then(JsonValue::as_i64)
+                    .and_then(|usecs| NaiveDateTime::from_timestamp_opt(usecs / 1_000_000, 0));
+
+                out.push(Bookmark {
+                    url: String::from(uri),
+                    title: if title.is_empty() { None } else { Some(String::from(title)) },
+                    description: None,
+                    added: added,
+                    tags: get_str("tags").map(split_tags).unwrap_or_else(Vec::new),
+                    folders: folders.clone(),
+                });
+            }
+        },
+        _ => {},
+    }
+}
+
+/// Parse a list of URLs, one per line
+///
+/// Everything after the URL on a line is used as title. Empty lines and lines starting with a
+/// `#` are ignored.
+pub fn parse_url_list(text: &str) -> Vec<Bookmark> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let mut words = line.split_whitespace();
+            let url       = words.next().map(String::from).unwrap_or_default(); // line is not empty
+            let title     = words.collect::<Vec<_>>().join(" ");
+
+            let mut bookmark = Bookmark::new(url);
+            if !title.is_empty() {
+                bookmark.title = Some(title);
+            }
+            bookmark
+        })
+        .collect()
+}
+
+/// Get the name of the collection for a bookmark in `folders`
+///
+/// Whitespace in folder names is replaced by underscores, slashes by dashes. Bookmarks which are
+/// not in a folder go to the `default` collection.
+pub fn collection_name(folders: &[String], default: &str) -> String {
+    let parts = folders
+        .iter()
+        .map(|f| f.split_whitespace().collect::<Vec<_>>().join("_").replace('/', "-"))
+        .filter(|f| !f.is_empty())
+        .collect::<Vec<_>>();
+
+    if parts.is_empty() {
+        String::from(default)
+    } else {
+        parts.join("/")
+    }
+}
+
+/// Import bookmarks into the store
+///
+/// Bookmarks which are already in their collection are skipped, their metadata is not changed.
+pub fn import_bookmarks(store: &Store, bookmarks: Vec<Bookmark>, default_collection: &str)
+    -> Result<ImportReport>
+{
+    let mut report = ImportReport::default();
+    let mut by_collection : BTreeMap<String, Vec<(Url, Bookmark)>> = BTreeMap::new();
+
+    for bookmark in bookmarks {
+        match Url::parse(&bookmark.url) {
+            Ok(url) => by_collection
+                .entry(collection_name(&bookmark.folders, default_collection))
+                .or_insert_with(Vec::new)
+                .push((url, bookmark)),
+            Err(e)  => {
+                warn!("Cannot parse '{}' as URL: {}", bookmark.url, e);
+                report.invalid.push(bookmark.url);
+            },
+        }
+    }
+
+    for (name, bookmarks) in by_collection {
+        debug!("Importing {} bookmarks into collection '{}'", bookmarks.len(), name);
+        let mut collection = store.retrieve(ModuleEntryPath::new(name.as_str()).into_storeid()?)?;
+        let mut urls       = collection.links(store)?.collect::<::std::result::Result<Vec<Url>, _>>()?;
+        let mut new        = vec![];
+
+        for (url, bookmark) in bookmarks {
+            if urls.contains(&url) {
+                report.skipped += 1;
+            } else {
+                urls.push(url.clone());
+                new.push((url, bookmark));
+            }
+        }
+
+        if new.is_empty() {
+            continue;
+        }
+
+        let _ = collection.set_external_links(store, urls)?;
+
+        for (url, mut bookmark) in new {
+            bookmark.tags = sanitize_tags(&bookmark.tags);
+            let _ = write_metadata(store, &url, &bookmark)?;
+            report.imported += 1;
+        }
+    }
+
+    Ok(report)
+}
+
+fn split_tags(tags: &str) -> Vec<String> {
+    tags.split(',')
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// The lowercased name of a tag, including a leading slash for closing tags
+fn tag_name(tag: &str) -> String {
+    tag.trim()
+        .chars()
+        .take_while(|c| !c.is_whitespace())
+        .collect::<String>()
+        .to_ascii_lowercase()
+}
+
+/// Get the text up to the closing tag `close` (lowercase, without the ">") and the rest of the
+/// document after the closing tag
+fn element_text<'a>(rest: &'a str, close: &str) -> (String, &'a str) {
+    match find_ignore_case(rest, close) {
+        Some(pos) => {
+            let text  = decode_entities(rest[..pos].trim());
+            let after = &rest[pos..];
+            let rest  = after.find('>').map(|gt| &after[gt + 1..]).unwrap_or("");
+            (text, rest)
+        },
+        None => (decode_entities(rest.trim()), ""),
+    }
+}
+
+/// Find `needle` (which has to start with a "<") in `haystack`, ignoring ASCII case
+fn find_ignore_case(haystack: &str, needle: &str) -> Option<usize> {
+    let bytes = haystack.as_bytes();
+    let n     = needle.len();
+
+    haystack
+        .match_indices('<')
+        .map(|(i, _)| i)
+        .find(|&i| i + n <= bytes.len() && bytes[i..i + n].eq_ignore_ascii_case(needle.as_bytes()))
+}
+
+/// Parse the attributes of a tag (`A HREF="..." TAGS="a,b"`), names are lowercased
+fn parse_attributes(tag: &str) -> Vec<(String, String)> {
+    let mut attrs = vec![];
+    let mut chars = tag.trim().chars().skip_while(|c| !c.is_whitespace()).peekable();
+
+    loop {
+        while chars.peek().map(|c| c.is_whitespace()).unwrap_or(false) {
+            let _ = chars.next();
+        }
+
+        let name = {
+            let mut name = String::new();
+            while let Some(&c) = chars.peek() {
+                if c == '=' || c.is_whitespace() {
+                    break;
+                }
+                name.push(c);
+                let _ = chars.next();
+            }
+            name.to_ascii_lowercase()
+        };
+
+        if name.is_empty() {
+            break;
+        }
+
+        while chars.peek().map(|c| c.is_whitespace()).unwrap_or(false) {
+            let _ = chars.next();
+        }
+
+        let mut value = String::new();
+        if chars.peek() == Some(&'=') {
+            let _ = chars.next();
+            while chars.peek().map(|c| c.is_whitespace()).unwrap_or(false) {
+                let _ = chars.next();
+            }
+
+            match chars.peek().cloned() {
+                Some(quote) if quote == '"' || quote == '\'' => {
+                    let _ = chars.next();
+                    for c in chars.by_ref() {
+                        if c == quote {
+                            break;
+                        }
+                        value.push(c);
+                    }
+                },
+                _ => {
+                    while let Some(&c) = chars.peek() {
+                        if c.is_whitespace() {
+                            break;
+                        }
+                        value.push(c);
+                        let _ = chars.next();
+                    }
+                },
+            }
+        }
+
+        attrs.push((name, decode_entities(&value)));
+    }
+
+    attrs
+}
+
+fn attribute<'a>(attrs: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    attrs.iter().find(|&&(ref n, _)| n == name).map(|&(_, ref v)| v.as_str())
+}
+
+fn decode_entities(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use metadata::Bookmark;
+
+    const NETSCAPE : &'static str = r#"<!DOCTYPE NETSCAPE-Bookmark-file-1>
+<META HTTP-EQUIV="Content-Type" CONTENT="text/html; charset=UTF-8">
+<TITLE>Bookmarks</TITLE>
+<H1>Bookmarks</H1>
+<DL><p>
+    <DT><A HREF="https://imag-pim.org/" ADD_DATE="1514764800" TAGS="pim,rust">imag</A>
+    <DD>The imag website
+    <DT><H3 ADD_DATE="1514764800">Development Tools</H3>
+    <DL><p>
+        <DT><A HREF="https://doc.rust-lang.org/std/?search=a&amp;b">Rust &amp; std</A>
+        <DT><H3>Nested</H3>
+        <DL><p>
+            <DT><A HREF='https://crates.io/'>crates.io</A>
+        </DL><p>
+    </DL><p>
+    <DT><A HREF="https://example.com/"></A>
+</DL><p>
+"#;
+
+    #[test]
+    fn test_parse_netscape_html() {
+        let bms = parse_netscape_html(NETSCAPE);
+        assert_eq!(bms.len(), 4);
+
+        assert_eq!(bms[0].url, "https://imag-pim.org/");
+        assert_eq!(bms[0].title, Some(String::from("imag")));
+        assert_eq!(bms[0].tags, vec!["pim", "rust"]);
+        assert_eq!(bms[0].description, Some(String::from("The imag website")));
+        assert_eq!(bms[0].added, Some(NaiveDateTime::from_timestamp(1514764800, 0)));
+        assert!(bms[0].folders.is_empty());
+
+        assert_eq!(bms[1].url, "https://doc.rust-lang.org/std/?search=a&b");
+        assert_eq!(bms[1].title, Some(String::from("Rust & std")));
+        assert_eq!(bms[1].folders, vec!["Development Tools"]);
+        assert_eq!(bms[1].description, None);
+
+        assert_eq!(bms[2].url, "https://crates.io/");
+        assert_eq!(bms[2].folders, vec!["Development Tools", "Nested"]);
+
+        assert_eq!(bms[3].title, None);
+        assert!(bms[3].folders.is_empty());
+    }
+
+    #[test]
+    fn test_parse_firefox_json() {
+        let json = r#"{"title":"","type":"text/x-moz-place-container","root":"placesRoot","children":[
+            {"title":"Bookmarks Toolbar","type":"text/x-moz-place-container","root":"toolbarFolder","children":[
+                {"title":"imag","type":"text/x-moz-place","uri":"https://imag-pim.org/","tags":"pim, rust","dateAdded":1514764800000000},
+                {"title":"Most Visited","type":"text/x-moz-place","uri":"place:sort=8&maxResults=10"}
+            ]},
+            {"title":"Tags","type":"text/x-moz-place-container","root":"tagsFolder","children":[
+                {"title":"pim","type":"text/x-moz-place-container","children":[
+                    {"title":"imag","type":"text/x-moz-place","uri":"https://imag-pim.org/"}
+                ]}
+            ]}
+        ]}"#;
+
+        let bms = parse_firefox_json(json).unwrap();
+        assert_eq!(bms, vec![Bookmark {
+            url: String::from("https://imag-pim.org/"),
+            title: Some(String::from("imag")),
+            description: None,
+            added: Some(NaiveDateTime::from_timestamp(1514764800, 0)),
+            tags: vec![String::from("pim"), String::from("rust")],
+            folders: vec![String::from("Bookmarks Toolbar")],
+        }]);
+    }
+
+    #[test]
+    fn test_parse_url_list() {
+        let bms = parse_url_list("# my links\nhttps://imag-pim.org/ imag website\n\nhttps://crates.io/\n");
+        assert_eq!(bms.len(), 2);
+        assert_eq!(bms[0].title, Some(String::from("imag website")));
+        assert_eq!(bms[1].url, "https://crates.io/");
+        assert_eq!(bms[1].title, None);
+    }
+
+    #[test]
+    fn test_collection_name() {
+        let folders = vec![String::from("Bookmarks Toolbar"), String::from("a/b")];
+        assert_eq!(collection_name(&folders, "default"), "Bookmarks_Toolbar/a-b");
+        assert_eq!(collection_name(&[], "default"), "default");
+    }
+}
@@ -35,16 +35,29 @@
     while_true,
 )]
 
+#[macro_use] extern crate log;
 extern crate url;
 extern crate regex;
+extern crate toml;
+extern crate toml_query;
+extern crate serde_json;
+extern crate chrono;
+#[cfg(feature = "http-check")] extern crate reqwest;
 #[macro_use] extern crate error_chain;
 
 #[macro_use] extern crate libimagstore;
 extern crate libimagerror;
 extern crate libimagentrylink;
+extern crate libimagentrytag;
+extern crate libimagutil;
 
 module_entry_path_mod!("bookmark");
 
+pub mod check;
 pub mod collection;
 pub mod error;
+pub mod export;
+pub mod import;
 pub mod link;
+pub mod metadata;
+pub mod search;
@@ -0,0 +1,196 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Metadata of bookmarks
+//!
+//! Bookmarks are external links, which are represented by entries in `links/external/` in the
+//! store. The metadata of a bookmark (title, description and the date it was added) is stored in
+//! the header of this entry, its tags are imag tags on this entry.
+
+use chrono::NaiveDateTime;
+use toml::Value;
+use toml_query::insert::TomlValueInsertExt;
+use toml_query::read::TomlValueReadTypeExt;
+use url::Url;
+
+use libimagstore::store::Entry;
+use libimagstore::store::Store;
+use libimagentrylink::external::external_link_store_id;
+use libimagentrytag::tagable::Tagable;
+
+use collection::BookmarkCollection;
+use collection::BookmarkCollectionStore;
+use error::BookmarkErrorKind as BEK;
+use error::BookmarkError as BE;
+use error::Result;
+
+/// The format the date a bookmark was added is stored in
+pub const ADDED_DATE_FORMAT : &'static str = "%Y-%m-%dT%H:%M:%S";
+
+/// A bookmark with its metadata
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bookmark {
+    pub url: String,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub added: Option<NaiveDateTime>,
+    pub tags: Vec<String>,
+
+    /// The folders (collections) the bookmark is in, outermost first
+    pub folders: Vec<String>,
+}
+
+impl Bookmark {
+
+    /// A bookmark for `url` without any metadata
+    pub fn new(url: String) -> Bookmark {
+        Bookmark {
+            url: url,
+            title: None,
+            description: None,
+            added: None,
+            tags: vec![],
+            folders: vec![],
+        }
+    }
+
+}
+
+/// Metadata of a bookmark, to be implemented on the entry of an external link
+pub trait BookmarkMetadata {
+    fn get_bookmark_title(&self) -> Result<Option<String>>;
+    fn set_bookmark_title(&mut self, title: &str) -> Result<()>;
+
+    fn get_bookmark_description(&self) -> Result<Option<String>>;
+    fn set_bookmark_description(&mut self, description: &str) -> Result<()>;
+
+    fn get_bookmark_added(&self) -> Result<Option<NaiveDateTime>>;
+    fn set_bookmark_added(&mut self, added: &NaiveDateTime) -> Result<()>;
+}
+
+impl BookmarkMetadata for Entry {
+
+    fn get_bookmark_title(&self) -> Result<Option<String>> {
+        self.get_header().read_string("bookmark.title").map_err(From::from)
+    }
+
+    fn set_bookmark_title(&mut self, title: &str) -> Result<()> {
+        self.get_header_mut()
+            .insert("bookmark.title", Value::String(String::from(title)))
+            .map(|_| ())
+            .map_err(From::from)
+    }
+
+    fn get_bookmark_description(&self) -> Result<Option<String>> {
+        self.get_header().read_string("bookmark.description").map_err(From::from)
+    }
+
+    fn set_bookmark_description(&mut self, description: &str) -> Result<()> {
+        self.get_header_mut()
+            .insert("bookmark.description", Value::String(String::from(description)))
+            .map(|_| ())
+            .map_err(From::from)
+    }
+
+    fn get_bookmark_added(&self) -> Result<Option<NaiveDateTime>> {
+        match self.get_header().read_string("bookmark.added")? {
+            Some(s) => NaiveDateTime::parse_from_str(&s, ADDED_DATE_FORMAT).map(Some).map_err(From::from),
+            None    => Ok(None),
+        }
+    }
+
+    fn set_bookmark_added(&mut self, added: &NaiveDateTime) -> Result<()> {
+        let s = added.format(ADDED_DATE_FORMAT).to_string();
+        self.get_header_mut()
+            .insert("bookmark.added", Value::String(s))
+            .map(|_| ())
+            .map_err(From::from)
+    }
+
+}
+
+/// Write the metadata of `bookmark` to the entry of its link
+///
+/// Title and description are only written if they are set, tags are added to the tags the
+/// bookmark has already. If the bookmark has no date it was added, the current time is used.
+pub fn write_metadata(store: &Store, url: &Url, bookmark: &Bookmark) -> Result<()> {
+    let mut entry = store.retrieve(external_link_store_id(url)?)?;
+
+    if let Some(ref title) = bookmark.title {
+        let _ = entry.set_bookmark_title(title)?;
+    }
+
+    if let Some(ref description) = bookmark.description {
+        let _ = entry.set_bookmark_description(description)?;
+    }
+
+    match bookmark.added {
+        Some(ref added) => { let _ = entry.set_bookmark_added(added)?; },
+        None            => if entry.get_bookmark_added()?.is_none() {
+            let now = ::chrono::offset::Local::now().naive_local();
+            let _   = entry.set_bookmark_added(&now)?;
+        },
+    }
+
+    for tag in bookmark.tags.iter() {
+        let _ = entry.add_tag(tag.clone())?;
+    }
+
+    Ok(())
+}
+
+/// Read the metadata of the bookmark for `url`
+pub fn read_metadata(store: &Store, url: &Url) -> Result<Bookmark> {
+    let mut bookmark = Bookmark::new(String::from(url.as_str()));
+
+    if let Some(entry) = store.get(external_link_store_id(url)?)? {
+        bookmark.title       = entry.get_bookmark_title()?;
+        bookmark.description = entry.get_bookmark_description()?;
+        bookmark.added       = entry.get_bookmark_added()?;
+        bookmark.tags        = entry.get_tags()?;
+    }
+
+    Ok(bookmark)
+}
+
+/// Get the bookmarks of the collections `names`, with their metadata
+///
+/// The folders of each bookmark are the components of the name of its collection.
+pub fn collect_bookmarks(store: &Store, names: &[String]) -> Result<Vec<Bookmark>> {
+    let mut bookmarks = vec![];
+
+    for name in names {
+        let collection = BookmarkCollectionStore::get(store, name)?
+            .ok_or_else(|| BE::from_kind(BEK::CollectionNotFound))?;
+
+        let folders = name
+            .split('/')
+            .filter(|f| !f.is_empty())
+            .map(String::from)
+            .collect::<Vec<_>>();
+
+        for url in collection.links(store)? {
+            let mut bookmark = read_metadata(store, &url?)?;
+            bookmark.folders = folders.clone();
+            bookmarks.push(bookmark);
+        }
+    }
+
+    Ok(bookmarks)
+}
@@ -0,0 +1,85 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Searching bookmarks
+//!
+//! Bookmarks are searched by their URL, title and description. The search is a case-insensitive
+//! substring search, which can be narrowed down to bookmarks with certain tags.
+
+use libimagstore::store::Store;
+
+use error::Result;
+use metadata::Bookmark;
+use metadata::collect_bookmarks;
+
+/// Check whether `bookmark` matches `query` and has all of `tags`
+///
+/// An empty query matches all bookmarks.
+pub fn matches(bookmark: &Bookmark, query: &str, tags: &[String]) -> bool {
+    let query    = query.to_lowercase();
+    let contains = |s: &str| s.to_lowercase().contains(&query);
+
+    let text_matches = query.is_empty()
+        || contains(&bookmark.url)
+        || bookmark.title.as_ref().map(|t| contains(t)).unwrap_or(false)
+        || bookmark.description.as_ref().map(|d| contains(d)).unwrap_or(false);
+
+    text_matches && tags.iter().all(|tag| bookmark.tags.contains(tag))
+}
+
+/// Search the bookmarks in the collections `names` for `query`
+///
+/// See `matches()`.
+pub fn search(store: &Store, names: &[String], query: &str, tags: &[String]) -> Result<Vec<Bookmark>> {
+    collect_bookmarks(store, names)
+        .map(|bms| bms.into_iter().filter(|bm| matches(bm, query, tags)).collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::matches;
+    use metadata::Bookmark;
+
+    fn bookmark() -> Bookmark {
+        let mut bm     = Bookmark::new(String::from("https://imag-pim.org/"));
+        bm.title       = Some(String::from("imag"));
+        bm.description = Some(String::from("The personal information management suite"));
+        bm.tags        = vec![String::from("pim"), String::from("rust")];
+        bm
+    }
+
+    #[test]
+    fn test_matches_fields() {
+        let bm = bookmark();
+        assert!(matches(&bm, "", &[]));
+        assert!(matches(&bm, "imag-pim.org", &[]));
+        assert!(matches(&bm, "IMAG", &[]));
+        assert!(matches(&bm, "Information Management", &[]));
+        assert!(!matches(&bm, "calendar", &[]));
+    }
+
+    #[test]
+    fn test_matches_tags() {
+        let bm = bookmark();
+        assert!(matches(&bm, "imag", &[String::from("pim")]));
+        assert!(matches(&bm, "", &[String::from("pim"), String::from("rust")]));
+        assert!(!matches(&bm, "imag", &[String::from("pim"), String::from("web")]));
+    }
+
+}
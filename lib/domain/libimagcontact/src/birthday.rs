@@ -0,0 +1,369 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Birthdays and anniversaries of contacts
+//!
+//! The BDAY and ANNIVERSARY properties of a vcard may contain partial dates (like "--0415" if the
+//! year is not known), so these are parsed into `PartialDate`s.
+
+use std::fmt::{Display, Formatter, Error as FmtError};
+use std::result::Result as RResult;
+
+use chrono::NaiveDate;
+use chrono::NaiveDateTime;
+use chrono::Datelike;
+
+use libimagstore::store::Store;
+use libimagstore::storeid::StoreId;
+
+use contact::Contact;
+use error::Result;
+use iter::ContactIter;
+use store::ContactStore;
+
+/// A date which may lack the year
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PartialDate {
+    pub year  : Option<i32>,
+    pub month : u32,
+    pub day   : u32,
+}
+
+impl PartialDate {
+
+    /// Parse a date value from a vcard
+    ///
+    /// Supported are "YYYY-MM-DD", "YYYYMMDD", "--MMDD" and "--MM-DD", optionally followed by a
+    /// time ("T..."), which is ignored.
+    pub fn parse(s: &str) -> Option<PartialDate> {
+        let s    = s.trim();
+        let date = s.split('T').next().unwrap_or(s);
+
+        // Checked here, so the slicing below cannot split a multi-byte character
+        if !date.chars().all(|c| c == '-' || c.is_ascii_digit()) {
+            return None;
+        }
+
+        let (year, rest) = if date.starts_with("--") {
+            (None, date[2..].chars().filter(|c| *c != '-').collect::<String>())
+        } else {
+            let digits = date.chars().filter(|c| *c != '-').collect::<String>();
+            if digits.len() != 8 {
+                return None;
+            }
+
+            let year = digits[0..4].parse::<i32>().ok()?;
+            (Some(year), String::from(&digits[4..]))
+        };
+
+        if rest.len() != 4 {
+            return None;
+        }
+
+        let month = rest[0..2].parse::<u32>().ok()?;
+        let day   = rest[2..4].parse::<u32>().ok()?;
+
+        // 2000 is a leap year, so the 29th of february is valid without a year
+        NaiveDate::from_ymd_opt(year.unwrap_or(2000), month, day)
+            .map(|_| PartialDate { year: year, month: month, day: day })
+    }
+
+    /// The date in the year `year`
+    ///
+    /// The 29th of february is the 28th in years which are not leap years.
+    pub fn in_year(&self, year: i32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, self.month, self.day)
+            .unwrap_or_else(|| NaiveDate::from_ymd(year, 2, 28))
+    }
+
+    /// All yearly occurrences of the date between `from` and `until` (both inclusive)
+    pub fn occurrences_between(&self, from: &NaiveDate, until: &NaiveDate) -> Vec<NaiveDate> {
+        (from.year()..(until.year() + 1))
+            .filter(|y| self.year.map(|year| year <= *y).unwrap_or(true))
+            .map(|y| self.in_year(y))
+            .filter(|d| d >= from && d <= until)
+            .collect()
+    }
+
+    /// The number of years between the date and `date`, if the year is known
+    pub fn years_at(&self, date: &NaiveDate) -> Option<i32> {
+        self.year.map(|y| date.year() - y)
+    }
+
+}
+
+impl Display for PartialDate {
+    fn fmt(&self, fmt: &mut Formatter) -> RResult<(), FmtError> {
+        match self.year {
+            Some(year) => write!(fmt, "{:04}-{:02}-{:02}", year, self.month, self.day),
+            None       => write!(fmt, "--{:02}-{:02}", self.month, self.day),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateKind {
+    Birthday,
+    Anniversary,
+}
+
+impl Display for DateKind {
+    fn fmt(&self, fmt: &mut Formatter) -> RResult<(), FmtError> {
+        match *self {
+            DateKind::Birthday    => write!(fmt, "Birthday"),
+            DateKind::Anniversary => write!(fmt, "Anniversary"),
+        }
+    }
+}
+
+/// A birthday or anniversary of a contact
+#[derive(Debug, Clone)]
+pub struct ContactDate {
+    pub id   : StoreId,
+    pub uid  : String,
+    pub name : String,
+    pub kind : DateKind,
+    pub date : PartialDate,
+}
+
+/// A birthday or anniversary on a specific day
+#[derive(Debug, Clone)]
+pub struct ContactEvent {
+    pub date : ContactDate,
+    pub on   : NaiveDate,
+}
+
+impl ContactEvent {
+
+    /// How old the contact gets or how many years the anniversary is, if the year is known
+    pub fn years(&self) -> Option<i32> {
+        self.date.date.years_at(&self.on)
+    }
+
+}
+
+impl Display for ContactEvent {
+    fn fmt(&self, fmt: &mut Formatter) -> RResult<(), FmtError> {
+        write!(fmt, "{} {} of {}", self.on, self.date.kind, self.date.name)?;
+        match self.years() {
+            Some(years) => write!(fmt, " ({})", years),
+            None        => Ok(()),
+        }
+    }
+}
+
+/// Get the birthdays and anniversaries of all contacts
+///
+/// Dates which cannot be parsed are ignored with a warning.
+pub fn contact_dates(store: &Store) -> Result<Vec<ContactDate>> {
+    let mut dates = Vec::new();
+
+    for entry in ContactIter::new(store.all_contacts()?, store) {
+        let entry = entry?;
+        let card  = entry.deser()?;
+        let uid   = card.uid().cloned().unwrap_or_else(String::new);
+        let name  = card.fullname().first().cloned().unwrap_or_else(|| uid.clone());
+
+        for &(kind, value) in [(DateKind::Birthday, card.bday()), (DateKind::Anniversary, card.anniversary())].iter() {
+            if let Some(value) = value {
+                match PartialDate::parse(value) {
+                    Some(date) => dates.push(ContactDate {
+                        id   : entry.get_location().clone(),
+                        uid  : uid.clone(),
+                        name : name.clone(),
+                        kind : kind,
+                        date : date,
+                    }),
+                    None => warn!("Cannot parse '{}' of {} as date", value, entry.get_location()),
+                }
+            }
+        }
+    }
+
+    Ok(dates)
+}
+
+/// Get the birthdays and anniversaries between `from` and `until` (both inclusive), sorted by date
+pub fn events_between(store: &Store, from: &NaiveDate, until: &NaiveDate) -> Result<Vec<ContactEvent>> {
+    let mut events = contact_dates(store)?
+        .into_iter()
+        .flat_map(|date| {
+            date.date
+                .occurrences_between(from, until)
+                .into_iter()
+                .map(|on| ContactEvent { date: date.clone(), on: on })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    events.sort_by(|a, b| a.on.cmp(&b.on).then_with(|| a.date.name.cmp(&b.date.name)));
+    Ok(events)
+}
+
+/// Render the dates as iCalendar with one yearly recurring event per date
+pub fn to_ics(dates: &[ContactDate], now: &NaiveDateTime) -> String {
+    let mut ics = String::new();
+
+    {
+        let mut line = |s: String| {
+            ics.push_str(&fold_line(&s));
+            ics.push_str("\r\n");
+        };
+
+        line(String::from("BEGIN:VCALENDAR"));
+        line(String::from("VERSION:2.0"));
+        line(String::from("PRODID:-//imag//imag-contact//EN"));
+
+        for date in dates {
+            let kind  = match date.kind {
+                DateKind::Birthday    => "birthday",
+                DateKind::Anniversary => "anniversary",
+            };
+            let start = date.date.in_year(date.date.year.unwrap_or(2000));
+            let rrule = if date.date.month == 2 && date.date.day == 29 {
+                // Otherwise it would only recur in leap years
+                "FREQ=YEARLY;BYMONTH=2;BYMONTHDAY=-1"
+            } else {
+                "FREQ=YEARLY"
+            };
+
+            line(String::from("BEGIN:VEVENT"));
+            line(format!("UID:{}-{}@imag", date.uid, kind));
+            line(format!("DTSTAMP:{}", now.format("%Y%m%dT%H%M%SZ")));
+            line(format!("DTSTART;VALUE=DATE:{}", start.format("%Y%m%d")));
+            line(format!("RRULE:{}", rrule));
+            line(format!("SUMMARY:{}", escape_text(&format!("{} of {}", date.kind, date.name))));
+            line(String::from("TRANSP:TRANSPARENT"));
+            line(String::from("END:VEVENT"));
+        }
+
+        line(String::from("END:VCALENDAR"));
+    }
+
+    ics
+}
+
+/// Fold a content line after 75 octets, as required by RFC 5545 (section 3.1)
+///
+/// Continuation lines start with a space. Lines are only split between characters, so multi-byte
+/// characters are kept intact.
+fn fold_line(s: &str) -> String {
+    let mut folded = String::with_capacity(s.len());
+    let mut octets = 0;
+
+    for c in s.chars() {
+        if octets + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            octets = 1;
+        }
+
+        folded.push(c);
+        octets += c.len_utf8();
+    }
+
+    folded
+}
+
+fn escape_text(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(PartialDate::parse("1985-04-15"), Some(PartialDate { year: Some(1985), month: 4, day: 15 }));
+        assert_eq!(PartialDate::parse("19850415"), Some(PartialDate { year: Some(1985), month: 4, day: 15 }));
+        assert_eq!(PartialDate::parse("1985-04-15T12:00:00Z"), Some(PartialDate { year: Some(1985), month: 4, day: 15 }));
+        assert_eq!(PartialDate::parse("--0415"), Some(PartialDate { year: None, month: 4, day: 15 }));
+        assert_eq!(PartialDate::parse("--04-15"), Some(PartialDate { year: None, month: 4, day: 15 }));
+        assert_eq!(PartialDate::parse("--0229"), Some(PartialDate { year: None, month: 2, day: 29 }));
+        assert_eq!(PartialDate::parse("---15"), None);
+        assert_eq!(PartialDate::parse("1985-02-30"), None);
+        assert_eq!(PartialDate::parse("foo"), None);
+        assert_eq!(PartialDate::parse("123é4156"), None);
+        assert_eq!(PartialDate::parse("--1é5"), None);
+    }
+
+    #[test]
+    fn test_occurrences() {
+        let date  = PartialDate::parse("1985-01-02").unwrap();
+        let from  = NaiveDate::from_ymd(2017, 12, 1);
+        let until = NaiveDate::from_ymd(2018, 2, 1);
+
+        assert_eq!(date.occurrences_between(&from, &until), vec![NaiveDate::from_ymd(2018, 1, 2)]);
+        assert_eq!(date.years_at(&NaiveDate::from_ymd(2018, 1, 2)), Some(33));
+
+        let leap = PartialDate::parse("--0229").unwrap();
+        assert_eq!(leap.in_year(2018), NaiveDate::from_ymd(2018, 2, 28));
+        assert_eq!(leap.in_year(2020), NaiveDate::from_ymd(2020, 2, 29));
+        assert_eq!(leap.years_at(&from), None);
+    }
+
+    #[test]
+    fn test_ics() {
+        use libimagstore::storeid::StoreId;
+        use std::path::PathBuf;
+
+        let date = ContactDate {
+            id   : StoreId::new_baseless(PathBuf::from("contact/uid")).unwrap(),
+            uid  : String::from("uid"),
+            name : String::from("Foo, Bar"),
+            kind : DateKind::Birthday,
+            date : PartialDate::parse("--0415").unwrap(),
+        };
+        let now = NaiveDate::from_ymd(2018, 1, 1).and_hms(12, 0, 0);
+        let ics = to_ics(&[date], &now);
+
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(ics.contains("UID:uid-birthday@imag\r\n"));
+        assert!(ics.contains("DTSTART;VALUE=DATE:20000415\r\n"));
+        assert!(ics.contains("RRULE:FREQ=YEARLY\r\n"));
+        assert!(ics.contains("SUMMARY:Birthday of Foo\\, Bar\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+    }
+
+    #[test]
+    fn test_fold_line() {
+        assert_eq!(fold_line("SUMMARY:short"), "SUMMARY:short");
+
+        let long   = format!("SUMMARY:{}", "ä".repeat(50));
+        let folded = fold_line(&long);
+        for line in folded.split("\r\n") {
+            assert!(line.len() <= 75);
+        }
+        assert_eq!(folded.replace("\r\n ", ""), long);
+    }
+
+    #[test]
+    fn test_not_before_birth() {
+        let date  = PartialDate::parse("2018-06-01").unwrap();
+        let from  = NaiveDate::from_ymd(2017, 1, 1);
+        let until = NaiveDate::from_ymd(2018, 12, 31);
+
+        assert_eq!(date.occurrences_between(&from, &until), vec![NaiveDate::from_ymd(2018, 6, 1)]);
+    }
+}
@@ -0,0 +1,501 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::fmt::Debug;
+
+use vobject::Component;
+use vobject::Property;
+use vobject::parse_component;
+use vobject::write_component;
+use vobject::vcard::Vcard;
+
+use deser::DeserVcard;
+use error::ContactError as CE;
+use error::ContactErrorKind as CEK;
+use error::Result;
+use util;
+
+/// A postal address as stored in the ADR property of a vcard
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Address {
+    pub po_box   : String,
+    pub extended : String,
+    pub street   : String,
+    pub code     : String,
+    pub city     : String,
+    pub region   : String,
+    pub country  : String,
+}
+
+impl Address {
+
+    /// Parse the raw value of an ADR property
+    pub fn from_raw(raw: &str) -> Address {
+        let mut parts = raw.splitn(7, ';').map(String::from);
+        let mut next  = || parts.next().unwrap_or_else(String::new);
+
+        Address {
+            po_box   : next(),
+            extended : next(),
+            street   : next(),
+            code     : next(),
+            city     : next(),
+            region   : next(),
+            country  : next(),
+        }
+    }
+
+    /// Get the raw value for an ADR property
+    pub fn to_raw(&self) -> String {
+        [&self.po_box, &self.extended, &self.street, &self.code, &self.city, &self.region, &self.country]
+            .iter()
+            .map(|s| s.as_str())
+            .collect::<Vec<&str>>()
+            .join(";")
+    }
+
+}
+
+/// A vcard which can be modified and written back
+///
+/// In contrast to `DeserVcard`, this type keeps the complete vcard component, so properties which
+/// imag does not know about survive a modification.
+#[derive(Debug, Clone)]
+pub struct ContactCard(Component);
+
+impl ContactCard {
+
+    pub fn from_buf(buf: &str) -> Result<ContactCard> {
+        let component = parse_component(buf)?;
+
+        if component.name != "VCARD" {
+            return Err(CE::from_kind(CEK::NotAVcard(component.name)));
+        }
+
+        Ok(ContactCard(component))
+    }
+
+    pub fn from_path<A: AsRef<Path> + Debug>(p: A) -> Result<ContactCard> {
+        util::read_to_string(p).and_then(|buf| ContactCard::from_buf(&buf))
+    }
+
+    pub fn component(&self) -> &Component {
+        &self.0
+    }
+
+    pub fn uid(&self) -> Option<String> {
+        self.0.get_only("UID").map(Property::value_as_string)
+    }
+
+    /// Get the values of all properties with the name `name`
+    pub fn values(&self, name: &str) -> Vec<String> {
+        self.0.get_all(name).iter().map(Property::value_as_string).collect()
+    }
+
+    /// Get the values of all properties with the name `name`, together with their TYPE parameter
+    pub fn typed_values(&self, name: &str) -> Vec<(Option<String>, String)> {
+        self.0
+            .get_all(name)
+            .iter()
+            .map(|p| (p.params.get("TYPE").cloned(), p.value_as_string()))
+            .collect()
+    }
+
+    /// Get the components of the N property
+    ///
+    /// These are family name, given name, additional names, prefixes and suffixes, in this order.
+    pub fn name_parts(&self) -> Vec<String> {
+        self.0
+            .get_only("N")
+            .map(|p| p.raw_value.split(';').map(String::from).collect())
+            .unwrap_or_else(Vec::new)
+    }
+
+    pub fn properties(&self, name: &str) -> &[Property] {
+        self.0.get_all(name)
+    }
+
+    /// Add a property `name` with the raw value `raw`
+    ///
+    /// Returns false if there is already a property with this name and value.
+    pub fn add_value(&mut self, name: &str, raw: String, ty: Option<String>) -> bool {
+        if self.0.get_all(name).iter().any(|p| p.raw_value == raw) {
+            return false;
+        }
+
+        let mut params = BTreeMap::new();
+        if let Some(ty) = ty {
+            params.insert(String::from("TYPE"), ty);
+        }
+
+        self.0.push(Property {
+            name       : String::from(name),
+            params     : params,
+            raw_value  : raw,
+            prop_group : None,
+        });
+        true
+    }
+
+    /// Remove all properties `name` with the raw value `raw`
+    ///
+    /// Returns whether a property was removed.
+    pub fn remove_value(&mut self, name: &str, raw: &str) -> bool {
+        let (removed, empty) = match self.0.props.get_mut(name) {
+            None        => return false,
+            Some(props) => {
+                let len = props.len();
+                props.retain(|p| p.raw_value != raw);
+                (props.len() != len, props.is_empty())
+            },
+        };
+
+        if empty {
+            let _ = self.0.remove(name);
+        }
+
+        removed
+    }
+
+    /// Replace all properties `name` with `props`
+    ///
+    /// Properties in `props` which exist with the same value already are kept as they are, so their
+    /// parameters (like TYPE) are not lost.
+    pub fn replace_properties(&mut self, name: &str, props: Vec<Property>) {
+        let old = self.0.remove(name).unwrap_or_else(Vec::new);
+
+        for prop in props {
+            let prop = old
+                .iter()
+                .find(|o| o.raw_value == prop.raw_value)
+                .cloned()
+                .unwrap_or(prop);
+            self.0.push(prop);
+        }
+    }
+
+    /// Replace the properties named `names` with the ones from `other`
+    pub fn update_from(&mut self, other: &ContactCard, names: &[&str]) {
+        for name in names {
+            self.replace_properties(name, other.properties(name).to_vec());
+        }
+    }
+
+    pub fn emails(&self) -> Vec<String> {
+        self.values("EMAIL")
+    }
+
+    pub fn add_email(&mut self, addr: String, ty: Option<String>) -> bool {
+        self.add_value("EMAIL", addr, ty)
+    }
+
+    pub fn remove_email(&mut self, addr: &str) -> bool {
+        self.remove_value("EMAIL", addr)
+    }
+
+    pub fn phones(&self) -> Vec<String> {
+        self.values("TEL")
+    }
+
+    pub fn add_phone(&mut self, number: String, ty: Option<String>) -> bool {
+        self.add_value("TEL", number, ty)
+    }
+
+    pub fn remove_phone(&mut self, number: &str) -> bool {
+        self.remove_value("TEL", number)
+    }
+
+    pub fn addresses(&self) -> Vec<Address> {
+        self.0.get_all("ADR").iter().map(|p| Address::from_raw(&p.raw_value)).collect()
+    }
+
+    pub fn add_address(&mut self, adr: &Address, ty: Option<String>) -> bool {
+        self.add_value("ADR", adr.to_raw(), ty)
+    }
+
+    pub fn remove_address(&mut self, adr: &Address) -> bool {
+        self.remove_value("ADR", &adr.to_raw())
+    }
+
+    /// Get the categories of the contact
+    ///
+    /// A vcard may contain several CATEGORIES properties, each containing a comma separated list.
+    pub fn categories(&self) -> Vec<String> {
+        self.0
+            .get_all("CATEGORIES")
+            .iter()
+            .flat_map(|p| {
+                p.raw_value
+                    .split(',')
+                    .map(|c| c.trim().to_string())
+                    .filter(|c| !c.is_empty())
+                    .collect::<Vec<String>>()
+            })
+            .collect()
+    }
+
+    pub fn add_category(&mut self, category: String) -> bool {
+        let mut categories = self.categories();
+        if categories.contains(&category) {
+            return false;
+        }
+
+        categories.push(category);
+        self.set_categories(categories);
+        true
+    }
+
+    pub fn remove_category(&mut self, category: &str) -> bool {
+        let categories = self.categories();
+        let len        = categories.len();
+        let categories = categories.into_iter().filter(|c| c != category).collect::<Vec<_>>();
+
+        if categories.len() == len {
+            return false;
+        }
+
+        self.set_categories(categories);
+        true
+    }
+
+    fn set_categories(&mut self, categories: Vec<String>) {
+        let params = self.0
+            .get_all("CATEGORIES")
+            .first()
+            .map(|p| p.params.clone())
+            .unwrap_or_else(BTreeMap::new);
+
+        let _ = self.0.remove("CATEGORIES");
+        if !categories.is_empty() {
+            self.0.push(Property {
+                name       : String::from("CATEGORIES"),
+                params     : params,
+                raw_value  : categories.join(","),
+                prop_group : None,
+            });
+        }
+    }
+
+    /// Merge the properties of `other` into this card
+    ///
+    /// Properties which may only exist once (like N or BDAY) are only taken from `other` if this
+    /// card does not have them. The UID and the meta data of `other` are never taken.
+    pub fn merge(&mut self, other: &ContactCard) {
+        const IGNORED : &'static [&'static str] = &["UID", "VERSION", "PRODID", "REV"];
+        const SINGLE  : &'static [&'static str] = &["N", "FN", "BDAY", "ANNIVERSARY", "GENDER", "KIND"];
+
+        for (name, props) in other.0.props.iter() {
+            if IGNORED.contains(&name.as_str()) {
+                continue;
+            }
+
+            if name == "CATEGORIES" {
+                for category in other.categories() {
+                    let _ = self.add_category(category);
+                }
+            } else if SINGLE.contains(&name.as_str()) {
+                if self.0.get_all(name).is_empty() {
+                    if let Some(prop) = props.first() {
+                        self.0.push(prop.clone());
+                    }
+                }
+            } else {
+                for prop in props {
+                    if !self.0.get_all(name).iter().any(|p| p.raw_value == prop.raw_value) {
+                        self.0.push(prop.clone());
+                    }
+                }
+            }
+        }
+    }
+
+    /// Serialize the card to the vcard format
+    pub fn to_vcard_string(&self) -> String {
+        write_component(&self.0)
+    }
+
+    pub fn deser(&self) -> Result<DeserVcard> {
+        Vcard::build(&self.to_vcard_string()).map(DeserVcard::from).map_err(From::from)
+    }
+
+}
+
+/// Build a card from the data imag has in the header of an entry
+///
+/// This is lossy, as the header only contains the properties imag knows about. It is only meant
+/// to be used if there is no vcard file for a contact.
+impl<'a> From<&'a DeserVcard> for ContactCard {
+    fn from(deser: &'a DeserVcard) -> ContactCard {
+        let mut component = Component::new("VCARD");
+
+        {
+            let mut push = |name: &str, raw: &String, params: BTreeMap<String, String>| {
+                component.push(Property {
+                    name       : String::from(name),
+                    params     : params,
+                    raw_value  : raw.clone(),
+                    prop_group : None,
+                });
+            };
+
+            macro_rules! push_all {
+                ($name:expr, $values:expr) => {
+                    for value in $values {
+                        push($name, value, BTreeMap::new());
+                    }
+                };
+            }
+
+            push_all!("VERSION"      , deser.version());
+            push_all!("UID"          , deser.uid());
+            push_all!("ADR"          , deser.adr());
+            push_all!("ANNIVERSARY"  , deser.anniversary());
+            push_all!("BDAY"         , deser.bday());
+            push_all!("CATEGORIES"   , deser.categories());
+            push_all!("CLIENTPIDMAP" , deser.clientpidmap());
+            push_all!("FN"           , deser.fullname());
+            push_all!("GENDER"       , deser.gender());
+            push_all!("GEO"          , deser.geo());
+            push_all!("IMPP"         , deser.impp());
+            push_all!("KEY"          , deser.key());
+            push_all!("LANG"         , deser.lang());
+            push_all!("LOGO"         , deser.logo());
+            push_all!("MEMBER"       , deser.member());
+            push_all!("N"            , deser.name());
+            push_all!("NICKNAME"     , deser.nickname());
+            push_all!("NOTE"         , deser.note());
+            push_all!("ORG"          , deser.org());
+            push_all!("PHOTO"        , deser.photo());
+            push_all!("PRODID"       , deser.proid());
+            push_all!("RELATED"      , deser.related());
+            push_all!("REV"          , deser.rev());
+            push_all!("ROLE"         , deser.role());
+            push_all!("SOUND"        , deser.sound());
+            push_all!("TEL"          , deser.tel());
+            push_all!("TITLE"        , deser.title());
+            push_all!("TZ"           , deser.tz());
+            push_all!("URL"          , deser.url());
+
+            for email in deser.email() {
+                push("EMAIL", &email.address, email.properties.clone());
+            }
+        }
+
+        ContactCard(component)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const CARD : &'static str = "BEGIN:VCARD\r
+VERSION:4.0\r
+UID:test-uid\r
+FN:Foo Bar\r
+N:Bar;Foo;;;\r
+EMAIL;TYPE=home:foo@example.org\r
+CATEGORIES:friends,work\r
+X-CUSTOM:something imag does not know\r
+END:VCARD\r
+";
+
+    #[test]
+    fn test_unknown_properties_survive() {
+        let mut card = ContactCard::from_buf(CARD).unwrap();
+        assert!(card.add_email(String::from("bar@example.org"), None));
+
+        let card = ContactCard::from_buf(&card.to_vcard_string()).unwrap();
+        assert_eq!(card.values("X-CUSTOM"), vec![String::from("something imag does not know")]);
+        assert_eq!(card.emails().len(), 2);
+    }
+
+    #[test]
+    fn test_add_remove_email() {
+        let mut card = ContactCard::from_buf(CARD).unwrap();
+
+        assert!(!card.add_email(String::from("foo@example.org"), None));
+        assert!(card.remove_email("foo@example.org"));
+        assert!(!card.remove_email("foo@example.org"));
+        assert!(card.emails().is_empty());
+    }
+
+    #[test]
+    fn test_categories() {
+        let mut card = ContactCard::from_buf(CARD).unwrap();
+        assert_eq!(card.categories(), vec![String::from("friends"), String::from("work")]);
+
+        assert!(card.add_category(String::from("family")));
+        assert!(!card.add_category(String::from("work")));
+        assert!(card.remove_category("friends"));
+
+        assert_eq!(card.categories(), vec![String::from("work"), String::from("family")]);
+        assert_eq!(card.properties("CATEGORIES").len(), 1);
+    }
+
+    #[test]
+    fn test_replace_keeps_parameters() {
+        let mut card = ContactCard::from_buf(CARD).unwrap();
+        let email    = Property {
+            name       : String::from("EMAIL"),
+            params     : BTreeMap::new(),
+            raw_value  : String::from("foo@example.org"),
+            prop_group : None,
+        };
+
+        card.replace_properties("EMAIL", vec![email]);
+
+        let emails = card.properties("EMAIL");
+        assert_eq!(emails.len(), 1);
+        assert_eq!(emails[0].params.get("TYPE"), Some(&String::from("home")));
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut card = ContactCard::from_buf(CARD).unwrap();
+        let other    = ContactCard::from_buf("BEGIN:VCARD\r
+VERSION:4.0\r
+UID:other-uid\r
+FN:Foo B. Bar\r
+BDAY:2000-01-01\r
+EMAIL:foo@example.org\r
+EMAIL:foo@example.com\r
+CATEGORIES:work,family\r
+END:VCARD\r
+").unwrap();
+
+        card.merge(&other);
+
+        assert_eq!(card.uid(), Some(String::from("test-uid")));
+        assert_eq!(card.values("FN"), vec![String::from("Foo Bar")]);
+        assert_eq!(card.values("BDAY"), vec![String::from("2000-01-01")]);
+        assert_eq!(card.emails(), vec![String::from("foo@example.org"), String::from("foo@example.com")]);
+        assert_eq!(card.categories(), vec![String::from("friends"), String::from("work"), String::from("family")]);
+    }
+
+    #[test]
+    fn test_address_raw() {
+        let adr = Address::from_raw("box;;street 1;12345;city;;country");
+        assert_eq!(adr.street, "street 1");
+        assert_eq!(adr.region, "");
+        assert_eq!(adr.to_raw(), "box;;street 1;12345;city;;country");
+    }
+}
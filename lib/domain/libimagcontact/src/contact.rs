@@ -17,18 +17,26 @@
 // Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
 //
 
+use std::path::PathBuf;
+use std::fs::File;
+use std::io::Write;
+
 use toml::to_string as toml_to_string;
 use toml::from_str as toml_from_str;
 use toml_query::read::TomlValueReadExt;
+use toml_query::read::TomlValueReadTypeExt;
+use toml_query::insert::TomlValueInsertExt;
 
 use libimagstore::store::Entry;
 use libimagentryutil::isa::Is;
 use libimagentryutil::isa::IsKindHeaderPathProvider;
 
+use card::ContactCard;
 use deser::DeserVcard;
 use error::Result;
 use error::ContactError as CE;
 use error::ContactErrorKind as CEK;
+use util;
 
 /// Trait to be implemented on ::libimagstore::store::Entry
 pub trait Contact {
@@ -39,6 +47,22 @@ pub trait Contact {
 
     fn deser(&self) -> Result<DeserVcard>;
 
+    /// Get the path of the vcard file this contact was imported from, if it is known
+    fn vcard_path(&self) -> Result<Option<PathBuf>>;
+
+    /// Get the contact as modifiable card
+    ///
+    /// The card is read from the vcard file. If the file is not known, it is built from the data
+    /// in the header, which only contains the properties imag knows about.
+    fn card(&self) -> Result<ContactCard>;
+
+    // modifying data
+
+    /// Write a modified card to the vcard file and update the data in the header
+    ///
+    /// Fails if the UID of the card is not the UID of the contact.
+    fn write_card(&mut self, card: &ContactCard) -> Result<()>;
+
     // More convenience functionality may follow
 
 }
@@ -64,5 +88,44 @@ impl Contact for Entry {
         Ok(deser)
     }
 
+    fn vcard_path(&self) -> Result<Option<PathBuf>> {
+        self.get_header()
+            .read_string("contact.file")
+            .map(|o| o.map(PathBuf::from))
+            .map_err(From::from)
+    }
+
+    fn card(&self) -> Result<ContactCard> {
+        match self.vcard_path()? {
+            Some(path) => ContactCard::from_path(path),
+            None       => {
+                warn!("No vcard file known for {}, using data from header", self.get_location());
+                self.deser().map(|deser| ContactCard::from(&deser))
+            },
+        }
+    }
+
+    fn write_card(&mut self, card: &ContactCard) -> Result<()> {
+        let deser = card.deser()?;
+
+        match (self.deser()?.uid(), deser.uid()) {
+            (Some(expected), Some(found)) => if expected != found {
+                return Err(CE::from_kind(CEK::UidMismatch(expected.clone(), found.clone())))
+            },
+            (_, None) => return Err(CE::from_kind(CEK::UidMissing(card.to_vcard_string()))),
+            (None, _) => {},
+        }
+
+        if let Some(path) = self.vcard_path()? {
+            debug!("Writing vcard to {:?}", path);
+            File::create(&path)?.write_all(card.to_vcard_string().as_bytes())?;
+            util::record_file(self, &path)?;
+        }
+
+        let value = util::deser_to_value(&deser)?;
+        let _     = self.get_header_mut().insert("contact.data", value)?;
+        Ok(())
+    }
+
 }
 
@@ -0,0 +1,224 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Detection of duplicated contacts
+//!
+//! Contacts are considered duplicates if they share a name, an email address or a phone number.
+//! All of these are normalized before comparing, so "Bar, Foo" and "foo bar" are the same name and
+//! "+49 1234 567890" and "01234/567890" are the same phone number.
+
+use std::fmt::{Display, Formatter, Error as FmtError};
+
+use libimagstore::storeid::StoreId;
+
+use deser::DeserVcard;
+
+/// Why two contacts are considered duplicates
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DuplicateReason {
+    Name(String),
+    Email(String),
+    Phone(String),
+}
+
+impl Display for DuplicateReason {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), FmtError> {
+        match *self {
+            DuplicateReason::Name(ref n)  => write!(fmt, "same name '{}'", n),
+            DuplicateReason::Email(ref e) => write!(fmt, "same email address '{}'", e),
+            DuplicateReason::Phone(ref p) => write!(fmt, "same phone number '{}'", p),
+        }
+    }
+}
+
+/// Two contacts which are probably the same person
+#[derive(Debug, Clone)]
+pub struct Duplicate {
+    pub first   : StoreId,
+    pub second  : StoreId,
+    pub reasons : Vec<DuplicateReason>,
+}
+
+/// Normalize a name: lowercase, without punctuation, words sorted
+pub fn normalize_name(name: &str) -> String {
+    let mut words = name
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .map(|w| {
+            w.chars()
+                .filter(|c| c.is_alphanumeric())
+                .flat_map(char::to_lowercase)
+                .collect::<String>()
+        })
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<String>>();
+
+    words.sort();
+    words.join(" ")
+}
+
+pub fn normalize_email(email: &str) -> String {
+    let email = email.trim().to_lowercase();
+
+    if email.starts_with("mailto:") {
+        String::from(&email[7..])
+    } else {
+        email
+    }
+}
+
+/// Normalize a phone number: only the digits, without leading zeros
+pub fn normalize_phone(phone: &str) -> String {
+    phone
+        .chars()
+        .filter(|c| c.is_digit(10))
+        .skip_while(|c| *c == '0')
+        .collect()
+}
+
+/// Check whether two normalized phone numbers are the same
+///
+/// Numbers with and without country code are the same number, so one number ending with the
+/// other one is considered a match, if the shorter one has at least 7 digits.
+pub fn phones_match(a: &str, b: &str) -> bool {
+    let (short, long) = if a.len() < b.len() { (a, b) } else { (b, a) };
+    short == long || (short.len() >= 7 && long.ends_with(short))
+}
+
+/// The normalized data of a contact which is compared
+struct Keys {
+    names  : Vec<String>,
+    emails : Vec<String>,
+    phones : Vec<String>,
+}
+
+impl<'a> From<&'a DeserVcard> for Keys {
+    fn from(card: &'a DeserVcard) -> Keys {
+        let mut names = card.fullname().iter().map(|n| normalize_name(n)).collect::<Vec<_>>();
+
+        if let Some(name) = card.name() {
+            // family and given name
+            let name = name.split(';').take(2).collect::<Vec<_>>().join(" ");
+            names.push(normalize_name(&name));
+        }
+
+        names.retain(|n| !n.is_empty());
+        names.dedup();
+
+        let emails = card
+            .email()
+            .iter()
+            .map(|e| normalize_email(&e.address))
+            .filter(|e| !e.is_empty())
+            .collect();
+
+        let phones = card
+            .tel()
+            .iter()
+            .map(|p| normalize_phone(p))
+            .filter(|p| !p.is_empty())
+            .collect();
+
+        Keys {
+            names: names,
+            emails: emails,
+            phones: phones,
+        }
+    }
+}
+
+impl Keys {
+
+    fn compare(&self, other: &Keys) -> Vec<DuplicateReason> {
+        let mut reasons = Vec::new();
+
+        if let Some(name) = self.names.iter().find(|n| other.names.contains(n)) {
+            reasons.push(DuplicateReason::Name(name.clone()));
+        }
+
+        for email in self.emails.iter().filter(|e| other.emails.contains(e)) {
+            reasons.push(DuplicateReason::Email(email.clone()));
+        }
+
+        for phone in self.phones.iter().filter(|p| other.phones.iter().any(|o| phones_match(p, o))) {
+            reasons.push(DuplicateReason::Phone(phone.clone()));
+        }
+
+        reasons
+    }
+
+}
+
+/// Find the duplicates in a list of contacts
+///
+/// Each pair of duplicates is returned once, the contact which comes first in `contacts` is the
+/// `first` one.
+pub fn find_duplicates(contacts: &[(StoreId, DeserVcard)]) -> Vec<Duplicate> {
+    let keys = contacts
+        .iter()
+        .map(|&(ref id, ref card)| (id, Keys::from(card)))
+        .collect::<Vec<_>>();
+
+    let mut duplicates = Vec::new();
+    for (i, &(first, ref first_keys)) in keys.iter().enumerate() {
+        for &(second, ref second_keys) in keys.iter().skip(i + 1) {
+            let reasons = first_keys.compare(second_keys);
+
+            if !reasons.is_empty() {
+                debug!("Duplicate: {} and {}: {:?}", first, second, reasons);
+                duplicates.push(Duplicate {
+                    first   : first.clone(),
+                    second  : second.clone(),
+                    reasons : reasons,
+                });
+            }
+        }
+    }
+
+    duplicates
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_normalize_name() {
+        assert_eq!(normalize_name("Foo Bar"), normalize_name("bar, foo"));
+        assert_eq!(normalize_name("  Foo   Bar "), "bar foo");
+        assert_eq!(normalize_name("Dr. Foo"), "dr foo");
+    }
+
+    #[test]
+    fn test_normalize_email() {
+        assert_eq!(normalize_email(" Foo@Example.org"), "foo@example.org");
+        assert_eq!(normalize_email("mailto:foo@example.org"), "foo@example.org");
+    }
+
+    #[test]
+    fn test_phones_match() {
+        let a = normalize_phone("+49 1234 567890");
+        let b = normalize_phone("01234/567890");
+        let c = normalize_phone("0123 456");
+
+        assert!(phones_match(&a, &b));
+        assert!(phones_match(&b, &a));
+        assert!(!phones_match(&a, &c));
+        assert!(phones_match(&c, &c));
+    }
+}
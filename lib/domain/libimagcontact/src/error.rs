@@ -28,6 +28,8 @@ error_chain! {
         StoreError(::libimagstore::error::StoreError, ::libimagstore::error::StoreErrorKind);
         VObjectError(::vobject::error::VObjectError, ::vobject::error::VObjectErrorKind);
         EntryUtilError(::libimagentryutil::error::EntryUtilError, ::libimagentryutil::error::EntryUtilErrorKind);
+        LinkError(::libimagentrylink::error::LinkError, ::libimagentrylink::error::LinkErrorKind);
+        RefError(::libimagentryref::error::RefError, ::libimagentryref::error::RefErrorKind);
     }
 
     foreign_links {
@@ -60,6 +62,16 @@ error_chain! {
             display("Vcard has no UID : {}", buf)
         }
 
+        UidMismatch(expected: String, found: String) {
+            description("Vcard UID does not match the UID of the contact")
+            display("Vcard UID '{}' does not match the UID '{}' of the contact", found, expected)
+        }
+
+        NotAVcard(name: String) {
+            description("Component is not a vcard")
+            display("Component is not a vcard, but {}", name)
+        }
+
     }
 }
 
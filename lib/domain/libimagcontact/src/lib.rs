@@ -43,17 +43,26 @@ extern crate toml_query;
 extern crate uuid;
 extern crate serde;
 #[macro_use] extern crate serde_derive;
+extern crate chrono;
+#[cfg(test)] extern crate tempdir;
 
 #[macro_use] extern crate libimagstore;
 extern crate libimagerror;
 #[macro_use] extern crate libimagentryutil;
+extern crate libimagentrylink;
+extern crate libimagentryref;
 
 module_entry_path_mod!("contact");
 
+pub mod birthday;
+pub mod card;
 pub mod contact;
+pub mod dedup;
 pub mod error;
 pub mod iter;
+pub mod query;
 pub mod store;
+pub mod sync;
 pub mod deser;
 mod util;
 
@@ -0,0 +1,136 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Fuzzy querying of contacts, for mail clients
+//!
+//! A query matches against the names, nicknames and email addresses of a contact. The result is
+//! one `QueryResult` per email address, as this is what mail clients need.
+
+use libimagstore::store::Store;
+
+use contact::Contact;
+use deser::DeserVcard;
+use error::Result;
+use iter::ContactIter;
+use store::ContactStore;
+
+/// An email address of a contact which matched a query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryResult {
+    pub email : String,
+    pub name  : String,
+    pub ty    : Option<String>,
+    pub score : usize,
+}
+
+/// Match `pattern` fuzzily against `text`, ignoring case
+///
+/// Returns a score if it matches, the higher the better. Substrings score better than
+/// subsequences, substrings at the start score better than substrings in the middle and
+/// subsequences with few gaps score better than those with many.
+pub fn fuzzy_match(pattern: &str, text: &str) -> Option<usize> {
+    let pattern = pattern.to_lowercase();
+    let text    = text.to_lowercase();
+
+    if pattern.is_empty() {
+        return Some(1);
+    }
+
+    if let Some(pos) = text.find(&pattern) {
+        let pos = text[..pos].chars().count();
+        return Some(200 - ::std::cmp::min(pos, 99));
+    }
+
+    let mut gaps  = 0;
+    let mut chars = text.chars();
+    for p in pattern.chars() {
+        loop {
+            match chars.next() {
+                None                => return None,
+                Some(c) if c == p   => break,
+                Some(_)             => gaps += 1,
+            }
+        }
+    }
+
+    Some(100 - ::std::cmp::min(gaps, 99))
+}
+
+/// Query the contacts, the best matches first
+pub fn query(store: &Store, pattern: &str) -> Result<Vec<QueryResult>> {
+    let mut results = Vec::new();
+
+    for entry in ContactIter::new(store.all_contacts()?, store) {
+        results.append(&mut query_card(&entry?.deser()?, pattern));
+    }
+
+    results.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.email.cmp(&b.email)));
+    Ok(results)
+}
+
+fn query_card(card: &DeserVcard, pattern: &str) -> Vec<QueryResult> {
+    let name = card
+        .fullname()
+        .first()
+        .cloned()
+        .or_else(|| card.name().map(|n| n.split(';').rev().filter(|s| !s.is_empty()).collect::<Vec<_>>().join(" ")))
+        .unwrap_or_else(String::new);
+
+    let name_score = card
+        .fullname()
+        .iter()
+        .chain(card.name())
+        .chain(card.nickname().iter())
+        .filter_map(|n| fuzzy_match(pattern, n))
+        .max();
+
+    card.email()
+        .iter()
+        .filter_map(|email| {
+            // If the name matches, all addresses of the contact are results, otherwise only the
+            // ones which match themselves
+            let score = match (name_score, fuzzy_match(pattern, &email.address)) {
+                (Some(n), Some(e)) => Some(::std::cmp::max(n, e)),
+                (n, e)             => n.or(e),
+            };
+
+            score.map(|score| QueryResult {
+                email : email.address.clone(),
+                name  : name.clone(),
+                ty    : email.properties.get("TYPE").cloned(),
+                score : score,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_match() {
+        assert!(fuzzy_match("foo", "Foo Bar").is_some());
+        assert!(fuzzy_match("fbar", "Foo Bar").is_some());
+        assert!(fuzzy_match("baz", "Foo Bar").is_none());
+        assert!(fuzzy_match("foo", "Foo Bar") > fuzzy_match("bar", "Foo Bar"));
+        assert!(fuzzy_match("bar", "Foo Bar") > fuzzy_match("fbar", "Foo Bar"));
+        assert!(fuzzy_match("fb", "Foo Bar") > fuzzy_match("fr", "Foo Bar"));
+    }
+}
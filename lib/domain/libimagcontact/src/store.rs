@@ -20,8 +20,6 @@
 use std::path::PathBuf;
 
 use toml::Value;
-use toml::to_string as toml_to_string;
-use toml::from_str as toml_from_str;
 use toml_query::insert::TomlValueInsertExt;
 use vobject::vcard::Vcard;
 
@@ -31,9 +29,14 @@ use libimagstore::store::Store;
 use libimagstore::store::FileLockEntry;
 use libimagstore::storeid::StoreIdIterator;
 use libimagentryutil::isa::Is;
+use libimagentrylink::internal::InternalLinker;
+use libimagentrylink::internal::Link;
 
+use contact::Contact;
 use contact::IsContact;
+use dedup::Duplicate;
 use deser::DeserVcard;
+use iter::ContactIter;
 use module_path::ModuleEntryPath;
 use error::ContactError as CE;
 use error::ContactErrorKind as CEK;
@@ -53,17 +56,39 @@ pub trait ContactStore<'a> {
     // getting
 
     fn all_contacts(&'a self) -> Result<StoreIdIterator>;
+
+    /// Find contacts which are probably the same person
+    fn find_duplicates(&'a self) -> Result<Vec<Duplicate>>;
+
+    // merging
+
+    /// Merge the contact `remove` into the contact `keep`
+    ///
+    /// The vcard of `keep` gets the data of `remove`, links to `remove` are moved to `keep` and
+    /// `remove` is deleted from the store. The vcard file of `remove` is not touched, its path is
+    /// returned so the caller can decide what to do with it.
+    fn merge_contacts(&'a self, keep: StoreId, remove: StoreId)
+        -> Result<(FileLockEntry<'a>, Option<PathBuf>)>;
 }
 
 /// The extension for the Store to work with contacts
 impl<'a> ContactStore<'a> for Store {
 
+    /// Create contact ref from a vcard file
+    ///
+    /// The path of the file is recorded in the entry, so modifications can be written back.
     fn create_from_path(&'a self, p: &PathBuf) -> Result<FileLockEntry<'a>> {
-        util::read_to_string(p).and_then(|buf| self.create_from_buf(&buf))
+        let buf          = util::read_to_string(p)?;
+        let (sid, value) = prepare_fetching_from_store(&buf)?;
+        postprocess_fetched_entry(self.create(sid)?, value)
+            .and_then(|entry| record_path(entry, p))
     }
 
     fn retrieve_from_path(&'a self, p: &PathBuf) -> Result<FileLockEntry<'a>> {
-        util::read_to_string(p).and_then(|buf| self.retrieve_from_buf(&buf))
+        let buf          = util::read_to_string(p)?;
+        let (sid, value) = prepare_fetching_from_store(&buf)?;
+        postprocess_fetched_entry(self.retrieve(sid)?, value)
+            .and_then(|entry| record_path(entry, p))
     }
 
     /// Create contact ref from buffer
@@ -89,6 +114,56 @@ impl<'a> ContactStore<'a> for Store {
         Ok(StoreIdIterator::new(Box::new(iter)))
     }
 
+    fn find_duplicates(&'a self) -> Result<Vec<Duplicate>> {
+        let contacts = ContactIter::new(self.all_contacts()?, self)
+            .map(|entry| entry.and_then(|e| Ok((e.get_location().clone(), e.deser()?))))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(::dedup::find_duplicates(&contacts))
+    }
+
+    fn merge_contacts(&'a self, keep: StoreId, remove: StoreId)
+        -> Result<(FileLockEntry<'a>, Option<PathBuf>)>
+    {
+        let mut keep_entry = self
+            .get(keep.clone())?
+            .ok_or_else(|| CE::from_kind(CEK::EntryNotFound(keep.clone())))?;
+
+        let path = {
+            let mut remove_entry = self
+                .get(remove.clone())?
+                .ok_or_else(|| CE::from_kind(CEK::EntryNotFound(remove.clone())))?;
+
+            let mut card = keep_entry.card()?;
+            card.merge(&remove_entry.card()?);
+            keep_entry.write_card(&card)?;
+
+            // A link between the two contacts must not become a link from `keep` to itself
+            keep_entry.remove_internal_link(&mut remove_entry)?;
+
+            for link in remove_entry.get_internal_links()? {
+                debug!("Moving link {:?} to {}", link, keep);
+                let id        = link.get_store_id().clone();
+                let mut other = self
+                    .get(id.clone())?
+                    .ok_or_else(|| CE::from_kind(CEK::EntryNotFound(id)))?;
+
+                match link {
+                    Link::Id { .. }                    => keep_entry.add_internal_link(&mut other)?,
+                    Link::Annotated { annotation, .. } => {
+                        keep_entry.add_internal_annotated_link(&mut other, annotation)?
+                    },
+                }
+            }
+
+            remove_entry.unlink(self)?;
+            remove_entry.vcard_path()?
+        };
+
+        self.delete(remove)?;
+        Ok((keep_entry, path))
+    }
+
 }
 
 /// Prepare the fetching from the store.
@@ -100,11 +175,7 @@ fn prepare_fetching_from_store(buf: &str) -> Result<(StoreId, Value)> {
 
     let uid = vcard.uid().ok_or_else(|| CE::from_kind(CEK::UidMissing(buf.to_string())))?;
 
-    let value = { // dirty ugly hack
-        let serialized = DeserVcard::from(vcard);
-        let serialized = toml_to_string(&serialized)?;
-        toml_from_str::<Value>(&serialized)?
-    };
+    let value = util::deser_to_value(&DeserVcard::from(vcard))?;
 
     let sid = ModuleEntryPath::new(uid.raw()).into_storeid()?;
 
@@ -118,3 +189,9 @@ fn postprocess_fetched_entry<'a>(mut entry: FileLockEntry<'a>, value: Value) ->
     Ok(entry)
 }
 
+/// Record the path of the vcard file in the entry
+fn record_path<'a>(mut entry: FileLockEntry<'a>, p: &PathBuf) -> Result<FileLockEntry<'a>> {
+    let path = p.canonicalize().unwrap_or_else(|_| p.clone());
+    util::record_file(&mut entry, &path)?;
+    Ok(entry)
+}
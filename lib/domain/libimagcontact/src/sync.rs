@@ -0,0 +1,275 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Synchronization with a vdir
+//!
+//! A vdir is a directory with one vcard file per contact, as used by vdirsyncer for example.
+//! Synchronizing imports new files, updates contacts whose files were changed and removes contacts
+//! whose files vanished.
+//! Whether a file was changed is found out by the modification time first, the hash of the file
+//! (the SHA-1 of its content) is only calculated if the modification time changed.
+//!
+//! Contacts which are removed are unlinked from all entries first.
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fs::read_dir;
+use std::path::Path;
+use std::path::PathBuf;
+
+use toml_query::read::TomlValueReadExt;
+use toml_query::read::TomlValueReadTypeExt;
+use toml_query::insert::TomlValueInsertExt;
+use toml::Value;
+
+use libimagstore::store::Store;
+use libimagstore::storeid::StoreId;
+use libimagentrylink::internal::InternalLinker;
+use libimagentryref::generators::sha1::Sha1;
+use libimagentryref::refstore::UniqueRefPathGenerator;
+
+use contact::Contact;
+use error::ContactError as CE;
+use error::Result;
+use iter::ContactIter;
+use store::ContactStore;
+use util;
+
+/// What happened while synchronizing
+#[derive(Debug, Default)]
+pub struct SyncReport {
+    pub imported  : Vec<StoreId>,
+    pub updated   : Vec<StoreId>,
+    pub removed   : Vec<StoreId>,
+    pub unchanged : usize,
+
+    /// Files which could not be synchronized
+    pub failed    : Vec<(PathBuf, CE)>,
+}
+
+/// Synchronize the contacts in the store with the vcard files in `dir`
+///
+/// Errors with single files do not abort the synchronization, they are collected in the report.
+pub fn sync_vdir(store: &Store, dir: &Path) -> Result<SyncReport> {
+    let dir        = dir.canonicalize()?;
+    let mut report = SyncReport::default();
+
+    // All contacts, and the ones in the vdir by their path
+    let mut all_ids = HashSet::new();
+    let mut in_dir  = HashMap::new();
+    for entry in ContactIter::new(store.all_contacts()?, store) {
+        let entry = entry?;
+        let id    = entry.get_location().clone();
+
+        if let Some(path) = entry.vcard_path()? {
+            if path.starts_with(&dir) {
+                in_dir.insert(path, id.clone());
+            }
+        }
+
+        all_ids.insert(id);
+    }
+
+    for file in read_dir(&dir)? {
+        let path = file?.path();
+
+        let is_vcf = path.extension().map(|ext| ext == "vcf").unwrap_or(false);
+        if !path.is_file() || !is_vcf {
+            debug!("Ignoring {}", path.display());
+            continue;
+        }
+
+        let known = in_dir.remove(&path);
+        match sync_file(store, &path, known.as_ref()) {
+            Ok(SyncResult::Unchanged)   => report.unchanged += 1,
+            Ok(SyncResult::Changed(id)) => if all_ids.contains(&id) {
+                report.updated.push(id)
+            } else {
+                report.imported.push(id)
+            },
+            Err(e) => report.failed.push((path, e)),
+        }
+    }
+
+    // Whatever is left was not found in the directory anymore
+    for (path, id) in in_dir {
+        if path.exists() {
+            continue;
+        }
+
+        debug!("{} vanished, removing {}", path.display(), id);
+        remove_contact(store, &id)?;
+        report.removed.push(id);
+    }
+
+    Ok(report)
+}
+
+enum SyncResult {
+    Unchanged,
+    Changed(StoreId),
+}
+
+fn sync_file(store: &Store, path: &PathBuf, known: Option<&StoreId>) -> Result<SyncResult> {
+    if let Some(id) = known {
+        if let Some(mut entry) = store.get(id.clone())? {
+            let mtime = util::modification_time(path)?;
+            let old   = entry.get_header().read("contact.mtime")?.and_then(Value::as_integer);
+
+            if old == Some(mtime) {
+                return Ok(SyncResult::Unchanged);
+            }
+
+            let hash = Sha1::unique_hash(path)?;
+            if entry.get_header().read_string("contact.hash")? == Some(hash) {
+                // touched, but not changed
+                let _ = entry.get_header_mut().insert("contact.mtime", Value::Integer(mtime))?;
+                return Ok(SyncResult::Unchanged);
+            }
+        }
+    }
+
+    let entry = store.retrieve_from_path(path)?;
+    let id    = entry.get_location().clone();
+
+    // The UID in the file was changed, so the old contact is replaced by the new one
+    if let Some(old) = known {
+        if *old != id {
+            drop(entry);
+            remove_contact(store, old)?;
+        }
+    }
+
+    Ok(SyncResult::Changed(id))
+}
+
+/// Remove a contact from the store, after removing all links to it
+fn remove_contact(store: &Store, id: &StoreId) -> Result<()> {
+    if let Some(mut entry) = store.get(id.clone())? {
+        let _ = entry.unlink(store)?;
+    }
+
+    store.delete(id.clone()).map_err(From::from)
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs::File;
+    use std::fs::remove_file;
+    use std::io::Write;
+    use std::path::Path;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use tempdir::TempDir;
+    use toml::Value;
+    use toml_query::insert::TomlValueInsertExt;
+
+    use libimagstore::store::Store;
+    use libimagstore::file_abstraction::InMemoryFileAbstraction;
+    use libimagentrylink::internal::InternalLinker;
+
+    use super::sync_vdir;
+    use contact::Contact;
+    use store::ContactStore;
+
+    fn get_store() -> Store {
+        let backend = Arc::new(InMemoryFileAbstraction::default());
+        Store::new_with_backend(PathBuf::from("/"), &None, backend).unwrap()
+    }
+
+    fn write_vcard(dir: &Path, file: &str, uid: &str, name: &str) {
+        let vcard = format!("BEGIN:VCARD\r\nVERSION:4.0\r\nUID:{}\r\nFN:{}\r\nEND:VCARD\r\n", uid, name);
+        let _ = File::create(dir.join(file)).unwrap().write_all(vcard.as_bytes()).unwrap();
+    }
+
+    fn fullname(store: &Store, uid: &str) -> Option<String> {
+        store.get(PathBuf::from(format!("contact/{}", uid)))
+            .unwrap()
+            .unwrap()
+            .deser()
+            .unwrap()
+            .fullname()
+            .first()
+            .cloned()
+    }
+
+    #[test]
+    fn test_sync_add() {
+        let store = get_store();
+        let dir   = TempDir::new("imag-contact-sync").unwrap();
+        write_vcard(dir.path(), "a.vcf", "a", "Alice");
+        write_vcard(dir.path(), "b.vcf", "b", "Bob");
+        let _ = File::create(dir.path().join("README")).unwrap();
+
+        let report = sync_vdir(&store, dir.path()).unwrap();
+        assert_eq!(report.imported.len(), 2);
+        assert!(report.failed.is_empty());
+        assert_eq!(fullname(&store, "a"), Some(String::from("Alice")));
+
+        let report = sync_vdir(&store, dir.path()).unwrap();
+        assert!(report.imported.is_empty());
+        assert!(report.updated.is_empty());
+        assert_eq!(report.unchanged, 2);
+    }
+
+    #[test]
+    fn test_sync_update() {
+        let store = get_store();
+        let dir   = TempDir::new("imag-contact-sync").unwrap();
+        write_vcard(dir.path(), "a.vcf", "a", "Alice");
+        let _ = sync_vdir(&store, dir.path()).unwrap();
+
+        // The modification time has a resolution of seconds, so pretend the file was older
+        write_vcard(dir.path(), "a.vcf", "a", "Alice Cooper");
+        let _ = store.get(PathBuf::from("contact/a"))
+            .unwrap()
+            .unwrap()
+            .get_header_mut()
+            .insert("contact.mtime", Value::Integer(0))
+            .unwrap();
+
+        let report = sync_vdir(&store, dir.path()).unwrap();
+        assert_eq!(report.updated.len(), 1);
+        assert_eq!(fullname(&store, "a"), Some(String::from("Alice Cooper")));
+    }
+
+    #[test]
+    fn test_sync_vanish() {
+        let store = get_store();
+        let dir   = TempDir::new("imag-contact-sync").unwrap();
+        write_vcard(dir.path(), "a.vcf", "a", "Alice");
+        let _ = sync_vdir(&store, dir.path()).unwrap();
+
+        {
+            let mut note    = store.create(PathBuf::from("notes/a")).unwrap();
+            let mut contact = store.get(PathBuf::from("contact/a")).unwrap().unwrap();
+            let _ = note.add_internal_link(&mut contact).unwrap();
+        }
+
+        remove_file(dir.path().join("a.vcf")).unwrap();
+        let report = sync_vdir(&store, dir.path()).unwrap();
+        assert_eq!(report.removed.len(), 1);
+        assert!(store.get(PathBuf::from("contact/a")).unwrap().is_none());
+
+        let note = store.get(PathBuf::from("notes/a")).unwrap().unwrap();
+        assert_eq!(note.get_internal_links().unwrap().count(), 0);
+    }
+
+}
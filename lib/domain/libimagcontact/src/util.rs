@@ -20,8 +20,20 @@
 use std::path::Path;
 use std::fmt::Debug;
 use std::fs::File;
+use std::fs::metadata;
 use std::io::Read;
+use std::time::UNIX_EPOCH;
 
+use toml::Value;
+use toml::to_string as toml_to_string;
+use toml::from_str as toml_from_str;
+use toml_query::insert::TomlValueInsertExt;
+
+use libimagstore::store::Entry;
+use libimagentryref::generators::sha1::Sha1;
+use libimagentryref::refstore::UniqueRefPathGenerator;
+
+use deser::DeserVcard;
 use error::Result;
 
 pub fn read_to_string<A: AsRef<Path> + Debug>(pb: A) -> Result<String> {
@@ -35,3 +47,33 @@ pub fn read_to_string<A: AsRef<Path> + Debug>(pb: A) -> Result<String> {
     Ok(cont)
 }
 
+/// Convert the deserialized vcard to a toml value, to be put into the header of an entry
+pub fn deser_to_value(deser: &DeserVcard) -> Result<Value> {
+    // dirty ugly hack
+    let serialized = toml_to_string(deser)?;
+    toml_from_str::<Value>(&serialized).map_err(From::from)
+}
+
+/// Get the modification time of a file, in seconds since the epoch
+pub fn modification_time<A: AsRef<Path>>(path: A) -> Result<i64> {
+    let mtime = metadata(path)?.modified()?;
+    Ok(mtime.duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or(0))
+}
+
+/// Record the path of the vcard file at `path` in the entry, together with its hash and
+/// modification time
+pub fn record_file(entry: &mut Entry, path: &Path) -> Result<()> {
+    match path.to_str() {
+        Some(s) => {
+            let _ = entry.get_header_mut().insert("contact.file", Value::String(String::from(s)))?;
+        },
+        None => warn!("Path {:?} is not valid UTF-8, not recording it", path),
+    }
+
+    let hash  = Sha1::unique_hash(path)?;
+    let mtime = modification_time(path)?;
+
+    let _ = entry.get_header_mut().insert("contact.hash", Value::String(hash))?;
+    let _ = entry.get_header_mut().insert("contact.mtime", Value::Integer(mtime))?;
+    Ok(())
+}
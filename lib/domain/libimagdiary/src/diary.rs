@@ -18,6 +18,7 @@
 //
 
 use std::cmp::Ordering;
+use std::collections::BTreeSet;
 
 use libimagstore::store::FileLockEntry;
 use libimagstore::store::Store;
@@ -28,6 +29,7 @@ use chrono::offset::Local;
 use chrono::Datelike;
 use itertools::Itertools;
 use chrono::naive::NaiveDateTime;
+use chrono::naive::NaiveDate;
 use chrono::Timelike;
 
 use entry::IsDiaryEntry;
@@ -52,9 +54,18 @@ pub trait Diary {
 
     fn get_youngest_entry_id(&self, diary_name: &str) -> Option<Result<DiaryId>>;
 
+    /// Get the id of the entry before `id` in the same diary
+    fn get_previous_entry_id(&self, id: &DiaryId) -> Result<Option<DiaryId>>;
+
     /// Get all diary names
     fn diary_names(&self) -> Result<DiaryNameIterator>;
 
+    /// Get the ids of the entries of all diaries which were written on the month and day of
+    /// `date` in previous years
+    ///
+    /// The ids are ordered by their date and time, newest first.
+    fn entries_on_this_day(&self, date: &NaiveDate) -> Result<Vec<DiaryId>>;
+
 }
 
 impl Diary for Store {
@@ -146,6 +157,20 @@ impl Diary for Store {
         }
     }
 
+    fn get_previous_entry_id(&self, id: &DiaryId) -> Result<Option<DiaryId>> {
+        let mut previous = None;
+
+        for sid in Diary::entries(self, id.diary_name())? {
+            let other = DiaryId::from_storeid(&sid?)?;
+
+            if other < *id && previous.as_ref().map(|p| other > *p).unwrap_or(true) {
+                previous = Some(other);
+            }
+        }
+
+        Ok(previous)
+    }
+
     /// Get all diary names
     fn diary_names(&self) -> Result<DiaryNameIterator> {
         self.entries()
@@ -153,5 +178,72 @@ impl Diary for Store {
             .map_err(::error::DiaryError::from)
     }
 
+    fn entries_on_this_day(&self, date: &NaiveDate) -> Result<Vec<DiaryId>> {
+        let mut names = BTreeSet::new();
+        for name in self.diary_names()? {
+            names.insert(name?);
+        }
+
+        let mut ids = Vec::new();
+        for name in names {
+            debug!("Searching entries on this day in diary '{}'", name);
+            for sid in Diary::entries(self, &name)? {
+                let id = DiaryId::from_storeid(&sid?)?;
+                if is_on_this_day(&id, date) {
+                    ids.push(id);
+                }
+            }
+        }
+
+        ids.sort_by(|a, b| {
+            let a_dt : NaiveDateTime = a.clone().into();
+            let b_dt : NaiveDateTime = b.clone().into();
+            b_dt.cmp(&a_dt).then_with(|| a.diary_name().cmp(b.diary_name()))
+        });
+
+        Ok(ids)
+    }
+
+}
+
+/// Whether `id` was written on the month and day of `date`, in a year before `date`
+///
+/// On February 28th of a year which is not a leap year, entries of February 29th match as well.
+fn is_on_this_day(id: &DiaryId, date: &NaiveDate) -> bool {
+    let same_day = id.month() == date.month() && id.day() == date.day();
+    let leap_day = id.month() == 2 && id.day() == 29
+        && date.month() == 2 && date.day() == 28
+        && NaiveDate::from_ymd_opt(date.year(), 2, 29).is_none();
+
+    id.year() < date.year() && (same_day || leap_day)
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::naive::NaiveDate;
+
+    use diaryid::DiaryId;
+    use super::is_on_this_day;
+
+    #[test]
+    fn test_is_on_this_day() {
+        let date = NaiveDate::from_ymd(2018, 3, 5);
+
+        assert!(is_on_this_day(&DiaryId::new(String::from("d"), 2017, 3, 5, 12, 0, 0), &date));
+        assert!(is_on_this_day(&DiaryId::new(String::from("d"), 2001, 3, 5, 0, 0, 0), &date));
+        assert!(!is_on_this_day(&DiaryId::new(String::from("d"), 2018, 3, 5, 0, 0, 0), &date));
+        assert!(!is_on_this_day(&DiaryId::new(String::from("d"), 2017, 3, 6, 0, 0, 0), &date));
+        assert!(!is_on_this_day(&DiaryId::new(String::from("d"), 2017, 4, 5, 0, 0, 0), &date));
+    }
+
+    #[test]
+    fn test_is_on_this_day_leap_day() {
+        let leap_day = DiaryId::new(String::from("d"), 2016, 2, 29, 0, 0, 0);
+
+        assert!(is_on_this_day(&leap_day, &NaiveDate::from_ymd(2018, 2, 28)));
+        assert!(!is_on_this_day(&leap_day, &NaiveDate::from_ymd(2018, 3, 1)));
+        assert!(!is_on_this_day(&leap_day, &NaiveDate::from_ymd(2020, 2, 28)));
+        assert!(is_on_this_day(&leap_day, &NaiveDate::from_ymd(2020, 2, 29)));
+    }
 }
 
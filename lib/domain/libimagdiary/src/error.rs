@@ -29,6 +29,8 @@ error_chain! {
     links {
         StoreError(::libimagstore::error::StoreError, ::libimagstore::error::StoreErrorKind);
         EntryUtilError(::libimagentryutil::error::EntryUtilError, ::libimagentryutil::error::EntryUtilErrorKind);
+        LinkError(::libimagentrylink::error::LinkError, ::libimagentrylink::error::LinkErrorKind);
+        MarkdownError(::libimagentrymarkdown::error::MarkdownError, ::libimagentrymarkdown::error::MarkdownErrorKind);
     }
 
     errors {
@@ -87,6 +89,11 @@ error_chain! {
             display("Error while finding a diary name")
         }
 
+        TemplateError(msg: String) {
+            description("Error in diary template")
+            display("Error in diary template: {}", msg)
+        }
+
     }
 }
 
@@ -0,0 +1,300 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Exporting a diary
+//!
+//! A diary can be exported as one Markdown document, as one standalone HTML document or as a
+//! directory of HTML pages, with an index page for each year and each month.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use chrono::naive::NaiveDate;
+use chrono::naive::NaiveDateTime;
+
+use libimagstore::store::Entry;
+use libimagstore::store::Store;
+use libimagstore::storeid::IntoStoreId;
+use libimagentrylink::external::is_external_link_storeid;
+use libimagentrylink::internal::InternalLinker;
+use libimagentrymarkdown::html::to_html;
+use libimagutil::html::escape_html;
+use libimagutil::html::html_page;
+use libimagutil::html::write_file;
+
+use diary::Diary;
+use diaryid::DiaryId;
+use diaryid::FromStoreId;
+use error::Result;
+
+/// An entry of a diary, prepared for exporting
+#[derive(Debug, Clone)]
+pub struct ExportEntry {
+    id      : DiaryId,
+    content : String,
+    links   : Vec<String>,
+}
+
+impl ExportEntry {
+
+    pub fn new(id: DiaryId, content: String, links: Vec<String>) -> ExportEntry {
+        ExportEntry {
+            id: id,
+            content: content,
+            links: links,
+        }
+    }
+
+    pub fn id(&self) -> &DiaryId {
+        &self.id
+    }
+
+    pub fn content(&self) -> &String {
+        &self.content
+    }
+
+    /// The titles of the entries this entry links to
+    pub fn links(&self) -> &Vec<String> {
+        &self.links
+    }
+
+    /// The heading for this entry: the date, and the time if the entry is not a daily one
+    pub fn title(&self) -> String {
+        let dt : NaiveDateTime = self.id.clone().into();
+
+        if self.id.hour() == 0 && self.id.minute() == 0 && self.id.second() == 0 {
+            dt.format("%Y-%m-%d").to_string()
+        } else {
+            dt.format("%Y-%m-%d %H:%M:%S").to_string()
+        }
+    }
+
+    /// The name of the file for this entry in a HTML directory export, relative to the month
+    /// directory
+    pub fn file_name(&self) -> String {
+        format!("{:02}-{:02}{:02}{:02}.html",
+                self.id.day(),
+                self.id.hour(),
+                self.id.minute(),
+                self.id.second())
+    }
+
+}
+
+/// Collect the entries of a diary, ordered by their ID
+///
+/// If `from` or `until` are passed, only entries in this date range (both inclusive) are
+/// collected.
+pub fn collect_entries(store: &Store,
+                       diary_name: &str,
+                       from: Option<NaiveDate>,
+                       until: Option<NaiveDate>)
+    -> Result<Vec<ExportEntry>>
+{
+    let mut entries = Vec::new();
+
+    for sid in Diary::entries(store, diary_name)? {
+        let id   = DiaryId::from_storeid(&sid?)?;
+        let date = NaiveDate::from_ymd_opt(id.year(), id.month(), id.day());
+
+        let in_range = date
+            .map(|d| from.map(|f| f <= d).unwrap_or(true) && until.map(|u| d <= u).unwrap_or(true))
+            .unwrap_or(false);
+
+        if !in_range {
+            debug!("Not exporting {}, not in range", id);
+            continue;
+        }
+
+        let sid = id.clone().into_storeid()?;
+        match store.get(sid)? {
+            None        => warn!("Entry {} vanished, not exporting", id),
+            Some(entry) => {
+                let mut links = Vec::new();
+                for link in entry.get_internal_links()? {
+                    let link_id = link.get_store_id().clone();
+                    if is_external_link_storeid(&link_id) {
+                        continue;
+                    }
+
+                    match store.get(link_id.clone())? {
+                        Some(linked) => links.push(entry_title(&linked)),
+                        None         => links.push(link_id.local().display().to_string()),
+                    }
+                }
+
+                entries.push(ExportEntry::new(id, entry.get_content().clone(), links));
+            },
+        }
+    }
+
+    entries.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(entries)
+}
+
+/// The title of an entry: the first Markdown heading in its content or its ID
+fn entry_title(entry: &Entry) -> String {
+    entry.get_content()
+        .lines()
+        .map(str::trim)
+        .find(|line| line.starts_with('#'))
+        .map(|line| line.trim_left_matches('#').trim())
+        .and_then(|title| if title.is_empty() { None } else { Some(String::from(title)) })
+        .unwrap_or_else(|| entry.get_location().local().display().to_string())
+}
+
+/// Render the entries into one Markdown document
+pub fn to_markdown(diary_name: &str, entries: &[ExportEntry]) -> String {
+    let mut s = format!("# {}\n\n", diary_name);
+
+    for entry in entries {
+        s.push_str(&format!("## {}\n\n", entry.title()));
+
+        let content = entry.content().trim();
+        if !content.is_empty() {
+            s.push_str(content);
+            s.push_str("\n\n");
+        }
+
+        if !entry.links().is_empty() {
+            s.push_str("Linked:\n\n");
+            for link in entry.links() {
+                s.push_str(&format!("* {}\n", link));
+            }
+            s.push_str("\n");
+        }
+    }
+
+    s
+}
+
+/// Render the entries into one standalone HTML document
+pub fn to_html_document(diary_name: &str, entries: &[ExportEntry]) -> Result<String> {
+    let mut body = format!("<h1>{}</h1>\n", escape_html(diary_name));
+
+    for entry in entries {
+        body.push_str(&format!("<h2>{}</h2>\n", entry.title()));
+        body.push_str(&render_entry(entry)?);
+    }
+
+    Ok(html_page(diary_name, &body))
+}
+
+/// Render the entries into a directory of HTML pages
+///
+/// The directory contains an `index.html` listing the years, a directory per year with an
+/// `index.html` listing the months and a directory per month with an `index.html` and one page
+/// per entry.
+pub fn to_html_directory(diary_name: &str, entries: &[ExportEntry], dir: &Path) -> Result<()> {
+    let mut years : BTreeMap<i32, BTreeMap<u32, Vec<&ExportEntry>>> = BTreeMap::new();
+    for entry in entries {
+        years.entry(entry.id().year())
+            .or_insert_with(BTreeMap::new)
+            .entry(entry.id().month())
+            .or_insert_with(Vec::new)
+            .push(entry);
+    }
+
+    let mut index = format!("<h1>{}</h1>\n<ul>\n", escape_html(diary_name));
+    for (year, months) in years.iter() {
+        index.push_str(&format!("<li><a href=\"{0}/index.html\">{0}</a></li>\n", year));
+
+        let year_dir       = dir.join(format!("{}", year));
+        let mut year_index = format!("<p><a href=\"../index.html\">{}</a></p>\n<h1>{}</h1>\n<ul>\n",
+                                     escape_html(diary_name),
+                                     year);
+
+        for (month, month_entries) in months.iter() {
+            year_index.push_str(&format!("<li><a href=\"{0:02}/index.html\">{1}-{0:02}</a></li>\n",
+                                         month,
+                                         year));
+
+            let month_dir       = year_dir.join(format!("{:02}", month));
+            let mut month_index = format!("<p><a href=\"../index.html\">{}</a></p>\n<h1>{}-{:02}</h1>\n<ul>\n",
+                                          year,
+                                          year,
+                                          month);
+
+            for entry in month_entries {
+                month_index.push_str(&format!("<li><a href=\"{}\">{}</a></li>\n",
+                                              entry.file_name(),
+                                              entry.title()));
+
+                let body = format!("<p><a href=\"index.html\">{}-{:02}</a></p>\n<h1>{}</h1>\n{}",
+                                   year,
+                                   month,
+                                   entry.title(),
+                                   render_entry(entry)?);
+                write_file(&month_dir.join(entry.file_name()), &html_page(&entry.title(), &body))?;
+            }
+
+            month_index.push_str("</ul>\n");
+            let title = format!("{}-{:02}", year, month);
+            write_file(&month_dir.join("index.html"), &html_page(&title, &month_index))?;
+        }
+
+        year_index.push_str("</ul>\n");
+        write_file(&year_dir.join("index.html"), &html_page(&format!("{}", year), &year_index))?;
+    }
+
+    index.push_str("</ul>\n");
+    write_file(&dir.join("index.html"), &html_page(diary_name, &index)).map_err(From::from)
+}
+
+fn render_entry(entry: &ExportEntry) -> Result<String> {
+    let mut s = to_html(entry.content())?;
+
+    if !entry.links().is_empty() {
+        s.push_str("<p>Linked:</p>\n<ul>\n");
+        for link in entry.links() {
+            s.push_str(&format!("<li>{}</li>\n", escape_html(link)));
+        }
+        s.push_str("</ul>\n");
+    }
+
+    Ok(s)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_to_markdown() {
+        let entries = vec![
+            ExportEntry::new(DiaryId::new(String::from("test"), 2018, 3, 5, 0, 0, 0),
+                             String::from("First\n"),
+                             vec![String::from("Some note")]),
+            ExportEntry::new(DiaryId::new(String::from("test"), 2018, 3, 6, 12, 30, 0),
+                             String::from("Second"),
+                             vec![]),
+        ];
+
+        let md = to_markdown("test", &entries);
+        assert_eq!(md, "# test\n\n## 2018-03-05\n\nFirst\n\nLinked:\n\n* Some note\n\n## 2018-03-06 12:30:00\n\nSecond\n\n");
+    }
+
+    #[test]
+    fn test_file_name() {
+        let entry = ExportEntry::new(DiaryId::new(String::from("test"), 2018, 3, 5, 9, 5, 1),
+                                     String::new(),
+                                     vec![]);
+        assert_eq!(entry.file_name(), "05-090501.html");
+    }
+}
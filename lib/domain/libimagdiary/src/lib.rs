@@ -42,13 +42,19 @@ extern crate toml_query;
 extern crate itertools;
 #[macro_use] extern crate error_chain;
 extern crate filters;
+extern crate handlebars;
+extern crate serde;
+#[macro_use] extern crate serde_derive;
 
 #[macro_use] extern crate libimagstore;
 #[macro_use] extern crate libimagentryutil;
 extern crate libimagerror;
 extern crate libimagentryedit;
 extern crate libimagentryview;
+extern crate libimagentrylink;
+extern crate libimagentrymarkdown;
 extern crate libimagrt;
+extern crate libimagutil;
 
 module_entry_path_mod!("diary");
 
@@ -58,6 +64,9 @@ pub mod diaryid;
 pub mod diary;
 pub mod is_in_diary;
 pub mod entry;
+pub mod export;
 pub mod iter;
+pub mod stats;
+pub mod template;
 pub mod viewer;
 
@@ -0,0 +1,244 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Statistics about a diary
+//!
+//! Word counts, entries per month, writing streaks and gaps, all based on the dates of the
+//! `DiaryId`s.
+
+use std::collections::BTreeMap;
+
+use chrono::Duration;
+use chrono::naive::NaiveDate;
+
+use libimagstore::store::Store;
+use libimagstore::storeid::IntoStoreId;
+
+use diary::Diary;
+use diaryid::DiaryId;
+use diaryid::FromStoreId;
+use error::Result;
+
+/// A range of days, both inclusive
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DateRange {
+    pub start : NaiveDate,
+    pub end   : NaiveDate,
+}
+
+impl DateRange {
+
+    /// The number of days in this range
+    pub fn days(&self) -> i64 {
+        self.end.signed_duration_since(self.start).num_days() + 1
+    }
+
+}
+
+/// Number of entries and words of a month
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MonthStats {
+    pub entries : usize,
+    pub words   : usize,
+}
+
+/// Statistics about a diary
+#[derive(Debug, Clone, Default)]
+pub struct DiaryStats {
+    entries   : usize,
+    words     : usize,
+    per_month : BTreeMap<(i32, u32), MonthStats>,
+    per_day   : BTreeMap<NaiveDate, usize>,
+}
+
+impl DiaryStats {
+
+    /// Compute the statistics of the diary `diary_name`
+    pub fn compute(store: &Store, diary_name: &str) -> Result<DiaryStats> {
+        let mut stats = DiaryStats::default();
+
+        for sid in Diary::entries(store, diary_name)? {
+            let id  = DiaryId::from_storeid(&sid?)?;
+            let sid = id.clone().into_storeid()?;
+
+            match store.get(sid)? {
+                Some(entry) => stats.add(&id, entry.get_content()),
+                None        => warn!("Entry {} vanished", id),
+            }
+        }
+
+        Ok(stats)
+    }
+
+    /// Add an entry with `content` to the statistics
+    pub fn add(&mut self, id: &DiaryId, content: &str) {
+        let date = match NaiveDate::from_ymd_opt(id.year(), id.month(), id.day()) {
+            Some(date) => date,
+            None       => {
+                warn!("Entry {} has an invalid date, ignoring", id);
+                return;
+            }
+        };
+
+        let words = count_words(content);
+
+        self.entries += 1;
+        self.words   += words;
+
+        {
+            let month = self.per_month.entry((id.year(), id.month())).or_insert_with(MonthStats::default);
+            month.entries += 1;
+            month.words   += words;
+        }
+
+        *self.per_day.entry(date).or_insert(0) += words;
+    }
+
+    /// The number of entries
+    pub fn entries(&self) -> usize {
+        self.entries
+    }
+
+    /// The number of words in all entries
+    pub fn words(&self) -> usize {
+        self.words
+    }
+
+    /// The number of entries and words per (year, month)
+    pub fn per_month(&self) -> &BTreeMap<(i32, u32), MonthStats> {
+        &self.per_month
+    }
+
+    /// The number of words written per day, for each day with entries
+    pub fn per_day(&self) -> &BTreeMap<NaiveDate, usize> {
+        &self.per_day
+    }
+
+    /// The first and the last day with an entry
+    pub fn range(&self) -> Option<DateRange> {
+        match (self.per_day.keys().next(), self.per_day.keys().next_back()) {
+            (Some(start), Some(end)) => Some(DateRange { start: *start, end: *end }),
+            _ => None,
+        }
+    }
+
+    /// All streaks: ranges of consecutive days with entries
+    pub fn streaks(&self) -> Vec<DateRange> {
+        let mut streaks : Vec<DateRange> = Vec::new();
+
+        for date in self.per_day.keys() {
+            let continues = streaks
+                .last()
+                .map(|s| s.end + Duration::days(1) == *date)
+                .unwrap_or(false);
+
+            if continues {
+                streaks.last_mut().unwrap().end = *date; // checked above
+            } else {
+                streaks.push(DateRange { start: *date, end: *date });
+            }
+        }
+
+        streaks
+    }
+
+    /// The longest streak, the earliest one if there are several
+    pub fn longest_streak(&self) -> Option<DateRange> {
+        self.streaks()
+            .into_iter()
+            .fold(None, |longest: Option<DateRange>, s| match longest {
+                Some(l) => if s.days() > l.days() { Some(s) } else { Some(l) },
+                None    => Some(s),
+            })
+    }
+
+    /// The streak which ends on `today` or yesterday, if there is one
+    pub fn current_streak(&self, today: &NaiveDate) -> Option<DateRange> {
+        self.streaks()
+            .into_iter()
+            .last()
+            .and_then(|s| if s.end == *today || s.end + Duration::days(1) == *today {
+                Some(s)
+            } else {
+                None
+            })
+    }
+
+    /// Gaps between entries of at least `min_days` days without an entry
+    pub fn gaps(&self, min_days: i64) -> Vec<DateRange> {
+        let streaks = self.streaks();
+
+        streaks
+            .iter()
+            .zip(streaks.iter().skip(1))
+            .map(|(a, b)| DateRange { start: a.end + Duration::days(1), end: b.start - Duration::days(1) })
+            .filter(|gap| gap.days() >= min_days)
+            .collect()
+    }
+
+}
+
+/// Count the words of a text, that is the whitespace separated parts which contain at least one
+/// alphanumeric character
+pub fn count_words(s: &str) -> usize {
+    s.split_whitespace()
+        .filter(|w| w.chars().any(char::is_alphanumeric))
+        .count()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn id(y: i32, m: u32, d: u32) -> DiaryId {
+        DiaryId::new(String::from("test"), y, m, d, 0, 0, 0)
+    }
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd(y, m, d)
+    }
+
+    #[test]
+    fn test_count_words() {
+        assert_eq!(count_words("# Heading\n\nSome words - here.\n"), 4);
+        assert_eq!(count_words(""), 0);
+    }
+
+    #[test]
+    fn test_streaks_and_gaps() {
+        let mut stats = DiaryStats::default();
+        stats.add(&id(2018, 2, 27), "a b");
+        stats.add(&id(2018, 2, 28), "a");
+        stats.add(&id(2018, 3, 1), "a");
+        stats.add(&id(2018, 3, 1), "b c");
+        stats.add(&id(2018, 3, 5), "a");
+        stats.add(&id(2018, 3, 6), "a");
+
+        assert_eq!(stats.entries(), 6);
+        assert_eq!(stats.words(), 8);
+        assert_eq!(stats.per_month().get(&(2018, 3)), Some(&MonthStats { entries: 4, words: 5 }));
+        assert_eq!(stats.per_day().get(&date(2018, 3, 1)), Some(&3));
+
+        assert_eq!(stats.longest_streak(), Some(DateRange { start: date(2018, 2, 27), end: date(2018, 3, 1) }));
+        assert_eq!(stats.current_streak(&date(2018, 3, 7)), Some(DateRange { start: date(2018, 3, 5), end: date(2018, 3, 6) }));
+        assert_eq!(stats.current_streak(&date(2018, 3, 8)), None);
+        assert_eq!(stats.gaps(1), vec![DateRange { start: date(2018, 3, 2), end: date(2018, 3, 4) }]);
+        assert!(stats.gaps(4).is_empty());
+    }
+}
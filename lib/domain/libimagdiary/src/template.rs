@@ -0,0 +1,250 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Templates for new diary entries
+//!
+//! A template can be configured for each diary. It is a handlebars template which is rendered
+//! into new entries before they are edited:
+//!
+//! ```toml
+//! [diary.diaries.<name>]
+//! template = """
+//! # {{weekday}}, {{date}}
+//!
+//! {{#each answers}}
+//! ## {{question}}
+//!
+//! {{answer}}
+//!
+//! {{/each}}
+//! """
+//! prompts = [ "What did you do today", "What are you grateful for" ]
+//! ```
+//!
+//! The following variables are available in the template:
+//!
+//! * `diary`: the name of the diary
+//! * `date`, `time`: the date and time of the entry, as `YYYY-MM-DD` and `HH:MM:SS`
+//! * `year`, `month`, `day`, `weekday`: parts of the date
+//! * `previous`, `previous_date`: the content and the date of the previous entry in the diary
+//! * `answers`: a list of the prompts (`question`) and what was answered (`answer`)
+//!
+//! If prompts are configured without a template, each answer is rendered below its question.
+
+use chrono::naive::NaiveDateTime;
+use handlebars::Handlebars;
+use toml::Value;
+use toml_query::read::TomlValueReadExt;
+
+use libimagrt::runtime::Runtime;
+use libimagstore::store::Store;
+use libimagstore::store::FileLockEntry;
+use libimagstore::storeid::IntoStoreId;
+
+use config::get_diary_config_section;
+use diary::Diary;
+use diaryid::DiaryId;
+use entry::DiaryEntry;
+use error::DiaryError as DE;
+use error::DiaryErrorKind as DEK;
+use error::Result;
+
+const DEFAULT_TEMPLATE : &'static str = "{{#each answers}}## {{question}}\n\n{{answer}}\n\n{{/each}}";
+
+/// The template of a diary
+#[derive(Debug, Clone)]
+pub struct DiaryTemplate {
+    template : Option<String>,
+    prompts  : Vec<String>,
+}
+
+#[derive(Serialize)]
+struct Answer {
+    question : String,
+    answer   : String,
+}
+
+#[derive(Serialize)]
+struct TemplateData {
+    diary         : String,
+    date          : String,
+    time          : String,
+    year          : i32,
+    month         : u32,
+    day           : u32,
+    weekday       : String,
+    previous      : Option<String>,
+    previous_date : Option<String>,
+    answers       : Vec<Answer>,
+}
+
+impl DiaryTemplate {
+
+    pub fn new(template: Option<String>, prompts: Vec<String>) -> DiaryTemplate {
+        DiaryTemplate {
+            template: template,
+            prompts: prompts,
+        }
+    }
+
+    /// Read the template of the diary `diary_name` from the configuration
+    ///
+    /// Returns `None` if there is neither a template nor prompts configured.
+    pub fn from_config(rt: &Runtime, diary_name: &str) -> Result<Option<DiaryTemplate>> {
+        let section = match get_diary_config_section(rt) {
+            Some(section) => section,
+            None          => return Ok(None),
+        };
+
+        let template_path = format!("diaries.{}.template", diary_name);
+        let template      = match section.read(&template_path) {
+            Ok(Some(&Value::String(ref s))) => Some(s.clone()),
+            Ok(Some(_)) => {
+                let s = format!("Type error at 'diary.{}': should be a string", template_path);
+                return Err(s).map_err(From::from)
+            },
+            Ok(None) | Err(_) => None,
+        };
+
+        let prompts_path = format!("diaries.{}.prompts", diary_name);
+        let prompts      = match section.read(&prompts_path) {
+            Ok(Some(&Value::Array(ref ary))) => {
+                let mut prompts = Vec::new();
+                for elem in ary {
+                    match *elem {
+                        Value::String(ref s) => prompts.push(s.clone()),
+                        _ => {
+                            let s = format!("Type error at 'diary.{}': should be an array of strings", prompts_path);
+                            return Err(s).map_err(From::from)
+                        },
+                    }
+                }
+                prompts
+            },
+            Ok(Some(_)) => {
+                let s = format!("Type error at 'diary.{}': should be an array of strings", prompts_path);
+                return Err(s).map_err(From::from)
+            },
+            Ok(None) | Err(_) => Vec::new(),
+        };
+
+        if template.is_none() && prompts.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(DiaryTemplate::new(template, prompts)))
+        }
+    }
+
+    /// The questions which should be answered before rendering the template
+    pub fn prompts(&self) -> &Vec<String> {
+        &self.prompts
+    }
+
+    /// Render the template for the entry `id`
+    ///
+    /// `answers` are the answers to the prompts, in the same order.
+    pub fn render(&self,
+                  id: &DiaryId,
+                  previous: Option<(&DiaryId, &str)>,
+                  answers: Vec<String>)
+        -> Result<String>
+    {
+        let dt : NaiveDateTime = id.clone().into();
+
+        let data = TemplateData {
+            diary         : id.diary_name().clone(),
+            date          : dt.format("%Y-%m-%d").to_string(),
+            time          : dt.format("%H:%M:%S").to_string(),
+            year          : id.year(),
+            month         : id.month(),
+            day           : id.day(),
+            weekday       : dt.format("%A").to_string(),
+            previous      : previous.map(|(_, content)| String::from(content)),
+            previous_date : previous.map(|(id, _)| {
+                let dt : NaiveDateTime = id.clone().into();
+                dt.format("%Y-%m-%d").to_string()
+            }),
+            answers       : self.prompts
+                .iter()
+                .cloned()
+                .zip(answers.into_iter())
+                .map(|(question, answer)| Answer { question: question, answer: answer })
+                .collect(),
+        };
+
+        let template = self.template.as_ref().map(String::as_str).unwrap_or(DEFAULT_TEMPLATE);
+
+        let mut hb = Handlebars::new();
+        hb.register_escape_fn(::handlebars::no_escape);
+        hb.template_render(template, &data)
+            .map_err(|e| DE::from_kind(DEK::TemplateError(e.to_string())))
+    }
+
+    /// Fill a new entry with the rendered template
+    ///
+    /// Entries which have content already are not touched, so the template is only used for new
+    /// entries.
+    pub fn prefill<'a>(&self, store: &'a Store, entry: &mut FileLockEntry<'a>, answers: Vec<String>)
+        -> Result<()>
+    {
+        if !entry.get_content().is_empty() {
+            debug!("Entry {} has content already, not using template", entry.get_location());
+            return Ok(());
+        }
+
+        let id       = entry.diary_id()?;
+        let previous = match store.get_previous_entry_id(&id)? {
+            None              => None,
+            Some(previous_id) => {
+                let sid = previous_id.clone().into_storeid()?;
+                store.get(sid)?.map(|e| (previous_id, e.get_content().clone()))
+            },
+        };
+
+        let rendered = self.render(&id, previous.as_ref().map(|&(ref id, ref c)| (id, c.as_str())), answers)?;
+        *entry.get_content_mut() = rendered;
+        Ok(())
+    }
+
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let template = DiaryTemplate::new(Some(String::from("{{weekday}}, {{date}}\n{{#each answers}}{{question}}: {{answer}}\n{{/each}}{{previous_date}}")),
+                                          vec![String::from("Q")]);
+        let id       = DiaryId::new(String::from("test"), 2018, 3, 5, 0, 0, 0);
+        let previous = DiaryId::new(String::from("test"), 2018, 3, 4, 0, 0, 0);
+
+        let rendered = template.render(&id, Some((&previous, "content")), vec![String::from("A")]).unwrap();
+        assert_eq!(rendered, "Monday, 2018-03-05\nQ: A\n2018-03-04");
+    }
+
+    #[test]
+    fn test_render_default() {
+        let template = DiaryTemplate::new(None, vec![String::from("Q1"), String::from("Q2")]);
+        let id       = DiaryId::new(String::from("test"), 2018, 3, 5, 0, 0, 0);
+
+        let rendered = template.render(&id, None, vec![String::from("A1"), String::from("A2")]).unwrap();
+        assert_eq!(rendered, "## Q1\n\nA1\n\n## Q2\n\nA2\n\n");
+    }
+}
@@ -23,6 +23,9 @@ use toml_query::insert::TomlValueInsertExt;
 use chrono::NaiveDateTime;
 use chrono::Local;
 use chrono::NaiveDate;
+use kairos::timetype::TimeType;
+use kairos::parser::parse;
+use kairos::parser::Parsed;
 
 use error::HabitError as HE;
 use error::HabitErrorKind as HEK;
@@ -30,7 +33,10 @@ use error::*;
 use iter::HabitInstanceStoreIdIterator;
 use util::IsHabitCheck;
 use util::get_string_header_from_entry;
+use util::get_float_header_from_entry;
 use instance::IsHabitInstance;
+use instance::HabitInstance;
+use stats::HabitStatistics;
 
 use libimagentrylink::internal::InternalLinker;
 use libimagstore::store::Store;
@@ -78,6 +84,12 @@ pub trait HabitTemplate : Sized {
     /// Get the date of the next date when the habit should be done
     fn next_instance_date(&self) -> Result<Option<NaiveDate>>;
 
+    /// Get all dates between `start` and `end` (both inclusive) when the habit should be done
+    fn instance_dates_between(&self, start: &NaiveDate, end: &NaiveDate) -> Result<Vec<NaiveDate>>;
+
+    /// Compute statistics for this habit for the occurrences between `start` and `today`
+    fn statistics(&self, store: &Store, start: &NaiveDate, today: &NaiveDate) -> Result<HabitStatistics>;
+
     /// Check whether the instance is a habit by checking its headers for the habit data
     fn is_habit_template(&self) -> Result<bool>;
 
@@ -87,6 +99,23 @@ pub trait HabitTemplate : Sized {
     fn habit_comment(&self) -> Result<String>;
     fn habit_until_date(&self) -> Result<Option<String>>;
 
+    /// The unit of the target amount, e.g. "km", for habits with a target amount
+    fn habit_unit(&self) -> Result<Option<String>>;
+
+    /// The amount which should be done per occurrence, e.g. 5.0 (km)
+    fn habit_target(&self) -> Result<Option<f64>>;
+
+    /// Set the target amount (and unit) for this habit
+    ///
+    /// Existing instances of the habit which have no amount are migrated: As they were marked as
+    /// done, they get the target amount.
+    ///
+    /// Returns the number of migrated instances.
+    fn set_habit_target(&mut self, store: &Store, target: f64, unit: Option<String>) -> Result<usize>;
+
+    /// Get the dates and amounts of all instances of this habit
+    fn instance_amounts(&self, store: &Store) -> Result<Vec<(NaiveDate, Option<f64>)>>;
+
     fn instance_exists_for_date(&self, date: &NaiveDate) -> Result<bool>;
 
     /// Create a StoreId for a habit name and a date the habit should be instantiated for
@@ -139,38 +168,17 @@ impl HabitTemplate for Entry {
     }
 
     fn next_instance_date_after(&self, base: &NaiveDateTime) -> Result<Option<NaiveDate>> {
-        use kairos::timetype::TimeType;
-        use kairos::parser::parse;
-        use kairos::parser::Parsed;
         use kairos::iter::extensions::Every;
 
-        let date_from_s = |r: String| -> Result<TimeType> {
-            match parse(&r)? {
-                Parsed::TimeType(tt) => Ok(tt),
-                Parsed::Iterator(_) => {
-                    Err(format!("'{}' yields an iterator. Cannot use.", r).into())
-                },
-            }
-        };
-
         debug!("Base is {:?}", base);
 
-        let basedate  = date_from_s(self.habit_basedate()?)?;
+        let basedate  = timetype_from_string(self.habit_basedate()?)?;
         debug!("Basedate is {:?}", basedate);
 
-        let increment = date_from_s(self.habit_recur_spec()?)?;
+        let increment = timetype_from_string(self.habit_recur_spec()?)?;
         debug!("Increment is {:?}", increment);
 
-        let until = self.habit_until_date()?.map(|s| -> Result<_> {
-            try!(date_from_s(s))
-                .calculate()?
-                .get_moment()
-                .map(Clone::clone)
-                .ok_or_else(|| {
-                    let kind : HEK = "until-date seems to have non-date value".to_owned().into();
-                    HE::from_kind(kind)
-                })
-        });
+        let until = self.habit_until_date()?.map(until_date_from_string);
 
         debug!("Until-Date is {:?}", basedate);
 
@@ -201,8 +209,6 @@ impl HabitTemplate for Entry {
 
     /// Get the date of the next date when the habit should be done
     fn next_instance_date(&self) -> Result<Option<NaiveDate>> {
-        use kairos::timetype::TimeType;
-
         let today = TimeType::today();
         let today = today.get_moment().unwrap(); // we know this is safe.
         debug!("Today is {:?}", today);
@@ -210,6 +216,59 @@ impl HabitTemplate for Entry {
         self.next_instance_date_after(&today.date().and_hms(0, 0, 0))
     }
 
+    fn instance_dates_between(&self, start: &NaiveDate, end: &NaiveDate) -> Result<Vec<NaiveDate>> {
+        use kairos::iter::extensions::Every;
+
+        let basedate  = timetype_from_string(self.habit_basedate()?)?;
+        let increment = timetype_from_string(self.habit_recur_spec()?)?;
+        let until     = match self.habit_until_date()? {
+            Some(s) => Some(until_date_from_string(s)?.date()),
+            None    => None,
+        };
+
+        let mut dates = vec![];
+        for element in basedate.every(increment)? {
+            let element = element?.calculate()?;
+            let date    = match element.get_moment() {
+                Some(ndt) => ndt.date(),
+                None      => return Err("Iterator seems to return bogus values.".to_owned().into()),
+            };
+
+            if date > *end || until.map(|u| date > u).unwrap_or(false) {
+                break;
+            }
+
+            if date >= *start {
+                dates.push(date);
+            }
+        }
+
+        Ok(dates)
+    }
+
+    fn instance_amounts(&self, store: &Store) -> Result<Vec<(NaiveDate, Option<f64>)>> {
+        let mut amounts = vec![];
+        for id in self.linked_instances()? {
+            if let Some(instance) = store.get(id?)? {
+                amounts.push((instance.get_date()?, instance.get_amount()?));
+            }
+        }
+
+        Ok(amounts)
+    }
+
+    fn statistics(&self, store: &Store, start: &NaiveDate, today: &NaiveDate) -> Result<HabitStatistics> {
+        // We need the first occurrence after `today` as well, to know whether the instances done
+        // after the last occurrence until today count for it
+        let lookahead = self
+            .next_instance_date_after(&today.succ().and_hms(0, 0, 0))?
+            .unwrap_or_else(|| today.succ());
+
+        let expected = self.instance_dates_between(start, &lookahead)?;
+        let done     = self.instance_amounts(store)?;
+        Ok(HabitStatistics::new(expected, done, self.habit_target()?, *today))
+    }
+
     /// Check whether the instance is a habit by checking its headers for the habit data
     fn is_habit_template(&self) -> Result<bool> {
         self.is::<IsHabitTemplate>().map_err(From::from)
@@ -238,6 +297,40 @@ impl HabitTemplate for Entry {
             .map(|os| os.map(String::from))
     }
 
+    fn habit_unit(&self) -> Result<Option<String>> {
+        self.get_header()
+            .read_string("habit.template.unit")
+            .map_err(From::from)
+            .map(|os| os.map(String::from))
+    }
+
+    fn habit_target(&self) -> Result<Option<f64>> {
+        get_float_header_from_entry(self, "habit.template.target")
+    }
+
+    fn set_habit_target(&mut self, store: &Store, target: f64, unit: Option<String>) -> Result<usize> {
+        {
+            let hdr = self.get_header_mut();
+            let _   = hdr.insert("habit.template.target", Value::Float(target))?;
+            if let Some(unit) = unit {
+                let _ = hdr.insert("habit.template.unit", Value::String(unit))?;
+            }
+        }
+
+        let mut migrated = 0;
+        for id in self.linked_instances()? {
+            if let Some(mut instance) = store.get(id?)? {
+                if instance.get_amount()?.is_none() {
+                    debug!("Migrating {:?}: amount = {}", instance.get_location(), target);
+                    instance.set_amount(target)?;
+                    migrated += 1;
+                }
+            }
+        }
+
+        Ok(migrated)
+    }
+
     fn instance_exists_for_date(&self, date: &NaiveDate) -> Result<bool> {
         let name = self.habit_name()?;
         let date = date_to_string(date);
@@ -260,6 +353,26 @@ impl HabitTemplate for Entry {
 
 }
 
+fn timetype_from_string(s: String) -> Result<TimeType> {
+    match parse(&s)? {
+        Parsed::TimeType(tt) => Ok(tt),
+        Parsed::Iterator(_) => {
+            Err(format!("'{}' yields an iterator. Cannot use.", s).into())
+        },
+    }
+}
+
+fn until_date_from_string(s: String) -> Result<NaiveDateTime> {
+    timetype_from_string(s)?
+        .calculate()?
+        .get_moment()
+        .map(Clone::clone)
+        .ok_or_else(|| {
+            let kind : HEK = "until-date seems to have non-date value".to_owned().into();
+            HE::from_kind(kind)
+        })
+}
+
 fn instance_id_for_name_and_datestr(habit_name: &String, habit_date: &String) -> Result<StoreId> {
     use module_path::ModuleEntryPath;
 
@@ -293,6 +406,8 @@ pub mod builder {
         basedate: Option<NaiveDate>,
         recurspec: Option<String>,
         untildate: Option<NaiveDate>,
+        target: Option<f64>,
+        unit: Option<String>,
     }
 
     impl HabitBuilder {
@@ -322,6 +437,16 @@ pub mod builder {
             self
         }
 
+        pub fn with_target(mut self, target: f64) -> Self {
+            self.target = Some(target);
+            self
+        }
+
+        pub fn with_unit(mut self, unit: String) -> Self {
+            self.unit = Some(unit);
+            self
+        }
+
         pub fn build<'a>(self, store: &'a Store) -> Result<FileLockEntry<'a>> {
             #[inline]
             fn mkerr(s: &'static str) -> HE {
@@ -348,6 +473,11 @@ pub mod builder {
                 }
             }
 
+            if self.unit.is_some() && self.target.is_none() {
+                let e = HE::from_kind(HEK::HabitBuilderLogicError("unit without target amount"));
+                return Err(e);
+            }
+
             if let Err(e) = ::kairos::parser::parse(&recur) {
                 debug!("Kairos failed: {:?}", e);
                 return Err(e).map_err(From::from);
@@ -375,6 +505,14 @@ pub mod builder {
                 try!(entry.get_header_mut().insert("habit.template.until", Value::String(until)));
             }
 
+            if let Some(target) = self.target {
+                try!(entry.get_header_mut().insert("habit.template.target", Value::Float(target)));
+            }
+
+            if let Some(unit) = self.unit {
+                try!(entry.get_header_mut().insert("habit.template.unit", Value::String(unit)));
+            }
+
             debug!("Success: Created entry in store and set headers");
             Ok(entry)
         }
@@ -389,6 +527,8 @@ pub mod builder {
                 basedate: None,
                 recurspec: None,
                 untildate: None,
+                target: None,
+                unit: None,
             }
         }
     }
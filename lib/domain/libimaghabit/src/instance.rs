@@ -20,6 +20,7 @@
 use chrono::NaiveDate;
 use toml::Value;
 use toml_query::set::TomlValueSetExt;
+use toml_query::insert::TomlValueInsertExt;
 
 use error::*;
 use util::*;
@@ -44,6 +45,12 @@ pub trait HabitInstance {
     fn get_comment(&self) -> Result<String>;
     fn set_comment(&mut self, c: String) -> Result<()>;
     fn get_template_name(&self) -> Result<String>;
+
+    /// Get the amount which was done in this instance, for habits with a target amount
+    ///
+    /// Instances which were created before the habit got a target have no amount.
+    fn get_amount(&self) -> Result<Option<f64>>;
+    fn set_amount(&mut self, amount: f64) -> Result<()>;
 }
 
 provide_kindflag_path!(pub IsHabitInstance, "habit.instance.is_habit_instance");
@@ -84,4 +91,15 @@ impl HabitInstance for Entry {
         get_string_header_from_entry(self, "habit.instance.name")
     }
 
+    fn get_amount(&self) -> Result<Option<f64>> {
+        get_float_header_from_entry(self, "habit.instance.amount")
+    }
+
+    fn set_amount(&mut self, amount: f64) -> Result<()> {
+        self.get_header_mut()
+            .insert("habit.instance.amount", Value::Float(amount))
+            .map_err(From::from)
+            .map(|_| ())
+    }
+
 }
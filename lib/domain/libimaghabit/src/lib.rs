@@ -54,6 +54,7 @@ pub mod habit;
 pub mod instance;
 pub mod iter;
 pub mod result;
+pub mod stats;
 pub mod store;
 pub mod util;
 
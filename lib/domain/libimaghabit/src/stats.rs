@@ -0,0 +1,283 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Statistics about how well a habit is followed
+//!
+//! An occurrence of a habit (a date when the habit should be done, as calculated from the
+//! recurrence specification of the template) counts as fulfilled if there is an instance of the
+//! habit dated between this occurrence and the next one. For habits with a target amount, the
+//! amounts of these instances have to sum up to the target.
+//! The most recent occurrence is still "open" until the next occurrence is reached, so it is
+//! neither counted as missed nor does it break the current streak if it is not fulfilled yet.
+
+use chrono::NaiveDate;
+
+/// An occurrence of a habit
+#[derive(Debug, Clone, PartialEq)]
+pub struct Occurrence {
+    pub date: NaiveDate,
+
+    /// The sum of the amounts of the instances for this occurrence
+    ///
+    /// Instances without amount count with the target amount of the habit.
+    /// Always zero for habits without target amount.
+    pub amount: f64,
+
+    pub fulfilled: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HabitStatistics {
+    /// All occurrences up to (and including) `today`
+    occurrences: Vec<Occurrence>,
+
+    /// Dates of the instances of the habit
+    done: Vec<NaiveDate>,
+
+    target: Option<f64>,
+
+    today: NaiveDate,
+}
+
+impl HabitStatistics {
+
+    /// Compute statistics
+    ///
+    /// `expected` are the dates of the occurrences of the habit, which may contain dates after
+    /// `today` (these are only used to determine the fulfillment of the last occurrence up to
+    /// `today`), `done` are the dates and amounts of the instances of the habit and `target` the
+    /// target amount of the habit, if any.
+    pub fn new(mut expected: Vec<NaiveDate>,
+               mut done: Vec<(NaiveDate, Option<f64>)>,
+               target: Option<f64>,
+               today: NaiveDate)
+        -> Self
+    {
+        expected.sort();
+        expected.dedup();
+        done.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let occurrences = expected
+            .iter()
+            .enumerate()
+            .take_while(|&(_, date)| *date <= today)
+            .map(|(i, date)| {
+                let next      = expected.get(i + 1);
+                let instances = done
+                    .iter()
+                    .filter(|&&(ref d, _)| d >= date && next.map(|n| d < n).unwrap_or(true))
+                    .collect::<Vec<_>>();
+
+                let amount : f64 = instances
+                    .iter()
+                    .map(|&&(_, amount)| amount.or(target).unwrap_or(0.0))
+                    .sum();
+
+                let fulfilled = !instances.is_empty() && target.map(|t| amount >= t).unwrap_or(true);
+
+                Occurrence {
+                    date: *date,
+                    amount: amount,
+                    fulfilled: fulfilled,
+                }
+            })
+            .collect();
+
+        HabitStatistics {
+            occurrences: occurrences,
+            done: done.into_iter().map(|(d, _)| d).collect(),
+            target: target,
+            today: today,
+        }
+    }
+
+    /// All occurrences up to today
+    pub fn occurrences(&self) -> &Vec<Occurrence> {
+        &self.occurrences
+    }
+
+    /// The dates of all instances of the habit
+    pub fn done(&self) -> &Vec<NaiveDate> {
+        &self.done
+    }
+
+    pub fn target(&self) -> Option<f64> {
+        self.target
+    }
+
+    pub fn today(&self) -> &NaiveDate {
+        &self.today
+    }
+
+    /// The occurrences which are over, i.e. all but the most recent one if that one is not
+    /// fulfilled yet
+    fn closed_occurrences(&self) -> &[Occurrence] {
+        match self.occurrences.last() {
+            Some(o) if !o.fulfilled => &self.occurrences[..self.occurrences.len() - 1],
+            _                       => &self.occurrences[..],
+        }
+    }
+
+    /// The most recent occurrence, if it is not over yet
+    pub fn open_occurrence(&self) -> Option<&Occurrence> {
+        self.occurrences.last().and_then(|o| if o.fulfilled { None } else { Some(o) })
+    }
+
+    /// The dates of the occurrences which were missed
+    pub fn missed(&self) -> Vec<NaiveDate> {
+        self.closed_occurrences()
+            .iter()
+            .filter(|o| !o.fulfilled)
+            .map(|o| o.date)
+            .collect()
+    }
+
+    /// The occurrences which are done partially, i.e. where some, but not enough, amount was done
+    ///
+    /// This includes the open occurrence.
+    pub fn partial(&self) -> Vec<&Occurrence> {
+        self.occurrences
+            .iter()
+            .filter(|o| !o.fulfilled && o.amount > 0.0)
+            .collect()
+    }
+
+    /// The sum of the amounts done for all occurrences
+    pub fn total(&self) -> f64 {
+        self.occurrences.iter().map(|o| o.amount).sum()
+    }
+
+    /// The number of fulfilled occurrences
+    pub fn fulfilled(&self) -> usize {
+        self.occurrences.iter().filter(|o| o.fulfilled).count()
+    }
+
+    /// The rate of fulfilled occurrences, between 0.0 and 1.0
+    ///
+    /// Returns `None` if there are no occurrences which are over yet.
+    pub fn completion_rate(&self) -> Option<f64> {
+        let closed = self.closed_occurrences().len();
+        if closed == 0 {
+            None
+        } else {
+            Some(self.fulfilled() as f64 / closed as f64)
+        }
+    }
+
+    /// The number of consecutive fulfilled occurrences up to today
+    pub fn current_streak(&self) -> usize {
+        self.closed_occurrences()
+            .iter()
+            .rev()
+            .take_while(|o| o.fulfilled)
+            .count()
+    }
+
+    /// The highest number of consecutive fulfilled occurrences
+    pub fn longest_streak(&self) -> usize {
+        let mut longest = 0;
+        let mut current = 0;
+
+        for o in self.occurrences.iter() {
+            if o.fulfilled {
+                current += 1;
+                if current > longest {
+                    longest = current;
+                }
+            } else {
+                current = 0;
+            }
+        }
+
+        longest
+    }
+
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::NaiveDate;
+
+    use super::HabitStatistics;
+
+    fn d(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd(2018, 1, day)
+    }
+
+    fn done(dates: Vec<NaiveDate>) -> Vec<(NaiveDate, Option<f64>)> {
+        dates.into_iter().map(|d| (d, None)).collect()
+    }
+
+    #[test]
+    fn test_daily_streaks() {
+        let expected = (1..12).map(d).collect();
+        let done     = done(vec![d(1), d(2), d(4), d(5), d(6), d(8), d(9)]);
+        let stats    = HabitStatistics::new(expected, done, None, d(10));
+
+        assert_eq!(stats.occurrences().len(), 10);
+        assert_eq!(stats.missed(), vec![d(3), d(7)]);
+        assert_eq!(stats.fulfilled(), 7);
+        assert_eq!(stats.current_streak(), 2); // today is still open
+        assert_eq!(stats.longest_streak(), 3);
+        assert_eq!(stats.completion_rate(), Some(7.0 / 9.0));
+    }
+
+    #[test]
+    fn test_weekly_done_late() {
+        let expected = vec![d(1), d(8), d(15), d(22)];
+        let done     = done(vec![d(3), d(8), d(20)]);
+        let stats    = HabitStatistics::new(expected, done, None, d(16));
+
+        assert!(stats.missed().is_empty());
+        assert_eq!(stats.current_streak(), 3);
+        assert_eq!(stats.longest_streak(), 3);
+        assert_eq!(stats.completion_rate(), Some(1.0));
+    }
+
+    #[test]
+    fn test_nothing_due() {
+        let stats = HabitStatistics::new(vec![d(5)], vec![], None, d(1));
+
+        assert!(stats.occurrences().is_empty());
+        assert_eq!(stats.completion_rate(), None);
+        assert_eq!(stats.current_streak(), 0);
+    }
+
+    #[test]
+    fn test_quantified() {
+        let expected = (1..6).map(d).collect();
+        let done     = vec![
+            (d(1), Some(5.0)),
+            (d(2), Some(2.0)),
+            (d(2), Some(3.5)),
+            (d(3), Some(1.0)),
+            (d(4), None), // done before the habit got a target
+            (d(5), Some(2.0)),
+        ];
+        let stats = HabitStatistics::new(expected, done, Some(5.0), d(5));
+
+        assert_eq!(stats.fulfilled(), 3);
+        assert_eq!(stats.missed(), vec![d(3)]);
+        assert_eq!(stats.total(), 18.5);
+
+        let partial = stats.partial().into_iter().map(|o| o.date).collect::<Vec<_>>();
+        assert_eq!(partial, vec![d(3), d(5)]);
+        assert_eq!(stats.open_occurrence().map(|o| o.amount), Some(2.0));
+    }
+}
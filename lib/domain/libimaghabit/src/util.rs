@@ -89,3 +89,16 @@ pub fn get_string_header_from_entry(e: &Entry, path: &'static str) -> Result<Str
         .ok_or(HEK::HeaderFieldMissing(path).into())
 }
 
+/// Read a number from the header, which can be written either as integer or as float
+pub fn get_float_header_from_entry(e: &Entry, path: &'static str) -> Result<Option<f64>> {
+    use error::HabitErrorKind as HEK;
+    use toml::Value;
+    use toml_query::read::TomlValueReadExt;
+
+    match e.get_header().read(path)? {
+        Some(&Value::Float(f))   => Ok(Some(f)),
+        Some(&Value::Integer(i)) => Ok(Some(i as f64)),
+        Some(_)                  => Err(HEK::HeaderTypeError(path, "float").into()),
+        None                     => Ok(None),
+    }
+}
@@ -24,10 +24,14 @@ error_chain! {
 
     links {
         RefError(::libimagentryref::error::RefError, ::libimagentryref::error::RefErrorKind);
+        StoreError(::libimagstore::error::StoreError, ::libimagstore::error::StoreErrorKind);
+        LinkError(::libimagentrylink::error::LinkError, ::libimagentrylink::error::LinkErrorKind);
     }
 
     foreign_links {
         IoError(::std::io::Error);
+        TomlQueryError(::toml_query::error::Error);
+        WalkDirError(::walkdir::Error);
     }
 
 
@@ -59,6 +63,11 @@ error_chain! {
             description("IO Error")
             display("IO Error")
         }
+
+        AttachmentDoesNotExist(index: usize) {
+            description("Attachment does not exist")
+            display("Attachment {} does not exist", index)
+        }
     }
 }
 
@@ -0,0 +1,176 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Importing of Maildir folders
+//!
+//! Walks a Maildir (or a directory containing several Maildirs) and references all mails from
+//! the `cur` and `new` directories in the store. Mails which are already referenced are
+//! skipped, so the import can be re-run to pick up new mails.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use walkdir::WalkDir;
+
+use libimagstore::store::Store;
+use libimagstore::storeid::StoreId;
+use libimagentryref::refstore::RefStore;
+use libimagentryref::refstore::UniqueRefPathGenerator;
+
+use mail::Mail;
+use mail::UniqueMailRefGenerator;
+use error::MailError as ME;
+use error::Result;
+
+/// The result of a Maildir import
+#[derive(Debug)]
+pub struct ImportReport {
+    /// The entries which were created for newly imported mails
+    pub imported: Vec<StoreId>,
+
+    /// The number of mails which were already referenced in the store
+    pub skipped: usize,
+
+    /// The mail files which could not be imported, with the reason why
+    pub failed: Vec<(PathBuf, ME)>,
+}
+
+/// Import all mails from the Maildir folder(s) below `dir`
+///
+/// Only files in `cur` and `new` directories are considered mails. For each newly imported mail
+/// the threading headers are recorded (see `Mail::record_headers()`), linking the mails to
+/// their threads is left to `::thread::link_threads()`.
+pub fn import_maildir<P: AsRef<Path>>(store: &Store, dir: P) -> Result<ImportReport> {
+    let mut report = ImportReport {
+        imported: vec![],
+        skipped: 0,
+        failed: vec![],
+    };
+
+    for entry in WalkDir::new(dir).follow_links(true) {
+        let entry = entry?;
+        if !entry.file_type().is_file() || !is_in_maildir(entry.path()) {
+            continue;
+        }
+
+        let path = entry.path();
+        debug!("Importing mail: {}", path.display());
+
+        let hash = match UniqueMailRefGenerator::unique_hash(path) {
+            Ok(hash) => hash,
+            Err(e)   => {
+                report.failed.push((path.to_path_buf(), e));
+                continue;
+            },
+        };
+
+        if store.get_ref::<UniqueMailRefGenerator, _>(&hash)?.is_some() {
+            debug!("Already imported, skipping: {}", path.display());
+            report.skipped += 1;
+            continue;
+        }
+
+        match Mail::import_from_path(store, path).and_then(|mut mail| {
+            let _ = mail.record_headers()?;
+            Ok(mail.fle().get_location().clone())
+        }) {
+            Ok(id) => report.imported.push(id),
+            Err(e) => report.failed.push((path.to_path_buf(), e)),
+        }
+    }
+
+    Ok(report)
+}
+
+/// Check whether a file lives in the `cur` or `new` directory of a Maildir
+pub(crate) fn is_in_maildir(path: &Path) -> bool {
+    path.parent()
+        .and_then(Path::file_name)
+        .map(|name| name == "cur" || name == "new")
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs::File;
+    use std::fs::create_dir_all;
+    use std::io::Write;
+    use std::path::Path;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use tempdir::TempDir;
+
+    use libimagstore::store::Store;
+    use libimagstore::file_abstraction::InMemoryFileAbstraction;
+
+    use super::import_maildir;
+    use super::is_in_maildir;
+
+    fn get_store() -> Store {
+        let backend = Arc::new(InMemoryFileAbstraction::default());
+        Store::new_with_backend(PathBuf::from("/"), &None, backend).unwrap()
+    }
+
+    fn write_mail(path: &Path, message_id: Option<&str>, body: &str) {
+        let mut mail = String::from("From: a@example.com\r\nTo: b@example.com\r\nSubject: Hello\r\n");
+        if let Some(id) = message_id {
+            mail.push_str(&format!("Message-ID: {}\r\n", id));
+        }
+        mail.push_str("\r\n");
+        mail.push_str(body);
+
+        File::create(path).unwrap().write_all(mail.as_bytes()).unwrap();
+    }
+
+    #[test]
+    fn test_is_in_maildir() {
+        assert!(is_in_maildir(Path::new("/home/user/Mail/INBOX/cur/1234.host:2,S")));
+        assert!(is_in_maildir(Path::new("/home/user/Mail/INBOX/new/1234.host")));
+        assert!(!is_in_maildir(Path::new("/home/user/Mail/INBOX/tmp/1234.host")));
+        assert!(!is_in_maildir(Path::new("/home/user/Mail/INBOX/dovecot-uidlist")));
+    }
+
+    #[test]
+    fn test_import_same_subject_from_to() {
+        let store = get_store();
+        let dir   = TempDir::new("imag-mail-import").unwrap();
+        let cur   = dir.path().join("INBOX").join("cur");
+        create_dir_all(&cur).unwrap();
+
+        write_mail(&cur.join("1.host:2,S"), Some("<one@example.com>"), "first\r\n");
+        write_mail(&cur.join("2.host:2,S"), Some("<two@example.com>"), "second\r\n");
+        write_mail(&cur.join("3.host:2,S"), None, "third\r\n");
+        write_mail(&cur.join("4.host:2,S"), None, "fourth\r\n");
+
+        let report = import_maildir(&store, dir.path()).unwrap();
+        assert!(report.failed.is_empty());
+        assert_eq!(report.imported.len(), 4);
+        assert_eq!(report.skipped, 0);
+
+        // mails are identified by their Message-ID
+        assert!(store.get(PathBuf::from("mail/one@example.com")).unwrap().is_some());
+        assert!(store.get(PathBuf::from("mail/two@example.com")).unwrap().is_some());
+
+        let report = import_maildir(&store, dir.path()).unwrap();
+        assert!(report.imported.is_empty());
+        assert_eq!(report.skipped, 4);
+    }
+
+}
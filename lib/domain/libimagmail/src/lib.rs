@@ -37,14 +37,22 @@
 
 #[macro_use] extern crate log;
 extern crate email;
-extern crate filters;
 #[macro_use] extern crate error_chain;
+extern crate toml;
+extern crate toml_query;
+extern crate walkdir;
+#[cfg(test)] extern crate tempdir;
 
 extern crate libimagerror;
 extern crate libimagstore;
 extern crate libimagentryref;
+extern crate libimagentrylink;
 
 pub mod error;
+pub mod import;
 pub mod iter;
 pub mod mail;
+pub mod mime;
+pub mod repair;
+pub mod thread;
 
@@ -18,25 +18,39 @@
 //
 
 use std::path::Path;
+use std::path::PathBuf;
+use std::ffi::OsStr;
+use std::ffi::OsString;
 use std::fs::File;
 use std::io::Read;
+use std::io::Write;
 use std::fs::OpenOptions;
 use std::result::Result as RResult;
 
+use libimagstore::store::Entry;
 use libimagstore::store::Store;
 use libimagstore::storeid::StoreId;
 use libimagstore::store::FileLockEntry;
 use libimagentryref::reference::Ref;
 use libimagentryref::refstore::RefStore;
 use libimagentryref::refstore::UniqueRefPathGenerator;
+use libimagentryref::generators::sha1::Sha1;
+use libimagentrylink::internal::InternalLinker;
+
+use toml::Value;
+use toml_query::insert::TomlValueInsertExt;
 
 use email::MimeMessage;
 use email::results::ParsingResult as EmailParsingResult;
 
 use error::Result;
 use error::{ResultExt, MailError as ME, MailErrorKind as MEK};
+use mime::Attachment;
+use mime::decode_header_value;
+use mime::split_unquoted;
 
-struct UniqueMailRefGenerator;
+/// The `UniqueRefPathGenerator` which is used for referencing mails in the store
+pub struct UniqueMailRefGenerator;
 impl UniqueRefPathGenerator for UniqueMailRefGenerator {
     type Error = ME;
 
@@ -46,40 +60,19 @@ impl UniqueRefPathGenerator for UniqueMailRefGenerator {
     }
 
     /// A function which should generate a unique string for a Path
+    ///
+    /// This is the Message-ID of the mail, which does not change when the mail file is renamed
+    /// (e.g. because its flags changed). Mails without a Message-ID are identified by the hash of
+    /// the file content.
     fn unique_hash<A: AsRef<Path>>(path: A) -> RResult<String, Self::Error> {
-        use filters::filter::Filter;
-        use email::Header;
-
-        let mut s = String::new();
-        let _     = OpenOptions::new()
-            .read(true)
-            .write(false)
-            .create(false)
-            .open(path)?
-            .read_to_string(&mut s)?;
-
-        MimeMessage::parse(&s)
-            .chain_err(|| MEK::RefCreationError)
-            .and_then(|mail| {
-                let has_key = |hdr: &Header, exp: &str| hdr.name == exp;
-
-                let subject_filter = |hdr: &Header| has_key(hdr, "Subject");
-                let from_filter    = |hdr: &Header| has_key(hdr, "From");
-                let to_filter      = |hdr: &Header| has_key(hdr, "To");
-
-                let filter = subject_filter.or(from_filter).or(to_filter);
-
-                let mut v : Vec<String> = vec![];
-                for hdr in mail.headers.iter().filter(|item| filter.filter(item)) {
-                    let s = hdr
-                        .get_value()
-                        .chain_err(|| MEK::RefCreationError)?;
-
-                    v.push(s);
-                }
-                let s : String = v.join("");
-                Ok(s)
-            })
+        if let Some(id) = message_id_of_file(path.as_ref())? {
+            let id = id.trim_matches(|c| c == '<' || c == '>').replace('/', "_");
+            if !id.is_empty() {
+                return Ok(id);
+            }
+        }
+
+        Sha1::unique_hash(path).map_err(ME::from)
     }
 
     /// Postprocess the generated `StoreId` object
@@ -88,6 +81,27 @@ impl UniqueRefPathGenerator for UniqueMailRefGenerator {
     }
 }
 
+/// The `UniqueRefPathGenerator` which is used for referencing saved mail attachments
+///
+/// The hash is the SHA-1 of the file content, computed over the raw bytes, as attachments may be
+/// binary files.
+pub struct UniqueAttachmentRefGenerator;
+impl UniqueRefPathGenerator for UniqueAttachmentRefGenerator {
+    type Error = ME;
+
+    fn collection() -> &'static str {
+        "mail-attachment"
+    }
+
+    fn unique_hash<A: AsRef<Path>>(path: A) -> RResult<String, Self::Error> {
+        Sha1::unique_hash(path).map_err(ME::from)
+    }
+
+    fn postprocess_storeid(sid: StoreId) -> RResult<StoreId, Self::Error> {
+        Ok(sid)
+    }
+}
+
 struct Buffer(String);
 
 impl Buffer {
@@ -187,4 +201,272 @@ impl<'a> Mail<'a> {
         self.get_field("In-Reply-To")
     }
 
+    pub fn get_date(&self) -> Result<Option<String>> {
+        self.get_field("Date")
+    }
+
+    /// Get the Message-IDs from the "References" header of the mail, oldest first
+    pub fn get_references(&self) -> Result<Vec<String>> {
+        self.get_field("References")
+            .map(|refs| refs.map(|r| parse_message_ids(&r)).unwrap_or_else(Vec::new))
+    }
+
+    /// Get the Message-ID of the mail this mail is a reply to
+    ///
+    /// The "In-Reply-To" header is preferred, if it is not there, the last entry from the
+    /// "References" header is used.
+    pub fn get_parent_id(&self) -> Result<Option<String>> {
+        let in_reply_to = self.get_in_reply_to()?;
+        let references  = self.get_references()?;
+        Ok(parent_id(in_reply_to, &references))
+    }
+
+    /// Write the threading relevant headers of the mail into the header of the entry
+    ///
+    /// This writes `mail.message_id`, `mail.parent`, `mail.references`, `mail.subject`,
+    /// `mail.from` and `mail.date`, so the mails can be threaded without parsing the mail files
+    /// again.
+    pub fn record_headers(&mut self) -> Result<()> {
+        let message_id = self.get_message_id()?.and_then(|id| parse_message_ids(&id).pop());
+        let parent     = self.get_parent_id()?;
+        let references = self.get_references()?;
+        let subject    = self.get_subject()?;
+        let from       = self.get_from()?;
+        let date       = self.get_date()?;
+
+        let header = self.0.get_header_mut();
+        let fields = vec![
+            ("mail.message_id" , message_id),
+            ("mail.parent"     , parent),
+            ("mail.subject"    , subject),
+            ("mail.from"       , from),
+            ("mail.date"       , date),
+        ];
+
+        for (path, value) in fields {
+            if let Some(value) = value {
+                let _ = header.insert(path, Value::String(value))?;
+            }
+        }
+
+        let references = references.into_iter().map(Value::String).collect();
+        let _ = header.insert("mail.references", Value::Array(references))?;
+        Ok(())
+    }
+
+    /// Get a header field with all RFC 2047 encoded words decoded
+    pub fn get_decoded_field(&self, field: &str) -> Result<Option<String>> {
+        self.get_field(field).map(|value| value.map(|v| decode_header_value(&v)))
+    }
+
+    /// Get all addresses from the "From", "To" and "Cc" headers of the mail, lowercased
+    pub fn get_addresses(&self) -> Result<Vec<String>> {
+        let mut addresses = vec![];
+
+        for field in ["From", "To", "Cc"].iter() {
+            if let Some(value) = self.get_decoded_field(field)? {
+                addresses.append(&mut parse_addresses(&value));
+            }
+        }
+
+        addresses.sort();
+        addresses.dedup();
+        Ok(addresses)
+    }
+
+    /// Get the text of the mail
+    ///
+    /// For multipart mails, the "text/plain" part is used. If there is none, the "text/html"
+    /// part is converted to plain text.
+    pub fn get_text_body(&self) -> Result<Option<String>> {
+        self.1
+            .parsed()
+            .chain_err(|| MEK::MailParsingError)
+            .and_then(|parsed| ::mime::text_body(&parsed))
+    }
+
+    /// Get the attachments of the mail
+    pub fn get_attachments(&self) -> Result<Vec<Attachment>> {
+        self.1
+            .parsed()
+            .chain_err(|| MEK::MailParsingError)
+            .map(|parsed| ::mime::attachments(&parsed))
+    }
+
+    /// Save the attachment `index` of the mail to `dir` and reference it in the store
+    ///
+    /// If a file with the name of the attachment exists already in `dir`, the name is prefixed
+    /// with a number. The created entry is linked to the entry of the mail.
+    pub fn save_attachment<'b, P>(&mut self, store: &'b Store, index: usize, dir: P)
+        -> Result<FileLockEntry<'b>>
+        where P: AsRef<Path>
+    {
+        let (attachment, data) = {
+            let parsed = self.1.parsed().chain_err(|| MEK::MailParsingError)?;
+            ::mime::attachment_data(&parsed, index)
+                .ok_or_else(|| ME::from_kind(MEK::AttachmentDoesNotExist(index)))?
+        };
+
+        let name = attachment
+            .name
+            .as_ref()
+            .and_then(|name| Path::new(name).file_name().map(OsStr::to_os_string))
+            .unwrap_or_else(|| OsString::from(format!("attachment-{}", index)));
+
+        let path = unused_path(dir.as_ref(), &name);
+        debug!("Saving attachment {} to {}", index, path.display());
+
+        let _ = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)?
+            .write_all(&data)?;
+
+        let mut entry = store.retrieve_ref::<UniqueAttachmentRefGenerator, _>(&path)?;
+        let _ = entry.add_internal_link(&mut self.0)?;
+        Ok(entry)
+    }
+
+    /// Get the `FileLockEntry` which represents the mail
+    pub fn fle(&self) -> &FileLockEntry<'a> {
+        &self.0
+    }
+
+    /// Get the `FileLockEntry` which represents the mail, mutably
+    pub fn fle_mut(&mut self) -> &mut FileLockEntry<'a> {
+        &mut self.0
+    }
+
+}
+
+/// Get a path for a file `name` in `dir` which does not exist yet
+fn unused_path(dir: &Path, name: &OsStr) -> PathBuf {
+    let mut path = dir.join(name);
+    let mut n    = 1;
+
+    while path.exists() {
+        let mut numbered = OsString::from(format!("{}-", n));
+        numbered.push(name);
+        path = dir.join(numbered);
+        n   += 1;
+    }
+
+    path
+}
+
+/// Get all mails which are linked to `entry`, e.g. the mails linked to a contact
+pub fn linked_mails(entry: &Entry) -> Result<Vec<StoreId>> {
+    entry
+        .get_internal_links()
+        .map_err(From::from)
+        .map(|links| {
+            links.map(|link| link.get_store_id().clone())
+                .filter(|id| id.is_in_collection(&["mail"]))
+                .collect()
+        })
+}
+
+/// Read the Message-ID of the mail file at `path`, without referencing it in the store
+pub fn message_id_of_file<P: AsRef<Path>>(path: P) -> Result<Option<String>> {
+    let mut s = String::new();
+    let _     = File::open(path)?.read_to_string(&mut s)?;
+
+    Buffer::from(s)
+        .parsed()
+        .chain_err(|| MEK::MailParsingError)
+        .map(|parsed| {
+            parsed.headers
+                .iter()
+                .filter(|hdr| hdr.name == "Message-ID")
+                .nth(0)
+                .and_then(|hdr| hdr.get_value().ok())
+                .and_then(|id| parse_message_ids(&id).pop())
+        })
+}
+
+/// Extract all Message-IDs (`<...>`) from a header value, in order
+pub fn parse_message_ids(value: &str) -> Vec<String> {
+    let mut ids = vec![];
+    let mut rest = value;
+
+    while let Some(start) = rest.find('<') {
+        match rest[start..].find('>') {
+            Some(end) => {
+                ids.push(String::from(&rest[start..start + end + 1]));
+                rest = &rest[start + end + 1..];
+            },
+            None => break,
+        }
+    }
+
+    ids
+}
+
+/// Extract the addresses from an address list like `Name <a@example.com>, b@example.com`
+pub fn parse_addresses(value: &str) -> Vec<String> {
+    split_unquoted(value, ',')
+        .into_iter()
+        .filter_map(|mailbox| {
+            let address = match (mailbox.rfind('<'), mailbox.rfind('>')) {
+                (Some(start), Some(end)) if start < end => String::from(&mailbox[start + 1..end]),
+                _                                       => String::from(mailbox.trim()),
+            };
+
+            let address = address.trim().to_lowercase();
+            if address.contains('@') {
+                Some(address)
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Find the Message-ID of the parent of a mail
+///
+/// The (first) Message-ID from the "In-Reply-To" header is preferred, the last entry of the
+/// "References" header is used as fallback.
+fn parent_id(in_reply_to: Option<String>, references: &[String]) -> Option<String> {
+    in_reply_to
+        .and_then(|irt| parse_message_ids(&irt).into_iter().next())
+        .or_else(|| references.last().cloned())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_message_ids() {
+        let ids = parse_message_ids("<a@example.com>\n\t<b@example.com> <c@example.com>");
+        assert_eq!(ids, vec!["<a@example.com>", "<b@example.com>", "<c@example.com>"]);
+    }
+
+    #[test]
+    fn test_parse_message_ids_garbage() {
+        assert!(parse_message_ids("").is_empty());
+        assert!(parse_message_ids("no ids here").is_empty());
+        assert!(parse_message_ids("<unterminated@example.com").is_empty());
+    }
+
+    #[test]
+    fn test_parse_addresses() {
+        let addrs = parse_addresses("\"Doe, John\" <John@Example.com>, jane@example.com, undisclosed-recipients:;");
+        assert_eq!(addrs, vec!["john@example.com", "jane@example.com"]);
+    }
+
+    #[test]
+    fn test_parent_id_prefers_in_reply_to() {
+        let refs = vec![String::from("<a@example.com>"), String::from("<b@example.com>")];
+        let irt  = Some(String::from("<c@example.com> (Someone's message)"));
+        assert_eq!(parent_id(irt, &refs), Some(String::from("<c@example.com>")));
+    }
+
+    #[test]
+    fn test_parent_id_falls_back_to_references() {
+        let refs = vec![String::from("<a@example.com>"), String::from("<b@example.com>")];
+        assert_eq!(parent_id(None, &refs), Some(String::from("<b@example.com>")));
+        assert_eq!(parent_id(None, &[]), None);
+    }
+
 }
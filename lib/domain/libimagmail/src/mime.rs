@@ -0,0 +1,441 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! MIME handling for mails
+//!
+//! Helpers for decoding header values (RFC 2047), finding the text body of a (multipart) mail
+//! and listing its attachments.
+
+use email::MimeMessage;
+use email::rfc2047::decode_rfc2047;
+
+use error::Result;
+use error::{ResultExt, MailErrorKind as MEK};
+
+/// An attachment of a mail
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attachment {
+    /// The number of the attachment in the mail, starting at zero
+    pub index: usize,
+
+    /// The file name of the attachment, if the mail names it
+    pub name: Option<String>,
+
+    /// The MIME type of the attachment, for example "application/pdf"
+    pub content_type: String,
+
+    /// The decoded size of the attachment in bytes
+    pub size: usize,
+}
+
+/// Decode all RFC 2047 encoded words (`=?charset?encoding?text?=`) in a header value
+///
+/// Whitespace between two adjacent encoded words is removed, as the RFC requires. Encoded words
+/// which cannot be decoded are left as they are.
+pub fn decode_header_value(value: &str) -> String {
+    let mut result           = String::new();
+    let mut rest             = value;
+    let mut last_was_encoded = false;
+
+    while let Some(start) = rest.find("=?") {
+        let (before, candidate) = rest.split_at(start);
+
+        let decoded = encoded_word_len(candidate)
+            .and_then(|len| decode_rfc2047(&candidate[..len]).map(|d| (len, d)));
+
+        match decoded {
+            Some((len, decoded)) => {
+                if !(last_was_encoded && before.trim().is_empty()) {
+                    result.push_str(before);
+                }
+                result.push_str(&decoded);
+                last_was_encoded = true;
+                rest             = &candidate[len..];
+            },
+            None => {
+                result.push_str(before);
+                result.push_str("=?");
+                last_was_encoded = false;
+                rest             = &candidate[2..];
+            },
+        }
+    }
+
+    result.push_str(rest);
+    result
+}
+
+/// Get the length of the encoded word at the start of `s`, if there is one
+fn encoded_word_len(s: &str) -> Option<usize> {
+    let charset_end  = 2 + s[2..].find('?')?;
+    let encoding_end = charset_end + 1 + s[charset_end + 1..].find('?')?;
+    let text_end     = encoding_end + 1 + s[encoding_end + 1..].find("?=")?;
+    Some(text_end + 2)
+}
+
+/// Split a structured header value like `text/plain; charset="utf-8"` into its (lowercased)
+/// value and its parameters
+///
+/// Parameter names are lowercased, RFC 2231 encoded parameters (`filename*=UTF-8''...`) are
+/// decoded.
+pub fn parse_header_params(value: &str) -> (String, Vec<(String, String)>) {
+    let mut parts = split_unquoted(value, ';').into_iter();
+    let main      = parts.next().unwrap_or_default().trim().to_lowercase();
+
+    let params = parts
+        .filter_map(|part| {
+            let eq    = part.find('=')?;
+            let key   = part[..eq].trim().to_lowercase();
+            let value = part[eq + 1..].trim().trim_matches('"');
+
+            if key.ends_with('*') {
+                let value = value.splitn(3, '\'').nth(2).unwrap_or(value);
+                Some((String::from(key.trim_right_matches('*')), percent_decode(value)))
+            } else {
+                Some((key, String::from(value)))
+            }
+        })
+        .collect();
+
+    (main, params)
+}
+
+pub(crate) fn split_unquoted(s: &str, sep: char) -> Vec<String> {
+    let mut parts  = vec![];
+    let mut buf    = String::new();
+    let mut quoted = false;
+
+    for c in s.chars() {
+        if c == '"' {
+            quoted = !quoted;
+        }
+
+        if c == sep && !quoted {
+            parts.push(buf);
+            buf = String::new();
+        } else {
+            buf.push(c);
+        }
+    }
+
+    parts.push(buf);
+    parts
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes   = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i   = 0;
+
+    while i < bytes.len() {
+        let hex = if bytes[i] == b'%' && i + 2 < bytes.len() {
+            ::std::str::from_utf8(&bytes[i + 1..i + 3]).ok().and_then(|h| u8::from_str_radix(h, 16).ok())
+        } else {
+            None
+        };
+
+        match hex {
+            Some(b) => { out.push(b); i += 3; },
+            None    => { out.push(bytes[i]); i += 1; },
+        }
+    }
+
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Get the decoded value of the first header `name` (case insensitive) of a MIME part
+pub fn header_value(part: &MimeMessage, name: &str) -> Option<String> {
+    part.headers
+        .iter()
+        .find(|hdr| hdr.name.eq_ignore_ascii_case(name))
+        .and_then(|hdr| hdr.get_value::<String>().ok())
+        .map(|value| decode_header_value(&value))
+}
+
+/// Get the content type and its parameters of a MIME part, defaulting to "text/plain"
+fn content_type(part: &MimeMessage) -> (String, Vec<(String, String)>) {
+    header_value(part, "Content-Type")
+        .map(|ct| parse_header_params(&ct))
+        .unwrap_or_else(|| (String::from("text/plain"), vec![]))
+}
+
+fn param<'a>(params: &'a [(String, String)], key: &str) -> Option<&'a str> {
+    params.iter().find(|&&(ref k, _)| k == key).map(|&(_, ref v)| v.as_str())
+}
+
+/// All parts of the mail which have no sub-parts, depth first
+fn leaves(part: &MimeMessage) -> Vec<&MimeMessage> {
+    if part.children.is_empty() {
+        vec![part]
+    } else {
+        part.children.iter().flat_map(leaves).collect()
+    }
+}
+
+/// The file name of a MIME part, from the Content-Disposition or the Content-Type header
+fn part_name(part: &MimeMessage) -> Option<String> {
+    let from_disposition = header_value(part, "Content-Disposition")
+        .and_then(|cd| param(&parse_header_params(&cd).1, "filename").map(String::from));
+
+    from_disposition.or_else(|| param(&content_type(part).1, "name").map(String::from))
+}
+
+fn is_attachment(part: &MimeMessage) -> bool {
+    let is_disposed = header_value(part, "Content-Disposition")
+        .map(|cd| parse_header_params(&cd).0 == "attachment")
+        .unwrap_or(false);
+
+    is_disposed || part_name(part).is_some() || !content_type(part).0.starts_with("text/")
+}
+
+/// Get the text body of a mail
+///
+/// The first "text/plain" part is preferred, if there is none the first "text/html" part is used
+/// with all markup stripped.
+pub fn text_body(mail: &MimeMessage) -> Result<Option<String>> {
+    let texts = leaves(mail)
+        .into_iter()
+        .filter(|part| !is_attachment(part))
+        .map(|part| (content_type(part).0, part))
+        .collect::<Vec<_>>();
+
+    if let Some(&(_, part)) = texts.iter().find(|&&(ref ct, _)| ct == "text/plain") {
+        return part.decoded_body_string().chain_err(|| MEK::MailParsingError).map(Some);
+    }
+
+    if let Some(&(_, part)) = texts.iter().find(|&&(ref ct, _)| ct == "text/html") {
+        return part
+            .decoded_body_string()
+            .chain_err(|| MEK::MailParsingError)
+            .map(|html| Some(strip_html(&html)));
+    }
+
+    Ok(None)
+}
+
+/// Get the attachments of a mail
+pub fn attachments(mail: &MimeMessage) -> Vec<Attachment> {
+    attachment_parts(mail)
+        .into_iter()
+        .enumerate()
+        .map(|(index, part)| Attachment {
+            index: index,
+            name: part_name(part),
+            content_type: content_type(part).0,
+            size: part.decoded_body_bytes().map(|b| b.len()).unwrap_or_else(|| part.body.len()),
+        })
+        .collect()
+}
+
+/// Get an attachment of a mail and its decoded content
+pub fn attachment_data(mail: &MimeMessage, index: usize) -> Option<(Attachment, Vec<u8>)> {
+    let part = attachment_parts(mail).into_iter().nth(index)?;
+    let data = part.decoded_body_bytes().unwrap_or_else(|| part.body.clone().into_bytes());
+
+    let attachment = Attachment {
+        index: index,
+        name: part_name(part),
+        content_type: content_type(part).0,
+        size: data.len(),
+    };
+
+    Some((attachment, data))
+}
+
+fn attachment_parts(mail: &MimeMessage) -> Vec<&MimeMessage> {
+    leaves(mail).into_iter().filter(|part| is_attachment(part)).collect()
+}
+
+/// Convert HTML to plain text by removing all markup
+///
+/// Scripts and styles are dropped, block elements are turned into line breaks and the most
+/// common character references are decoded.
+pub fn strip_html(html: &str) -> String {
+    let mut text = String::new();
+    let mut rest = html;
+
+    while let Some(start) = rest.find('<') {
+        push_text(&mut text, &rest[..start]);
+
+        let end = match rest[start..].find('>') {
+            Some(end) => start + end,
+            None      => {
+                rest = &rest[start..];
+                break;
+            },
+        };
+
+        let tag  = rest[start + 1..end].trim().to_ascii_lowercase();
+        let name = tag
+            .trim_left_matches('/')
+            .chars()
+            .take_while(|c| c.is_alphanumeric())
+            .collect::<String>();
+
+        rest = &rest[end + 1..];
+
+        if !tag.starts_with('/') && (name == "script" || name == "style") {
+            let close = format!("</{}", name);
+            rest = match rest.to_ascii_lowercase().find(&close) {
+                Some(pos) => {
+                    let after = &rest[pos..];
+                    after.find('>').map(|gt| &after[gt + 1..]).unwrap_or("")
+                },
+                None => "",
+            };
+            continue;
+        }
+
+        match name.as_str() {
+            "br" | "p" | "div" | "li" | "tr" | "table" | "ul" | "ol" | "blockquote" |
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => text.push('\n'),
+            _ => {},
+        }
+    }
+
+    push_text(&mut text, rest);
+
+    let mut result = String::new();
+    let mut blank  = false;
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() {
+            if !blank && !result.is_empty() {
+                result.push('\n');
+            }
+            blank = true;
+        } else {
+            result.push_str(line);
+            result.push('\n');
+            blank = false;
+        }
+    }
+
+    String::from(result.trim_right())
+}
+
+/// Append HTML text to `out`, decoding character references and collapsing whitespace
+fn push_text(out: &mut String, text: &str) {
+    let mut last_space = out.is_empty() || out.ends_with(char::is_whitespace);
+
+    for c in decode_entities(text).chars() {
+        if c.is_whitespace() && c != '\u{a0}' {
+            if !last_space {
+                out.push(' ');
+                last_space = true;
+            }
+        } else {
+            out.push(if c == '\u{a0}' { ' ' } else { c });
+            last_space = false;
+        }
+    }
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut result = String::new();
+    let mut rest   = text;
+
+    while let Some(start) = rest.find('&') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let decoded = rest
+            .find(';')
+            .and_then(|end| if end <= 10 { Some(end) } else { None })
+            .and_then(|end| decode_entity(&rest[1..end]).map(|c| (end, c)));
+
+        match decoded {
+            Some((end, c)) => {
+                result.push(c);
+                rest = &rest[end + 1..];
+            },
+            None => {
+                result.push('&');
+                rest = &rest[1..];
+            },
+        }
+    }
+
+    result.push_str(rest);
+    result
+}
+
+fn decode_entity(entity: &str) -> Option<char> {
+    match entity {
+        "amp"  => Some('&'),
+        "lt"   => Some('<'),
+        "gt"   => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some('\u{a0}'),
+        _ if entity.starts_with("#x") || entity.starts_with("#X") => {
+            u32::from_str_radix(&entity[2..], 16).ok().and_then(::std::char::from_u32)
+        },
+        _ if entity.starts_with('#') => {
+            entity[1..].parse::<u32>().ok().and_then(::std::char::from_u32)
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_decode_header_value_plain() {
+        assert_eq!(decode_header_value("Hello World"), "Hello World");
+        assert_eq!(decode_header_value("1 =? 2"), "1 =? 2");
+    }
+
+    #[test]
+    fn test_decode_header_value_encoded() {
+        assert_eq!(decode_header_value("=?UTF-8?Q?caf=C3=A9?= au lait"), "café au lait");
+        assert_eq!(decode_header_value("=?UTF-8?B?SGVsbG8=?= =?UTF-8?B?IFdvcmxk?="), "Hello World");
+    }
+
+    #[test]
+    fn test_parse_header_params() {
+        let (main, params) = parse_header_params("Text/Plain; charset=\"utf-8\"; format=flowed");
+        assert_eq!(main, "text/plain");
+        assert_eq!(params, vec![
+            (String::from("charset"), String::from("utf-8")),
+            (String::from("format"), String::from("flowed")),
+        ]);
+    }
+
+    #[test]
+    fn test_parse_header_params_quoted_and_encoded() {
+        let (main, params) = parse_header_params("attachment; filename=\"a; b.pdf\"");
+        assert_eq!(main, "attachment");
+        assert_eq!(params, vec![(String::from("filename"), String::from("a; b.pdf"))]);
+
+        let (_, params) = parse_header_params("attachment; filename*=UTF-8''caf%C3%A9.txt");
+        assert_eq!(params, vec![(String::from("filename"), String::from("café.txt"))]);
+    }
+
+    #[test]
+    fn test_strip_html() {
+        let html = "<html><head><style>p { color: red; }</style></head>\
+                    <body><p>Hello&nbsp;<b>World</b> &amp; all</p><p>Second<br>line</p>\
+                    <script>alert('x');</script></body></html>";
+
+        assert_eq!(strip_html(html), "Hello World & all\n\nSecond\nline");
+    }
+
+}
@@ -0,0 +1,414 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Repairing of dangling mail references
+//!
+//! Maildir renames mail files when their flags change (the `:2,S` suffix) and moves them from
+//! `new` to `cur`, which leaves the references in the store pointing to files which do not exist
+//! anymore. This module finds the moved files in the Maildir folders and updates the references.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use walkdir::WalkDir;
+use toml_query::read::TomlValueReadTypeExt;
+
+use libimagstore::store::Store;
+use libimagstore::storeid::StoreId;
+use libimagentrylink::internal::InternalLinker;
+use libimagentryref::reference::Ref;
+use libimagentryref::refstore::UniqueRefPathGenerator;
+
+use mail::UniqueMailRefGenerator;
+use mail::message_id_of_file;
+use import::is_in_maildir;
+use error::MailError as ME;
+use error::Result;
+
+/// The result of a repair run
+#[derive(Debug)]
+pub struct RepairReport {
+    /// The references which were updated, with the new path of the mail file
+    pub repaired: Vec<(StoreId, PathBuf)>,
+
+    /// The references which are still dangling, because no matching file was found
+    pub unresolved: Vec<StoreId>,
+}
+
+/// The result of re-keying the mail references
+#[derive(Debug)]
+pub struct RekeyReport {
+    /// The references which were moved, with their new id
+    pub moved: Vec<(StoreId, StoreId)>,
+
+    /// The references which were not moved because an entry with the new id exists already
+    pub conflicts: Vec<(StoreId, StoreId)>,
+
+    /// The references which could not be re-keyed, with the reason why
+    pub failed: Vec<(StoreId, ME)>,
+}
+
+/// A mail reference which points to a file which does not exist anymore
+struct Dangling {
+    id: StoreId,
+    hash: String,
+    message_id: Option<String>,
+    found: Option<PathBuf>,
+}
+
+/// Get all mail references from the store which point to a file which does not exist
+pub fn dangling_mails(store: &Store) -> Result<Vec<StoreId>> {
+    find_dangling(store).map(|ds| ds.into_iter().map(|d| d.id).collect())
+}
+
+/// Relocate dangling mail references by searching the Maildir folders below `roots`
+///
+/// A mail file matches a dangling reference if it has the same unique hash or, if that fails,
+/// the same Message-ID (as recorded by `Mail::record_headers()`).
+pub fn repair_refs<P: AsRef<Path>>(store: &Store, roots: &[P]) -> Result<RepairReport> {
+    let mut dangling = find_dangling(store)?;
+
+    if !dangling.is_empty() {
+        'roots: for root in roots {
+            for entry in WalkDir::new(root).follow_links(true) {
+                let entry = entry?;
+                if !entry.file_type().is_file() || !is_in_maildir(entry.path()) {
+                    continue;
+                }
+
+                if dangling.iter().all(|d| d.found.is_some()) {
+                    break 'roots;
+                }
+
+                match_file(entry.path(), &mut dangling);
+            }
+        }
+    }
+
+    let mut report = RepairReport {
+        repaired: vec![],
+        unresolved: vec![],
+    };
+
+    for d in dangling {
+        match d.found {
+            Some(path) => {
+                if let Some(mut entry) = store.get(d.id.clone())? {
+                    debug!("Relocating {} to {}", d.id, path.display());
+                    let _ = entry.make_ref(d.hash, &path)?;
+                    report.repaired.push((d.id, path));
+                }
+            },
+            None => report.unresolved.push(d.id),
+        }
+    }
+
+    Ok(report)
+}
+
+/// Move mail references to the id `UniqueMailRefGenerator` computes for their mail file
+///
+/// Mails used to be identified by the hash of the mail file, they are now identified by their
+/// Message-ID. Each mail reference which points to an existing file and which does not have the
+/// id computed for the file is moved to that id, its hash is updated and its links are re-added.
+/// References which point to files which do not exist anymore are left alone, they have to be
+/// repaired with `repair_refs()` first.
+pub fn rekey_mails(store: &Store) -> Result<RekeyReport> {
+    let mut report = RekeyReport {
+        moved: vec![],
+        conflicts: vec![],
+        failed: vec![],
+    };
+
+    let mut ids = vec![];
+    for id in store.entries()? {
+        let id = id?;
+        if id.is_in_collection(&[UniqueMailRefGenerator::collection()]) {
+            ids.push(id);
+        }
+    }
+
+    for id in ids {
+        match rekey_mail(store, &id) {
+            Ok(Rekeyed::Unchanged)        => {},
+            Ok(Rekeyed::Moved(new_id))    => report.moved.push((id, new_id)),
+            Ok(Rekeyed::Conflict(new_id)) => report.conflicts.push((id, new_id)),
+            Err(e)                        => report.failed.push((id, e)),
+        }
+    }
+
+    Ok(report)
+}
+
+enum Rekeyed {
+    Unchanged,
+    Moved(StoreId),
+    Conflict(StoreId),
+}
+
+fn rekey_mail(store: &Store, id: &StoreId) -> Result<Rekeyed> {
+    let (hash, path, new_id, linked) = {
+        let mut entry = match store.get(id.clone())? {
+            Some(entry) => entry,
+            None        => return Ok(Rekeyed::Unchanged),
+        };
+
+        if entry.is_dangling()? {
+            return Ok(Rekeyed::Unchanged);
+        }
+
+        let path   = entry.get_path()?;
+        let hash   = UniqueMailRefGenerator::unique_hash(&path)?;
+        let new_id = StoreId::new_baseless(PathBuf::from(format!("{}/{}", UniqueMailRefGenerator::collection(), hash)))?;
+
+        if new_id.local() == id.local() {
+            return Ok(Rekeyed::Unchanged);
+        }
+
+        if store.get(new_id.clone())?.is_some() {
+            return Ok(Rekeyed::Conflict(new_id));
+        }
+
+        let linked : Vec<StoreId> = entry
+            .get_internal_links()?
+            .map(|link| link.get_store_id().clone())
+            .collect();
+
+        let _ = entry.unlink(store)?;
+        (hash, path, new_id, linked)
+    };
+
+    debug!("Moving {} to {}", id, new_id);
+    let _ = store.move_by_id(id.clone(), new_id.clone())?;
+
+    if let Some(mut entry) = store.get(new_id.clone())? {
+        let _ = entry.make_ref(hash, &path)?;
+
+        for link in linked {
+            match store.get(link.clone())? {
+                Some(mut other) => { let _ = entry.add_internal_link(&mut other)?; },
+                None            => warn!("Linked entry {} does not exist, not linking", link),
+            }
+        }
+    }
+
+    Ok(Rekeyed::Moved(new_id))
+}
+
+fn find_dangling(store: &Store) -> Result<Vec<Dangling>> {
+    let mut dangling = vec![];
+
+    for id in store.entries()? {
+        let id = id?;
+        if !id.is_in_collection(&["mail"]) {
+            continue;
+        }
+
+        if let Some(entry) = store.get(id.clone())? {
+            if !entry.is_dangling()? {
+                continue;
+            }
+
+            debug!("Dangling mail reference: {}", id);
+            dangling.push(Dangling {
+                id: id,
+                hash: String::from(entry.get_hash()?),
+                message_id: entry.get_header().read_string("mail.message_id")?,
+                found: None,
+            });
+        }
+    }
+
+    Ok(dangling)
+}
+
+/// Check whether the file at `path` is the mail one of the dangling references points to
+fn match_file(path: &Path, dangling: &mut [Dangling]) {
+    match UniqueMailRefGenerator::unique_hash(path) {
+        Ok(hash) => {
+            if let Some(d) = dangling.iter_mut().find(|d| d.found.is_none() && d.hash == hash) {
+                d.found = Some(path.to_path_buf());
+                return;
+            }
+        },
+        Err(e) => debug!("Could not compute hash for {}: {:?}", path.display(), e),
+    }
+
+    let wants_message_id = dangling.iter().any(|d| d.found.is_none() && d.message_id.is_some());
+    if !wants_message_id {
+        return;
+    }
+
+    match message_id_of_file(path) {
+        Ok(Some(mid)) => {
+            let found = dangling
+                .iter_mut()
+                .find(|d| d.found.is_none() && d.message_id.as_ref() == Some(&mid));
+
+            if let Some(d) = found {
+                d.found = Some(path.to_path_buf());
+            }
+        },
+        Ok(None) => {},
+        Err(e)   => debug!("Could not read Message-ID from {}: {:?}", path.display(), e),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs::File;
+    use std::fs::create_dir_all;
+    use std::fs::remove_file;
+    use std::fs::rename;
+    use std::io::Write;
+    use std::path::Path;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use tempdir::TempDir;
+
+    use libimagstore::store::Store;
+    use libimagstore::file_abstraction::InMemoryFileAbstraction;
+    use libimagentryref::reference::Ref;
+
+    use libimagentrylink::internal::InternalLinker;
+
+    use super::dangling_mails;
+    use super::rekey_mails;
+    use super::repair_refs;
+    use import::import_maildir;
+
+    fn get_store() -> Store {
+        let backend = Arc::new(InMemoryFileAbstraction::default());
+        Store::new_with_backend(PathBuf::from("/"), &None, backend).unwrap()
+    }
+
+    fn write_mail(path: &Path) {
+        let mail = "From: a@example.com\r\nTo: b@example.com\r\nSubject: Hello\r\nMessage-ID: <one@example.com>\r\n\r\nbody\r\n";
+        create_dir_all(path.parent().unwrap()).unwrap();
+        File::create(path).unwrap().write_all(mail.as_bytes()).unwrap();
+    }
+
+    /// Create a Maildir with one mail in `new` and import it
+    fn setup(store: &Store) -> (TempDir, PathBuf) {
+        let dir  = TempDir::new("imag-mail-repair").unwrap();
+        let path = dir.path().join("INBOX").join("new").join("1.host");
+        write_mail(&path);
+
+        let report = import_maildir(store, dir.path()).unwrap();
+        assert_eq!(report.imported.len(), 1);
+        assert!(dangling_mails(store).unwrap().is_empty());
+
+        (dir, path)
+    }
+
+    fn ref_path(store: &Store) -> PathBuf {
+        store.get(PathBuf::from("mail/one@example.com")).unwrap().unwrap().get_path().unwrap()
+    }
+
+    #[test]
+    fn test_repair_moved_to_cur() {
+        let store       = get_store();
+        let (dir, path) = setup(&store);
+        let moved       = dir.path().join("INBOX").join("cur").join("1.host:2,S");
+        create_dir_all(moved.parent().unwrap()).unwrap();
+        rename(&path, &moved).unwrap();
+
+        assert_eq!(dangling_mails(&store).unwrap().len(), 1);
+
+        let report = repair_refs(&store, &[dir.path()]).unwrap();
+        assert_eq!(report.repaired.len(), 1);
+        assert_eq!(report.repaired[0].1, moved);
+        assert!(report.unresolved.is_empty());
+
+        assert_eq!(ref_path(&store), moved);
+        assert!(dangling_mails(&store).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_repair_gone() {
+        let store       = get_store();
+        let (dir, path) = setup(&store);
+        remove_file(&path).unwrap();
+
+        let report = repair_refs(&store, &[dir.path()]).unwrap();
+        assert!(report.repaired.is_empty());
+        assert_eq!(report.unresolved.len(), 1);
+        assert_eq!(ref_path(&store), path);
+    }
+
+    #[test]
+    fn test_repair_two_candidates() {
+        let store       = get_store();
+        let (dir, path) = setup(&store);
+        let first       = dir.path().join("INBOX").join("cur").join("1.host:2,S");
+        let second      = dir.path().join("Archive").join("cur").join("1.host:2,S");
+        write_mail(&first);
+        write_mail(&second);
+        remove_file(&path).unwrap();
+
+        let report = repair_refs(&store, &[dir.path()]).unwrap();
+        assert_eq!(report.repaired.len(), 1);
+        assert!(report.unresolved.is_empty());
+
+        let relocated = ref_path(&store);
+        assert!(relocated == first || relocated == second);
+        assert_eq!(report.repaired[0].1, relocated);
+    }
+
+    #[test]
+    fn test_rekey_old_reference() {
+        let store = get_store();
+        let dir   = TempDir::new("imag-mail-rekey").unwrap();
+        let path  = dir.path().join("INBOX").join("cur").join("1.host:2,S");
+        write_mail(&path);
+
+        {
+            let mut mail  = store.create(PathBuf::from("mail/0123456789abcdef")).unwrap();
+            let mut other = store.create(PathBuf::from("note/a")).unwrap();
+            mail.make_ref(String::from("0123456789abcdef"), &path).unwrap();
+            mail.add_internal_link(&mut other).unwrap();
+        }
+
+        let report = rekey_mails(&store).unwrap();
+        assert_eq!(report.moved.len(), 1);
+        assert!(report.conflicts.is_empty());
+        assert!(report.failed.is_empty());
+
+        assert!(store.get(PathBuf::from("mail/0123456789abcdef")).unwrap().is_none());
+        let mail = store.get(PathBuf::from("mail/one@example.com")).unwrap().unwrap();
+        assert_eq!(mail.get_hash().unwrap(), "one@example.com");
+        assert_eq!(mail.get_path().unwrap(), path);
+
+        let links = store
+            .get(PathBuf::from("note/a"))
+            .unwrap()
+            .unwrap()
+            .get_internal_links()
+            .unwrap()
+            .map(|link| link.get_store_id().local().display().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(links, vec!["mail/one@example.com"]);
+
+        // running it again does not change anything
+        let report = rekey_mails(&store).unwrap();
+        assert!(report.moved.is_empty());
+    }
+
+}
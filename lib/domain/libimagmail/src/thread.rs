@@ -0,0 +1,280 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Threading of mails
+//!
+//! Mails are threaded by their `Message-ID`, `In-Reply-To` and `References` headers, as recorded
+//! in the entry header by `Mail::record_headers()`. A reply is linked to its parent mail with an
+//! internal link, so threads are also visible for all other imag tools.
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use toml_query::read::TomlValueReadTypeExt;
+
+use libimagstore::store::Store;
+use libimagstore::storeid::StoreId;
+use libimagentrylink::internal::InternalLinker;
+
+use error::Result;
+
+/// A mail in a thread, with all replies to it
+#[derive(Debug)]
+pub struct ThreadNode {
+    pub id: StoreId,
+    pub message_id: Option<String>,
+    pub subject: Option<String>,
+    pub from: Option<String>,
+    pub date: Option<String>,
+    pub children: Vec<ThreadNode>,
+}
+
+/// The threading relevant information about one mail in the store
+#[derive(Debug)]
+struct MailInfo {
+    id: StoreId,
+    message_id: Option<String>,
+    parent: Option<String>,
+    subject: Option<String>,
+    from: Option<String>,
+    date: Option<String>,
+}
+
+/// Link all mails in the store to the mail they are a reply to
+///
+/// Mails which are already linked to their parent are left alone. Returns the number of links
+/// which were added.
+pub fn link_threads(store: &Store) -> Result<usize> {
+    let infos  = mail_infos(store)?;
+    let index  = message_index(&infos);
+    let mut linked = 0;
+
+    for info in infos.iter() {
+        let parent = match parent_of(&infos, &index, info) {
+            Some(p) => &infos[p],
+            None    => continue,
+        };
+
+        if parent.id == info.id {
+            continue;
+        }
+
+        let mut entry = match store.get(info.id.clone())? {
+            Some(entry) => entry,
+            None        => continue,
+        };
+
+        let is_linked = entry
+            .get_internal_links()?
+            .any(|link| *link.get_store_id() == parent.id);
+
+        if is_linked {
+            continue;
+        }
+
+        if let Some(mut parent_entry) = store.get(parent.id.clone())? {
+            debug!("Linking {} to its parent {}", info.id, parent.id);
+            let _ = entry.add_internal_link(&mut parent_entry)?;
+            linked += 1;
+        }
+    }
+
+    Ok(linked)
+}
+
+/// Get the whole thread the mail `id` is part of
+///
+/// Returns `None` if `id` is not a mail in the store.
+pub fn thread_of(store: &Store, id: &StoreId) -> Result<Option<ThreadNode>> {
+    mail_infos(store).map(|infos| build_thread(&infos, id))
+}
+
+fn mail_infos(store: &Store) -> Result<Vec<MailInfo>> {
+    let mut infos = vec![];
+
+    for id in store.entries()? {
+        let id = id?;
+        if !id.is_in_collection(&["mail"]) {
+            continue;
+        }
+
+        if let Some(entry) = store.get(id.clone())? {
+            let header = entry.get_header();
+            infos.push(MailInfo {
+                id: id,
+                message_id: header.read_string("mail.message_id")?,
+                parent: header.read_string("mail.parent")?,
+                subject: header.read_string("mail.subject")?,
+                from: header.read_string("mail.from")?,
+                date: header.read_string("mail.date")?,
+            });
+        }
+    }
+
+    infos.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(infos)
+}
+
+fn message_index(infos: &[MailInfo]) -> HashMap<&str, usize> {
+    infos.iter()
+        .enumerate()
+        .filter_map(|(i, info)| info.message_id.as_ref().map(|mid| (mid.as_str(), i)))
+        .collect()
+}
+
+fn parent_of(infos: &[MailInfo], index: &HashMap<&str, usize>, info: &MailInfo) -> Option<usize> {
+    info.parent
+        .as_ref()
+        .and_then(|parent| index.get(parent.as_str()))
+        .cloned()
+        .and_then(|p| if infos[p].id != info.id { Some(p) } else { None })
+}
+
+fn build_thread(infos: &[MailInfo], id: &StoreId) -> Option<ThreadNode> {
+    let start = match infos.iter().position(|info| info.id == *id) {
+        Some(start) => start,
+        None        => return None,
+    };
+
+    let index = message_index(infos);
+
+    // Walk up to the root of the thread, guarding against reference loops
+    let mut root    = start;
+    let mut visited = HashSet::new();
+    let _           = visited.insert(root);
+    while let Some(parent) = parent_of(infos, &index, &infos[root]) {
+        if !visited.insert(parent) {
+            break;
+        }
+        root = parent;
+    }
+
+    let mut children : HashMap<usize, Vec<usize>> = HashMap::new();
+    for (i, info) in infos.iter().enumerate() {
+        if let Some(parent) = parent_of(infos, &index, info) {
+            children.entry(parent).or_insert_with(Vec::new).push(i);
+        }
+    }
+
+    let mut seen = HashSet::new();
+    Some(build_node(infos, &children, root, &mut seen))
+}
+
+fn build_node(infos: &[MailInfo],
+              children: &HashMap<usize, Vec<usize>>,
+              i: usize,
+              seen: &mut HashSet<usize>)
+    -> ThreadNode
+{
+    let _    = seen.insert(i);
+    let info = &infos[i];
+
+    let mut node = ThreadNode {
+        id: info.id.clone(),
+        message_id: info.message_id.clone(),
+        subject: info.subject.clone(),
+        from: info.from.clone(),
+        date: info.date.clone(),
+        children: vec![],
+    };
+
+    if let Some(cs) = children.get(&i) {
+        for &c in cs {
+            if !seen.contains(&c) {
+                let child = build_node(infos, children, c, seen);
+                node.children.push(child);
+            }
+        }
+    }
+
+    node
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use libimagstore::storeid::StoreId;
+
+    use super::MailInfo;
+    use super::build_thread;
+
+    fn info(id: &str, mid: &str, parent: Option<&str>) -> MailInfo {
+        MailInfo {
+            id: StoreId::new_baseless(PathBuf::from(format!("mail/{}", id))).unwrap(),
+            message_id: Some(String::from(mid)),
+            parent: parent.map(String::from),
+            subject: None,
+            from: None,
+            date: None,
+        }
+    }
+
+    fn sid(id: &str) -> StoreId {
+        StoreId::new_baseless(PathBuf::from(format!("mail/{}", id))).unwrap()
+    }
+
+    #[test]
+    fn test_thread_from_reply() {
+        let infos = vec![
+            info("a", "<a@x>", None),
+            info("b", "<b@x>", Some("<a@x>")),
+            info("c", "<c@x>", Some("<b@x>")),
+            info("d", "<d@x>", Some("<a@x>")),
+            info("e", "<e@x>", None),
+        ];
+
+        let thread = build_thread(&infos, &sid("c")).unwrap();
+        assert_eq!(thread.id, sid("a"));
+        assert_eq!(thread.children.len(), 2);
+        assert_eq!(thread.children[0].id, sid("b"));
+        assert_eq!(thread.children[0].children[0].id, sid("c"));
+        assert_eq!(thread.children[1].id, sid("d"));
+    }
+
+    #[test]
+    fn test_thread_unknown_parent() {
+        let infos = vec![
+            info("a", "<a@x>", Some("<not-imported@x>")),
+            info("b", "<b@x>", Some("<a@x>")),
+        ];
+
+        let thread = build_thread(&infos, &sid("b")).unwrap();
+        assert_eq!(thread.id, sid("a"));
+        assert_eq!(thread.children.len(), 1);
+    }
+
+    #[test]
+    fn test_thread_loop() {
+        let infos = vec![
+            info("a", "<a@x>", Some("<b@x>")),
+            info("b", "<b@x>", Some("<a@x>")),
+        ];
+
+        let thread = build_thread(&infos, &sid("a")).unwrap();
+        assert_eq!(thread.children.len(), 1);
+        assert!(thread.children[0].children.is_empty());
+    }
+
+    #[test]
+    fn test_thread_not_a_mail() {
+        assert!(build_thread(&[], &sid("a")).is_none());
+    }
+
+}
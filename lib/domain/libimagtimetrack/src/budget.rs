@@ -0,0 +1,464 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Budgets for timetracking tags
+//!
+//! A budget limits the time which should be spent on a timetracking tag per period, for example
+//! "20 hours per week on 'project-x'".
+//!
+//! Budgets can be configured in the imag configuration file:
+//!
+//! ```toml
+//! [timetrack.budgets]
+//! project-x = { hours = 20, per = "week" }
+//! reading   = { minutes = 30, per = "day" }
+//! ```
+//!
+//! or be stored in the store, in entries `timetrack/budgets/<tag>` with a header like this:
+//!
+//! ```toml
+//! [timetrack.budget]
+//! tag = "project-x"
+//! hours = 20
+//! per = "week"
+//! ```
+//!
+//! Budgets from the store take precedence over budgets from the configuration file.
+//!
+
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::fmt::Error as FmtError;
+use std::fmt::Formatter;
+use std::path::PathBuf;
+
+use chrono::Datelike;
+use chrono::Duration;
+use chrono::NaiveDate;
+use chrono::NaiveDateTime as NDT;
+use toml::Value;
+use toml_query::delete::TomlValueDeleteExt;
+use toml_query::insert::TomlValueInsertExt;
+use toml_query::read::TomlValueReadExt;
+
+use libimagstore::store::Store;
+use libimagstore::storeid::IntoStoreId;
+use libimagstore::storeid::StoreId;
+
+use constants::*;
+use error::Result;
+use error::TimeTrackError as TTE;
+use error::TimeTrackErrorKind as TTEK;
+use module_path::ModuleEntryPath;
+use tag::TimeTrackingTag as TTT;
+use timetracking::TimeTracking;
+use timetrackingstore::TimeTrackStore;
+
+/// The period a budget applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BudgetPeriod {
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+impl BudgetPeriod {
+
+    pub fn from_name(s: &str) -> Option<BudgetPeriod> {
+        match s {
+            "day"   => Some(BudgetPeriod::Day),
+            "week"  => Some(BudgetPeriod::Week),
+            "month" => Some(BudgetPeriod::Month),
+            "year"  => Some(BudgetPeriod::Year),
+            _       => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            BudgetPeriod::Day   => "day",
+            BudgetPeriod::Week  => "week",
+            BudgetPeriod::Month => "month",
+            BudgetPeriod::Year  => "year",
+        }
+    }
+
+    /// Get the start (inclusive) and end (exclusive) of the period which contains `dt`
+    ///
+    /// Weeks start on monday.
+    pub fn range_containing(&self, dt: &NDT) -> (NDT, NDT) {
+        let date = dt.date();
+        let (start, end) = match *self {
+            BudgetPeriod::Day  => (date, date.succ()),
+            BudgetPeriod::Week => {
+                let start = date - Duration::days(date.weekday().num_days_from_monday() as i64);
+                (start, start + Duration::days(7))
+            },
+            BudgetPeriod::Month => {
+                let start = NaiveDate::from_ymd(date.year(), date.month(), 1);
+                let end   = if date.month() == 12 {
+                    NaiveDate::from_ymd(date.year() + 1, 1, 1)
+                } else {
+                    NaiveDate::from_ymd(date.year(), date.month() + 1, 1)
+                };
+                (start, end)
+            },
+            BudgetPeriod::Year => {
+                (NaiveDate::from_ymd(date.year(), 1, 1), NaiveDate::from_ymd(date.year() + 1, 1, 1))
+            },
+        };
+
+        (start.and_hms(0, 0, 0), end.and_hms(0, 0, 0))
+    }
+
+}
+
+/// A budget for a timetracking tag
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Budget {
+    pub tag: TTT,
+    pub duration: Duration,
+    pub period: BudgetPeriod,
+}
+
+impl Budget {
+
+    /// Parse a budget from a table with the keys "hours" or "minutes" and "per"
+    ///
+    /// Hours and minutes can be integers or floats (like `30.5`).
+    pub fn from_toml(tag: TTT, v: &Value) -> Result<Budget> {
+        let err = || TTE::from_kind(TTEK::BudgetFormat(tag.as_str().to_owned()));
+
+        let seconds = match (v.read("hours")?, v.read("minutes")?) {
+            (Some(&Value::Integer(h)), None) => h * 3600,
+            (Some(&Value::Float(h)), None)   => (h * 3600.0).round() as i64,
+            (None, Some(&Value::Integer(m))) => m * 60,
+            (None, Some(&Value::Float(m)))   => (m * 60.0).round() as i64,
+            _                                => return Err(err()),
+        };
+
+        let period = match v.read("per")? {
+            Some(&Value::String(ref s)) => BudgetPeriod::from_name(s).ok_or_else(|| err())?,
+            _                           => return Err(err()),
+        };
+
+        Ok(Budget {
+            tag: tag,
+            duration: Duration::seconds(seconds),
+            period: period,
+        })
+    }
+
+    /// Get the amount of time this budget allows in the range `start` to `end`
+    ///
+    /// If the range is not exactly one period, the budget is prorated to the length of the range.
+    pub fn allowance(&self, start: &NDT, end: &NDT) -> Duration {
+        let (pstart, pend) = self.period.range_containing(start);
+        if pstart == *start && pend == *end {
+            return self.duration
+        }
+
+        let period_secs = pend.signed_duration_since(pstart).num_seconds();
+        let range_secs  = end.signed_duration_since(*start).num_seconds();
+        if period_secs <= 0 || range_secs <= 0 {
+            return Duration::zero()
+        }
+
+        let secs = (self.duration.num_seconds() as f64) * (range_secs as f64) / (period_secs as f64);
+        Duration::seconds(secs as i64)
+    }
+
+    /// Compute how much of this budget is consumed in the range `start` to `end`
+    ///
+    /// Trackings which are still running are counted up to `now`.
+    pub fn report(&self, store: &Store, start: NDT, end: NDT, now: NDT) -> Result<BudgetReport> {
+        let consumed = consumption(store, &self.tag, &start, &end, &now)?;
+
+        Ok(BudgetReport {
+            allowance: self.allowance(&start, &end),
+            budget: self.clone(),
+            start: start,
+            end: end,
+            consumed: consumed,
+        })
+    }
+
+    /// Compute how much of this budget is consumed in the period which contains `now`
+    pub fn report_current(&self, store: &Store, now: NDT) -> Result<BudgetReport> {
+        let (start, end) = self.period.range_containing(&now);
+        self.report(store, start, end, now)
+    }
+
+}
+
+impl Display for Budget {
+    fn fmt(&self, f: &mut Formatter) -> ::std::result::Result<(), FmtError> {
+        write!(f, "{}: {} per {}", self.tag, format_duration(&self.duration), self.period.as_str())
+    }
+}
+
+/// The consumption of a budget in a time range
+#[derive(Debug, Clone)]
+pub struct BudgetReport {
+    pub budget: Budget,
+    pub start: NDT,
+    pub end: NDT,
+    pub allowance: Duration,
+    pub consumed: Duration,
+}
+
+impl BudgetReport {
+
+    /// The time which is left, negative if the budget is overrun
+    pub fn remaining(&self) -> Duration {
+        self.allowance - self.consumed
+    }
+
+    pub fn is_overrun(&self) -> bool {
+        self.consumed > self.allowance
+    }
+
+}
+
+impl Display for BudgetReport {
+    fn fmt(&self, f: &mut Formatter) -> ::std::result::Result<(), FmtError> {
+        if self.is_overrun() {
+            write!(f, "{}: {} of {} used, overrun by {}",
+                   self.budget.tag,
+                   format_duration(&self.consumed),
+                   format_duration(&self.allowance),
+                   format_duration(&(self.consumed - self.allowance)))
+        } else {
+            write!(f, "{}: {} of {} used, {} remaining",
+                   self.budget.tag,
+                   format_duration(&self.consumed),
+                   format_duration(&self.allowance),
+                   format_duration(&self.remaining()))
+        }
+    }
+}
+
+/// Format a duration as "<hours>h <minutes>m"
+pub fn format_duration(d: &Duration) -> String {
+    let minutes = d.num_minutes();
+    let sign    = if minutes < 0 { "-" } else { "" };
+    format!("{}{}h {:02}m", sign, minutes.abs() / 60, minutes.abs() % 60)
+}
+
+/// Sum up the time tracked for `tag` in the range `start` to `end`
+///
+/// Only the part of a tracking which lies inside the range is counted. Trackings which are still
+/// running are counted up to `now`.
+pub fn consumption(store: &Store, tag: &TTT, start: &NDT, end: &NDT, now: &NDT) -> Result<Duration> {
+    let mut sum = Duration::zero();
+
+    for tracking in store.get_timetrackings()? {
+        let tracking = tracking?;
+        if tracking.get_timetrack_tag()? != *tag {
+            continue
+        }
+
+        let tstart = match tracking.get_start_datetime()? {
+            Some(s) => s,
+            None    => continue,
+        };
+        let tend = tracking.get_end_datetime()?.unwrap_or_else(|| *now);
+
+        let from = ::std::cmp::max(tstart, *start);
+        let to   = ::std::cmp::min(tend, *end);
+        if from < to {
+            sum = sum + to.signed_duration_since(from);
+        }
+    }
+
+    Ok(sum)
+}
+
+/// Read all budgets from the "timetrack.budgets" section of the configuration
+pub fn budgets_from_config(config: &Value) -> Result<Vec<Budget>> {
+    match config.read("timetrack.budgets")? {
+        None                         => Ok(vec![]),
+        Some(&Value::Table(ref tab)) => tab
+            .iter()
+            .map(|(tag, v)| Budget::from_toml(TTT::from(tag), v))
+            .collect(),
+        Some(_) => Err(TTE::from_kind(TTEK::HeaderFieldTypeError)),
+    }
+}
+
+/// Read all budgets from the store
+pub fn budgets_from_store(store: &Store) -> Result<Vec<Budget>> {
+    store.entries()?
+        .without_store()
+        .filter(|id| id.as_ref().map(|id| id.is_in_collection(&[CRATE_NAME, BUDGET_COLLECTION])).unwrap_or(true))
+        .filter_map(|id| match id {
+            Err(e) => Some(Err(TTE::from(e))),
+            Ok(id) => match store.get(id) {
+                Err(e)          => Some(Err(TTE::from(e))),
+                Ok(None)        => None,
+                Ok(Some(entry)) => Some(budget_from_header(entry.get_header())),
+            }
+        })
+        .collect()
+}
+
+/// Read all budgets, from the configuration and the store
+///
+/// If a budget for a tag exists in both, the one from the store wins.
+pub fn budgets(store: &Store, config: Option<&Value>) -> Result<Vec<Budget>> {
+    let mut map = BTreeMap::new();
+
+    if let Some(config) = config {
+        for budget in budgets_from_config(config)? {
+            map.insert(budget.tag.clone(), budget);
+        }
+    }
+
+    for budget in budgets_from_store(store)? {
+        map.insert(budget.tag.clone(), budget);
+    }
+
+    Ok(map.into_iter().map(|(_, v)| v).collect())
+}
+
+/// Store a budget in the store, overriding the existing budget for the tag
+///
+/// The budget is always stored in minutes, as a float if it is not a whole number of minutes.
+pub fn set_budget(store: &Store, budget: &Budget) -> Result<()> {
+    let mut entry = store.retrieve(budget_storeid(&budget.tag)?)?;
+    let header    = entry.get_header_mut();
+
+    let seconds = budget.duration.num_seconds();
+    let minutes = if seconds % 60 == 0 {
+        Value::Integer(seconds / 60)
+    } else {
+        Value::Float(seconds as f64 / 60.0)
+    };
+    let tag     = Value::String(budget.tag.as_str().to_owned());
+    let per     = Value::String(budget.period.as_str().to_owned());
+
+    if header.read(BUDGET_HOURS_HEADER_PATH)?.is_some() {
+        let _ = header.delete(BUDGET_HOURS_HEADER_PATH)?;
+    }
+    let _ = header.insert(BUDGET_TAG_HEADER_PATH, tag)?;
+    let _ = header.insert(BUDGET_MINUTES_HEADER_PATH, minutes)?;
+    let _ = header.insert(BUDGET_PER_HEADER_PATH, per)?;
+    Ok(())
+}
+
+fn budget_storeid(tag: &TTT) -> Result<StoreId> {
+    ModuleEntryPath::new(PathBuf::from(BUDGET_COLLECTION).join(tag.as_str()))
+        .into_storeid()
+        .map_err(From::from)
+}
+
+fn budget_from_header(header: &Value) -> Result<Budget> {
+    let tag = match header.read(BUDGET_TAG_HEADER_PATH)? {
+        Some(&Value::String(ref s)) => TTT::from(s),
+        _                           => return Err(TTE::from_kind(TTEK::HeaderReadError)),
+    };
+
+    match header.read(BUDGET_HEADER_PATH)? {
+        Some(v) => Budget::from_toml(tag, v),
+        None    => Err(TTE::from_kind(TTEK::HeaderReadError)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::Duration;
+    use chrono::naive::NaiveDate;
+
+    use super::*;
+
+    #[test]
+    fn test_period_ranges() {
+        let dt = NaiveDate::from_ymd(2018, 12, 13).and_hms(10, 0, 0); // a thursday
+
+        let (s, e) = BudgetPeriod::Week.range_containing(&dt);
+        assert_eq!(s, NaiveDate::from_ymd(2018, 12, 10).and_hms(0, 0, 0));
+        assert_eq!(e, NaiveDate::from_ymd(2018, 12, 17).and_hms(0, 0, 0));
+
+        let (s, e) = BudgetPeriod::Month.range_containing(&dt);
+        assert_eq!(s, NaiveDate::from_ymd(2018, 12, 1).and_hms(0, 0, 0));
+        assert_eq!(e, NaiveDate::from_ymd(2019, 1, 1).and_hms(0, 0, 0));
+    }
+
+    #[test]
+    fn test_budget_from_toml() {
+        let v : Value = ::toml::de::from_str("hours = 20\nper = \"week\"").unwrap();
+        let b = Budget::from_toml(TTT::from(String::from("foo")), &v).unwrap();
+        assert_eq!(b.duration, Duration::hours(20));
+        assert_eq!(b.period, BudgetPeriod::Week);
+
+        let v : Value = ::toml::de::from_str("minutes = 30.5\nper = \"day\"").unwrap();
+        let b = Budget::from_toml(TTT::from(String::from("foo")), &v).unwrap();
+        assert_eq!(b.duration, Duration::seconds(30 * 60 + 30));
+
+        let v : Value = ::toml::de::from_str("hours = 20\nper = \"fortnight\"").unwrap();
+        assert!(Budget::from_toml(TTT::from(String::from("foo")), &v).is_err());
+
+        let v : Value = ::toml::de::from_str("minutes = \"30\"\nper = \"day\"").unwrap();
+        assert!(Budget::from_toml(TTT::from(String::from("foo")), &v).is_err());
+    }
+
+    #[test]
+    fn test_set_budget_replaces_hours() {
+        use std::sync::Arc;
+        use libimagstore::file_abstraction::InMemoryFileAbstraction;
+
+        let store = Store::new_with_backend(PathBuf::from("/"),
+                                            &None,
+                                            Arc::new(InMemoryFileAbstraction::default())).unwrap();
+        let tag = TTT::from(String::from("foo"));
+
+        {
+            let mut entry = store.retrieve(budget_storeid(&tag).unwrap()).unwrap();
+            let header    = entry.get_header_mut();
+            header.insert(BUDGET_TAG_HEADER_PATH, Value::String(String::from("foo"))).unwrap();
+            header.insert(BUDGET_HOURS_HEADER_PATH, Value::Integer(20)).unwrap();
+            header.insert(BUDGET_PER_HEADER_PATH, Value::String(String::from("week"))).unwrap();
+        }
+
+        let budget = Budget {
+            tag: tag.clone(),
+            duration: Duration::seconds(90 * 60 + 30),
+            period: BudgetPeriod::Day,
+        };
+        set_budget(&store, &budget).unwrap();
+
+        let entry = store.get(budget_storeid(&tag).unwrap()).unwrap().unwrap();
+        assert_eq!(budget_from_header(entry.get_header()).unwrap(), budget);
+    }
+
+    #[test]
+    fn test_allowance_prorated() {
+        let b = Budget {
+            tag: TTT::from(String::from("foo")),
+            duration: Duration::hours(14),
+            period: BudgetPeriod::Week,
+        };
+
+        let start = NaiveDate::from_ymd(2018, 12, 10).and_hms(0, 0, 0);
+        assert_eq!(b.allowance(&start, &(start + Duration::days(7))), Duration::hours(14));
+        assert_eq!(b.allowance(&start, &(start + Duration::days(1))), Duration::hours(2));
+        assert_eq!(b.allowance(&start, &(start + Duration::days(14))), Duration::hours(28));
+    }
+}
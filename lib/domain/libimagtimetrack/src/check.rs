@@ -0,0 +1,355 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Consistency checks for timetrackings
+//!
+//! This module contains a checker which walks over all timetrackings in the store and reports
+//! anomalies, as well as functions to fix the reported anomalies.
+//!
+//! Detected anomalies are:
+//!
+//! * Trackings without start time
+//! * Trackings where the end time lies before the start time
+//! * Trackings which were started, but never stopped. Such a tracking is reported if another
+//!   tracking of the same tag was started after it, or if it is running longer than a maximum
+//!   duration
+//! * Trackings of the same tag which overlap
+//! * Trackings of the same tag which are only separated by a small gap (optional)
+//!
+
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::fmt::Error as FmtError;
+use std::fmt::Formatter;
+
+use chrono::Duration;
+use chrono::NaiveDateTime as NDT;
+
+use libimagstore::store::Store;
+use libimagstore::storeid::StoreId;
+
+use error::Result;
+use error::TimeTrackError as TTE;
+use error::TimeTrackErrorKind as TTEK;
+use tag::TimeTrackingTag as TTT;
+use timetracking::TimeTracking;
+use timetrackingstore::TimeTrackStore;
+
+/// The relevant data of a timetracking entry, without holding the entry itself
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrackingSummary {
+    pub id: StoreId,
+    pub tag: TTT,
+    pub start: Option<NDT>,
+    pub end: Option<NDT>,
+}
+
+impl Display for TrackingSummary {
+    fn fmt(&self, f: &mut Formatter) -> ::std::result::Result<(), FmtError> {
+        let start = self.start.map(|s| format!("{}", s)).unwrap_or_else(|| String::from("?"));
+        let end   = self.end.map(|e| format!("{}", e)).unwrap_or_else(|| String::from("..."));
+        write!(f, "{} ({} - {}, {})", self.tag, start, end, self.id)
+    }
+}
+
+/// An anomaly found by the checker
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Anomaly {
+    /// The tracking has no start time
+    MissingStart(TrackingSummary),
+
+    /// The tracking ends before it starts
+    EndBeforeStart(TrackingSummary),
+
+    /// The tracking was never stopped
+    ///
+    /// The second member is the start time of the next tracking of the same tag, if there is one
+    Dangling(TrackingSummary, Option<NDT>),
+
+    /// The two trackings of the same tag overlap
+    Overlap(TrackingSummary, TrackingSummary),
+
+    /// The two trackings of the same tag are only separated by a short gap
+    Gap(TrackingSummary, TrackingSummary),
+}
+
+impl Anomaly {
+
+    /// Get a human readable suggestion on how to fix the anomaly
+    pub fn suggestion(&self) -> String {
+        match *self {
+            Anomaly::MissingStart(_) => {
+                String::from("Set a start time by hand or delete the tracking")
+            },
+            Anomaly::EndBeforeStart(_) => {
+                String::from("Swap start and end time by hand or delete the tracking")
+            },
+            Anomaly::Dangling(_, Some(next)) => {
+                format!("Close the tracking, for example at {} when the next tracking started", next)
+            },
+            Anomaly::Dangling(_, None) => {
+                String::from("Close the tracking at the time it actually ended")
+            },
+            Anomaly::Overlap(_, _) => String::from("Merge both trackings into one"),
+            Anomaly::Gap(_, _)     => String::from("Merge both trackings into one"),
+        }
+    }
+
+}
+
+impl Display for Anomaly {
+    fn fmt(&self, f: &mut Formatter) -> ::std::result::Result<(), FmtError> {
+        match *self {
+            Anomaly::MissingStart(ref t)   => write!(f, "Missing start time: {}", t),
+            Anomaly::EndBeforeStart(ref t) => write!(f, "Ends before it starts: {}", t),
+            Anomaly::Dangling(ref t, _)    => write!(f, "Never stopped: {}", t),
+            Anomaly::Overlap(ref a, ref b) => write!(f, "Overlapping: {} and {}", a, b),
+            Anomaly::Gap(ref a, ref b)     => write!(f, "Short gap: {} and {}", a, b),
+        }
+    }
+}
+
+/// Collect the summaries of all timetrackings in the store
+pub fn summaries(store: &Store) -> Result<Vec<TrackingSummary>> {
+    store.get_timetrackings()?
+        .map(|r| r.map_err(TTE::from))
+        .map(|r| r.and_then(|e| {
+            Ok(TrackingSummary {
+                id: e.get_location().clone(),
+                tag: e.get_timetrack_tag()?,
+                start: e.get_start_datetime()?,
+                end: e.get_end_datetime()?,
+            })
+        }))
+        .collect()
+}
+
+/// Check all timetrackings in the store for anomalies
+///
+/// A tracking which is still running is reported as dangling if it was started more than
+/// `max_duration` before `now`.
+/// Two consecutive trackings of the same tag are reported as `Anomaly::Gap` if the gap between
+/// them is not longer than `max_gap`.
+///
+pub fn check_timetrackings(store: &Store,
+                           now: NDT,
+                           max_duration: Option<Duration>,
+                           max_gap: Option<Duration>)
+    -> Result<Vec<Anomaly>>
+{
+    summaries(store).map(|s| find_anomalies(s, now, max_duration, max_gap))
+}
+
+/// Find anomalies in a list of timetracking summaries
+///
+/// See `check_timetrackings()`.
+pub fn find_anomalies(trackings: Vec<TrackingSummary>,
+                      now: NDT,
+                      max_duration: Option<Duration>,
+                      max_gap: Option<Duration>)
+    -> Vec<Anomaly>
+{
+    let mut anomalies = vec![];
+    let mut by_tag : BTreeMap<TTT, Vec<(NDT, TrackingSummary)>> = BTreeMap::new();
+
+    for tracking in trackings {
+        match (tracking.start, tracking.end) {
+            (None, _) => anomalies.push(Anomaly::MissingStart(tracking)),
+            (Some(s), Some(e)) if e < s => anomalies.push(Anomaly::EndBeforeStart(tracking)),
+            (Some(s), _) => by_tag.entry(tracking.tag.clone()).or_insert_with(Vec::new).push((s, tracking)),
+        }
+    }
+
+    for (_, mut trackings) in by_tag {
+        trackings.sort_by(|a, b| a.0.cmp(&b.0));
+
+        // The tracking of this tag which ends latest so far
+        let mut previous : Option<TrackingSummary> = None;
+
+        for (start, tracking) in trackings {
+            let next = match previous {
+                None => tracking,
+                Some(prev) => match prev.end {
+                    None => {
+                        anomalies.push(Anomaly::Dangling(prev, Some(start)));
+                        tracking
+                    },
+                    Some(prev_end) => {
+                        if start < prev_end {
+                            anomalies.push(Anomaly::Overlap(prev.clone(), tracking.clone()));
+                        } else if max_gap.map(|gap| start.signed_duration_since(prev_end) <= gap).unwrap_or(false) {
+                            anomalies.push(Anomaly::Gap(prev.clone(), tracking.clone()));
+                        }
+
+                        match tracking.end {
+                            Some(end) if end <= prev_end => prev,
+                            _ => tracking,
+                        }
+                    },
+                },
+            };
+
+            previous = Some(next);
+        }
+
+        if let Some(last) = previous {
+            if last.end.is_none() {
+                let start   = last.start.unwrap_or(now); // all trackings in here have a start
+                let too_old = max_duration
+                    .map(|max| now.signed_duration_since(start) > max)
+                    .unwrap_or(false);
+
+                if too_old {
+                    anomalies.push(Anomaly::Dangling(last, None));
+                }
+            }
+        }
+    }
+
+    anomalies
+}
+
+/// Close a dangling tracking by setting its end time
+///
+/// Fails if `end` lies before the start of the tracking.
+pub fn close_dangling(store: &Store, id: StoreId, end: NDT) -> Result<()> {
+    let mut entry = store.get(id.clone())?.ok_or_else(|| TTE::from_kind(TTEK::EntryNotFound(id)))?;
+
+    match entry.get_start_datetime()? {
+        Some(start) if end < start => Err(TTE::from_kind(TTEK::EndBeforeStart)),
+        _ => entry.set_end_datetime(end),
+    }
+}
+
+/// Merge two trackings of the same tag into one
+///
+/// The tracking identified by `keep` gets the earliest start time and the latest end time of both
+/// trackings (no end time if one of them is still running), the tracking identified by `remove` is
+/// deleted from the store afterwards.
+pub fn merge_trackings(store: &Store, keep: StoreId, remove: StoreId) -> Result<()> {
+    let (tag, start, end) = {
+        let other = store
+            .get(remove.clone())?
+            .ok_or_else(|| TTE::from_kind(TTEK::EntryNotFound(remove.clone())))?;
+        (other.get_timetrack_tag()?, other.get_start_datetime()?, other.get_end_datetime()?)
+    };
+
+    {
+        let mut entry = store
+            .get(keep.clone())?
+            .ok_or_else(|| TTE::from_kind(TTEK::EntryNotFound(keep.clone())))?;
+
+        if entry.get_timetrack_tag()? != tag {
+            return Err(TTE::from_kind(TTEK::TagMismatch))
+        }
+
+        match (entry.get_start_datetime()?, start) {
+            (Some(a), Some(b)) if b < a => entry.set_start_datetime(b)?,
+            (None, Some(b))             => entry.set_start_datetime(b)?,
+            _                           => (),
+        }
+
+        match (entry.get_end_datetime()?, end) {
+            (Some(a), Some(b)) if b > a => entry.set_end_datetime(b)?,
+            (Some(_), None)             => entry.delete_end_datetime()?,
+            _                           => (),
+        }
+    }
+
+    store.delete(remove).map_err(From::from)
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use chrono::Duration;
+    use chrono::naive::NaiveDate;
+    use chrono::naive::NaiveDateTime as NDT;
+
+    use libimagstore::storeid::StoreId;
+
+    use super::*;
+
+    fn dt(h: u32, m: u32) -> NDT {
+        NaiveDate::from_ymd(2000, 1, 1).and_hms(h, m, 0)
+    }
+
+    fn tracking(name: &str, tag: &str, start: Option<NDT>, end: Option<NDT>) -> TrackingSummary {
+        TrackingSummary {
+            id: StoreId::new_baseless(PathBuf::from(format!("timetrack/{}", name))).unwrap(),
+            tag: TTT::from(String::from(tag)),
+            start: start,
+            end: end,
+        }
+    }
+
+    #[test]
+    fn test_no_anomalies() {
+        let trackings = vec![
+            tracking("a", "foo", Some(dt(1, 0)), Some(dt(2, 0))),
+            tracking("b", "foo", Some(dt(3, 0)), Some(dt(4, 0))),
+            tracking("c", "bar", Some(dt(1, 30)), None),
+        ];
+
+        assert!(find_anomalies(trackings, dt(5, 0), None, None).is_empty());
+    }
+
+    #[test]
+    fn test_invalid_trackings() {
+        let missing = tracking("a", "foo", None, Some(dt(2, 0)));
+        let swapped = tracking("b", "foo", Some(dt(4, 0)), Some(dt(3, 0)));
+        let trackings = vec![missing.clone(), swapped.clone()];
+
+        let anomalies = find_anomalies(trackings, dt(5, 0), None, None);
+        assert_eq!(anomalies, vec![Anomaly::MissingStart(missing), Anomaly::EndBeforeStart(swapped)]);
+    }
+
+    #[test]
+    fn test_dangling() {
+        let dangling = tracking("a", "foo", Some(dt(1, 0)), None);
+        let later    = tracking("b", "foo", Some(dt(3, 0)), Some(dt(4, 0)));
+        let running  = tracking("c", "bar", Some(dt(1, 0)), None);
+        let trackings = vec![dangling.clone(), later, running.clone()];
+
+        let anomalies = find_anomalies(trackings.clone(), dt(5, 0), None, None);
+        assert_eq!(anomalies, vec![Anomaly::Dangling(dangling.clone(), Some(dt(3, 0)))]);
+
+        let anomalies = find_anomalies(trackings, dt(5, 0), Some(Duration::hours(2)), None);
+        assert_eq!(anomalies, vec![
+            Anomaly::Dangling(running, None),
+            Anomaly::Dangling(dangling, Some(dt(3, 0))),
+        ]);
+    }
+
+    #[test]
+    fn test_overlap_and_gap() {
+        let a = tracking("a", "foo", Some(dt(1, 0)), Some(dt(3, 0)));
+        let b = tracking("b", "foo", Some(dt(2, 0)), Some(dt(2, 30)));
+        let c = tracking("c", "foo", Some(dt(3, 10)), Some(dt(4, 0)));
+        let d = tracking("d", "bar", Some(dt(2, 0)), Some(dt(5, 0)));
+        let trackings = vec![a.clone(), b.clone(), c.clone(), d];
+
+        let anomalies = find_anomalies(trackings.clone(), dt(5, 0), None, None);
+        assert_eq!(anomalies, vec![Anomaly::Overlap(a.clone(), b.clone())]);
+
+        let anomalies = find_anomalies(trackings, dt(5, 0), None, Some(Duration::minutes(15)));
+        assert_eq!(anomalies, vec![Anomaly::Overlap(a.clone(), b), Anomaly::Gap(a, c)]);
+    }
+}
@@ -23,3 +23,10 @@ pub const DATE_TIME_START_HEADER_PATH : &'static str = "timetrack.start";
 pub const DATE_TIME_END_HEADER_PATH : &'static str   = "timetrack.end";
 pub const DATE_TIME_TAG_HEADER_PATH : &'static str   = "timetrack.tag";
 
+pub const BUDGET_COLLECTION : &'static str          = "budgets";
+pub const SESSION_COLLECTION : &'static str         = "sessions";
+pub const BUDGET_HEADER_PATH : &'static str         = "timetrack.budget";
+pub const BUDGET_TAG_HEADER_PATH : &'static str     = "timetrack.budget.tag";
+pub const BUDGET_HOURS_HEADER_PATH : &'static str   = "timetrack.budget.hours";
+pub const BUDGET_MINUTES_HEADER_PATH : &'static str = "timetrack.budget.minutes";
+pub const BUDGET_PER_HEADER_PATH : &'static str     = "timetrack.budget.per";
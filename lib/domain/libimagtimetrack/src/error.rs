@@ -17,6 +17,8 @@
 // Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
 //
 
+use libimagstore::storeid::StoreId;
+
 error_chain! {
     types {
         TimeTrackError, TimeTrackErrorKind, ResultExt, Result;
@@ -27,6 +29,7 @@ error_chain! {
         DateTimeError(::libimagentrydatetime::error::DateError, ::libimagentrydatetime::error::DateErrorKind);
         DatePathError(::libimagentrydatetime::datepath::error::DatePathCompilerError, ::libimagentrydatetime::datepath::error::DatePathCompilerErrorKind);
         TomlError(::toml_query::error::Error, ::toml_query::error::ErrorKind);
+        LinkError(::libimagentrylink::error::LinkError, ::libimagentrylink::error::LinkErrorKind);
     }
 
     foreign_links {
@@ -48,6 +51,26 @@ error_chain! {
             description("Type error in header")
             display("Type error in header")
         }
+
+        EntryNotFound(id: StoreId) {
+            description("Timetracking entry not found")
+            display("Timetracking entry not found: {}", id)
+        }
+
+        EndBeforeStart {
+            description("End time lies before start time")
+            display("End time lies before start time")
+        }
+
+        BudgetFormat(tag: String) {
+            description("Budget has invalid format")
+            display("Budget for '{}' has invalid format, expected a number of 'hours' or 'minutes' and a period 'per'", tag)
+        }
+
+        TagMismatch {
+            description("Timetrackings have different tags")
+            display("Timetrackings have different tags")
+        }
     }
 }
 
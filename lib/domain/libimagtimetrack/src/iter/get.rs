@@ -39,7 +39,10 @@ impl<'a> Iterator for TimeTrackingsGetIterator<'a> {
         while let Some(next) = self.0.next() {
             match next {
                 Err(e)   => return Some(Err(e)),
-                Ok(next) => if next.is_in_collection(&[CRATE_NAME]) {
+                Ok(next) => if next.is_in_collection(&[CRATE_NAME])
+                    && !next.is_in_collection(&[CRATE_NAME, BUDGET_COLLECTION])
+                    && !next.is_in_collection(&[CRATE_NAME, SESSION_COLLECTION])
+                {
                     return match self.1.get(next) {
                         Ok(Some(fle)) => Some(Ok(fle)),
                         Ok(None)      => continue,
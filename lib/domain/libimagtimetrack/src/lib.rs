@@ -49,13 +49,17 @@ extern crate is_match;
 extern crate libimagstore;
 extern crate libimagentrydatetime;
 extern crate libimagentrytag;
+extern crate libimagentrylink;
 extern crate libimagerror;
 
 mod constants;
+pub mod budget;
+pub mod check;
 pub mod error;
 pub mod timetracking;
 pub mod timetrackingstore;
 pub mod iter;
+pub mod session;
 pub mod tag;
 
 module_entry_path_mod!("timetrack");
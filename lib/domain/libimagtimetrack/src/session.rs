@@ -0,0 +1,156 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Timetracking sessions
+//!
+//! A session groups several timetrackings of one tag, for example the work intervals of a
+//! `imag-timetrack timer` run. A session is stored as `/timetrack/sessions/<start>-<tag>` and
+//! uses the same header fields as a timetracking, so the `TimeTracking` trait can be used to get
+//! and set its start and end time. Sessions are not returned by
+//! `TimeTrackStore::get_timetrackings()`.
+//!
+//! Each timetracking which is created as part of a session is linked to the session entry.
+//!
+
+use std::path::PathBuf;
+
+use chrono::NaiveDateTime as NDT;
+use toml::Value;
+use toml_query::insert::TomlValueInsertExt;
+
+use libimagstore::store::FileLockEntry;
+use libimagstore::store::Store;
+use libimagstore::storeid::IntoStoreId;
+use libimagentrylink::internal::InternalLinker;
+
+use constants::*;
+use error::Result;
+use module_path::ModuleEntryPath;
+use tag::TimeTrackingTag as TTT;
+use timetracking::TimeTracking;
+use timetrackingstore::TimeTrackStore;
+
+pub trait TimeTrackSessionStore<'a> {
+
+    /// Create a new session for `tag`, starting at `start`
+    fn create_session_at(&'a self, start: &NDT, tag: &TTT) -> Result<FileLockEntry<'a>>;
+
+    /// Start a new timetracking at `start` which belongs to `session`
+    ///
+    /// The timetracking gets the tag of the session and is linked to the session entry.
+    fn start_session_tracking(&'a self, session: &mut FileLockEntry<'a>, start: &NDT)
+        -> Result<FileLockEntry<'a>>;
+
+}
+
+impl<'a> TimeTrackSessionStore<'a> for Store {
+
+    fn create_session_at(&'a self, start: &NDT, tag: &TTT) -> Result<FileLockEntry<'a>> {
+        let id = format!("{}-{}", start.format(DATE_TIME_FORMAT), tag.as_str());
+        let id = ModuleEntryPath::new(PathBuf::from(SESSION_COLLECTION).join(id)).into_storeid()?;
+
+        let mut session = self.create(id)?;
+        let _ = session
+            .get_header_mut()
+            .insert(DATE_TIME_TAG_HEADER_PATH, Value::String(tag.as_str().to_owned()))?;
+        session.set_start_datetime(*start)?;
+        Ok(session)
+    }
+
+    fn start_session_tracking(&'a self, session: &mut FileLockEntry<'a>, start: &NDT)
+        -> Result<FileLockEntry<'a>>
+    {
+        let tag          = session.get_timetrack_tag()?;
+        let mut tracking = self.create_timetracking_at(start, &tag)?;
+        tracking.add_internal_link(session)?;
+        Ok(tracking)
+    }
+
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use chrono::naive::NaiveDate;
+
+    use libimagstore::store::Store;
+    use libimagstore::file_abstraction::InMemoryFileAbstraction;
+    use libimagentrylink::internal::InternalLinker;
+
+    use super::*;
+    use tag::TimeTrackingTag as TTT;
+    use timetracking::TimeTracking;
+    use timetrackingstore::TimeTrackStore;
+
+    fn get_store() -> Store {
+        let backend = Arc::new(InMemoryFileAbstraction::default());
+        Store::new_with_backend(PathBuf::from("/"), &None, backend).unwrap()
+    }
+
+    #[test]
+    fn test_session_start_stop() {
+        let store = get_store();
+        let tag   = TTT::from(String::from("work"));
+        let start = NaiveDate::from_ymd(2018, 12, 10).and_hms(9, 0, 0);
+        let end   = NaiveDate::from_ymd(2018, 12, 10).and_hms(10, 0, 0);
+
+        let mut session = store.create_session_at(&start, &tag).unwrap();
+        assert_eq!(session.get_start_datetime().unwrap(), Some(start));
+        assert_eq!(session.get_end_datetime().unwrap(), None);
+        assert_eq!(session.get_timetrack_tag().unwrap(), tag);
+
+        session.set_end_datetime(end).unwrap();
+        assert_eq!(session.get_start_datetime().unwrap(), Some(start));
+        assert_eq!(session.get_end_datetime().unwrap(), Some(end));
+    }
+
+    #[test]
+    fn test_session_trackings() {
+        let store = get_store();
+        let tag   = TTT::from(String::from("work"));
+        let start = NaiveDate::from_ymd(2018, 12, 10).and_hms(9, 0, 0);
+        let pause = NaiveDate::from_ymd(2018, 12, 10).and_hms(9, 25, 0);
+        let again = NaiveDate::from_ymd(2018, 12, 10).and_hms(9, 30, 0);
+        let end   = NaiveDate::from_ymd(2018, 12, 10).and_hms(9, 55, 0);
+
+        let mut session = store.create_session_at(&start, &tag).unwrap();
+
+        let mut first = store.start_session_tracking(&mut session, &start).unwrap();
+        first.set_end_datetime(pause).unwrap();
+        let mut second = store.start_session_tracking(&mut session, &again).unwrap();
+        second.set_end_datetime(end).unwrap();
+        session.set_end_datetime(end).unwrap();
+
+        for tracking in vec![&first, &second] {
+            assert_eq!(tracking.get_timetrack_tag().unwrap(), tag);
+            let links = tracking.get_internal_links().unwrap().collect::<Vec<_>>();
+            assert_eq!(links.len(), 1);
+            assert_eq!(links[0].get_store_id(), session.get_location());
+        }
+
+        assert_eq!(first.get_start_datetime().unwrap(), Some(start));
+        assert_eq!(first.get_end_datetime().unwrap(), Some(pause));
+        assert_eq!(second.get_start_datetime().unwrap(), Some(again));
+        assert_eq!(second.get_end_datetime().unwrap(), Some(end));
+        assert_eq!(session.get_internal_links().unwrap().count(), 2);
+    }
+
+}
@@ -0,0 +1,146 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! A simple line based diff, for comparing revisions of wiki pages
+
+/// A line in a diff
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffLine<'a> {
+    Same(&'a str),
+    Added(&'a str),
+    Removed(&'a str),
+}
+
+impl<'a> DiffLine<'a> {
+
+    pub fn is_change(&self) -> bool {
+        match *self {
+            DiffLine::Same(_) => false,
+            _                 => true,
+        }
+    }
+
+}
+
+/// Compute the diff between `old` and `new`, line by line
+///
+/// This computes the longest common subsequence of the lines, which is quadratic in the number of
+/// lines, but wiki pages are not that long.
+pub fn diff<'a>(old: &'a str, new: &'a str) -> Vec<DiffLine<'a>> {
+    let old : Vec<&str> = old.lines().collect();
+    let new : Vec<&str> = new.lines().collect();
+
+    // lcs[i][j] is the length of the longest common subsequence of old[i..] and new[j..]
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                ::std::cmp::max(lcs[i + 1][j], lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut result = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            result.push(DiffLine::Same(old[i]));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            result.push(DiffLine::Removed(old[i]));
+            i += 1;
+        } else {
+            result.push(DiffLine::Added(new[j]));
+            j += 1;
+        }
+    }
+
+    result.extend(old[i..].iter().map(|l| DiffLine::Removed(l)));
+    result.extend(new[j..].iter().map(|l| DiffLine::Added(l)));
+    result
+}
+
+/// Format a diff with `-` and `+` prefixes, showing `context` unchanged lines around changes
+///
+/// Skipped unchanged lines are shown as `...`. If there are no changes, the result is empty.
+pub fn format_diff(lines: &[DiffLine], context: usize) -> String {
+    let show = |idx: usize| -> bool {
+        let from = idx.saturating_sub(context);
+        let to   = ::std::cmp::min(idx + context + 1, lines.len());
+        lines[from..to].iter().any(DiffLine::is_change)
+    };
+
+    let mut s       = String::new();
+    let mut skipped = false;
+
+    for (idx, line) in lines.iter().enumerate() {
+        if !show(idx) {
+            skipped = true;
+            continue;
+        }
+
+        if skipped {
+            s.push_str("...\n");
+            skipped = false;
+        }
+
+        match *line {
+            DiffLine::Same(l)    => s.push_str(&format!("  {}\n", l)),
+            DiffLine::Added(l)   => s.push_str(&format!("+ {}\n", l)),
+            DiffLine::Removed(l) => s.push_str(&format!("- {}\n", l)),
+        }
+    }
+
+    if skipped && !s.is_empty() {
+        s.push_str("...\n");
+    }
+
+    s
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_diff() {
+        let old = "a\nb\nc\nd";
+        let new = "a\nc\nd\ne";
+
+        assert_eq!(diff(old, new), vec![
+            DiffLine::Same("a"),
+            DiffLine::Removed("b"),
+            DiffLine::Same("c"),
+            DiffLine::Same("d"),
+            DiffLine::Added("e"),
+        ]);
+    }
+
+    #[test]
+    fn test_format_diff() {
+        let old = "1\n2\n3\n4\n5\n6";
+        let new = "1\n2\n3\n4\n5\nsix";
+
+        assert_eq!(format_diff(&diff(old, new), 1), "...\n  5\n- 6\n+ six\n");
+        assert_eq!(format_diff(&diff(old, old), 1), "");
+    }
+}
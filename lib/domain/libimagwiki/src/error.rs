@@ -27,6 +27,17 @@ error_chain! {
     links {
         StoreError(::libimagstore::error::StoreError, ::libimagstore::error::StoreErrorKind);
         LinkError(::libimagentrylink::error::LinkError, ::libimagentrylink::error::LinkErrorKind);
+        MarkdownError(::libimagentrymarkdown::error::MarkdownError, ::libimagentrymarkdown::error::MarkdownErrorKind);
+        TagError(::libimagentrytag::error::TagError, ::libimagentrytag::error::TagErrorKind);
+        CategoryError(::libimagentrycategory::error::CategoryError, ::libimagentrycategory::error::CategoryErrorKind);
+        DateError(::libimagentrydatetime::error::DateError, ::libimagentrydatetime::error::DateErrorKind);
+    }
+
+    foreign_links {
+        Io(::std::io::Error);
+        TomlQueryError(::toml_query::error::Error);
+        WalkDirError(::walkdir::Error);
+        YamlError(::yaml_rust::ScanError);
     }
 
     errors {
@@ -49,6 +60,36 @@ error_chain! {
             description("Index page for wiki is missing")
                 display("Index page for wiki is missing")
         }
+
+        PageDoesNotExist(name: String) {
+            description("Wiki page does not exist")
+                display("Wiki page '{}' does not exist", name)
+        }
+
+        PageExists(name: String) {
+            description("Wiki page exists already")
+                display("Wiki page '{}' exists already", name)
+        }
+
+        RevisionExists(name: String, number: usize) {
+            description("Revision of wiki page exists already")
+                display("Revision {} of wiki page '{}' exists already", number, name)
+        }
+
+        IndexCannotBeRenamed {
+            description("The index page of a wiki cannot be renamed")
+                display("The index page of a wiki cannot be renamed")
+        }
+
+        RevisionDoesNotExist(name: String, number: usize) {
+            description("Revision of wiki page does not exist")
+                display("Revision {} of wiki page '{}' does not exist", number, name)
+        }
+
+        NotAWikiPage(sid: StoreId) {
+            description("Entry is not a page of this wiki")
+                display("Entry is not a page of this wiki: {}", sid)
+        }
     }
 
 }
@@ -0,0 +1,243 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Exporting a wiki as a static HTML site
+//!
+//! Each page is rendered to `<page>.html`, with the links to other pages of the wiki rewritten to
+//! relative paths, so the site can be browsed offline. Each page lists its tags, category and the
+//! pages linking to it. The index page gets a list of all pages appended and `tags.html` lists
+//! the pages by tag and category.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+use libimagentrycategory::entry::EntryCategory;
+use libimagentrymarkdown::html::to_html;
+use libimagentrytag::tagable::Tagable;
+use libimagutil::html::escape_html;
+use libimagutil::html::html_page;
+use libimagutil::html::write_file;
+
+use error::Result;
+use links::PageLink;
+use links::internal_links;
+use links::rewrite_links;
+use wiki::Wiki;
+
+struct Page {
+    name     : String,
+    content  : String,
+    links    : Vec<PageLink>,
+    tags     : Vec<String>,
+    category : Option<String>,
+}
+
+/// Export all pages of `wiki` as HTML into `dir`
+///
+/// Returns the number of exported pages.
+pub fn export_html(wiki: &Wiki, dir: &Path) -> Result<usize> {
+    let mut pages = Vec::new();
+
+    for id in wiki.all_ids()? {
+        let id   = id?;
+        let name = match wiki.entry_name(&id) {
+            Some(name) => name,
+            None       => continue,
+        };
+
+        let entry = match wiki.store().get(id.clone())? {
+            Some(entry) => entry,
+            None        => {
+                warn!("Page {} vanished, not exporting", id);
+                continue
+            },
+        };
+
+        let category = if entry.has_category()? {
+            Some(entry.get_category()?)
+        } else {
+            None
+        };
+
+        pages.push(Page {
+            name     : name,
+            content  : entry.get_content().clone(),
+            links    : internal_links(entry.get_content()),
+            tags     : entry.get_tags()?,
+            category : category,
+        });
+    }
+
+    pages.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut backlinks : BTreeMap<PathBuf, Vec<&str>> = BTreeMap::new();
+    for page in pages.iter() {
+        let own_path = wiki.page_path(&page.name);
+        for link in page.links.iter().filter(|link| link.target != own_path) {
+            let sources = backlinks.entry(link.target.clone()).or_insert_with(Vec::new);
+            if !sources.contains(&page.name.as_str()) {
+                sources.push(&page.name);
+            }
+        }
+    }
+
+    for page in pages.iter() {
+        let mut body = format!("<p><a href=\"{0}index.html\">Index</a> | <a href=\"{0}tags.html\">Tags</a></p>\n",
+                               relative_prefix(&page.name));
+
+        body.push_str(&to_html(&rewrite_page_links(wiki, page))?);
+
+        if page.name == "index" {
+            body.push_str("<h2>All pages</h2>\n<ul>\n");
+            for other in pages.iter() {
+                body.push_str(&page_list_item(&page.name, &other.name));
+            }
+            body.push_str("</ul>\n");
+        }
+
+        if !page.tags.is_empty() || page.category.is_some() {
+            body.push_str("<p>");
+            if let Some(ref category) = page.category {
+                body.push_str(&format!("Category: {} ", escape_html(category)));
+            }
+            if !page.tags.is_empty() {
+                body.push_str(&format!("Tags: {}", escape_html(&page.tags.join(", "))));
+            }
+            body.push_str("</p>\n");
+        }
+
+        if let Some(sources) = backlinks.get(&wiki.page_path(&page.name)) {
+            body.push_str("<h2>Backlinks</h2>\n<ul>\n");
+            for source in sources.iter() {
+                body.push_str(&page_list_item(&page.name, source));
+            }
+            body.push_str("</ul>\n");
+        }
+
+        let path = dir.join(format!("{}.html", page.name));
+        write_file(&path, &html_page(&page.name, &body))?;
+    }
+
+    write_file(&dir.join("tags.html"), &html_page("Tags", &tags_page(&pages)))?;
+    Ok(pages.len())
+}
+
+/// Rewrite the links to pages of the wiki in the content of `page` to relative paths of the
+/// exported HTML files
+fn rewrite_page_links(wiki: &Wiki, page: &Page) -> String {
+    rewrite_links(&page.content, |link| {
+        wiki.entry_name_of_path(&link.target)
+            .map(|target| format!("{}{}.html", relative_prefix(&page.name), target))
+    })
+}
+
+fn tags_page(pages: &[Page]) -> String {
+    let mut tags       : BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    let mut categories : BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+
+    for page in pages {
+        for tag in page.tags.iter() {
+            tags.entry(tag.as_str()).or_insert_with(Vec::new).push(&page.name);
+        }
+
+        if let Some(ref category) = page.category {
+            categories.entry(category.as_str()).or_insert_with(Vec::new).push(&page.name);
+        }
+    }
+
+    let mut body = String::from("<p><a href=\"index.html\">Index</a></p>\n");
+    for &(title, ref map) in [("Tags", &tags), ("Categories", &categories)].iter() {
+        body.push_str(&format!("<h1>{}</h1>\n", title));
+        for (name, names) in map.iter() {
+            body.push_str(&format!("<h2>{}</h2>\n<ul>\n", escape_html(name)));
+            for page_name in names.iter() {
+                body.push_str(&page_list_item("tags", page_name));
+            }
+            body.push_str("</ul>\n");
+        }
+    }
+
+    body
+}
+
+/// The prefix to get from the HTML file of page `name` to the root of the export
+fn relative_prefix(name: &str) -> String {
+    "../".repeat(name.matches('/').count())
+}
+
+fn page_list_item(from: &str, to: &str) -> String {
+    format!("<li><a href=\"{}{}.html\">{}</a></li>\n", relative_prefix(from), to, escape_html(to))
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs::File;
+    use std::io::Read;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use tempdir::TempDir;
+
+    use libimagstore::store::Store;
+    use libimagstore::file_abstraction::InMemoryFileAbstraction;
+
+    use store::WikiStore;
+
+    use super::export_html;
+    use super::relative_prefix;
+    use super::page_list_item;
+
+    #[test]
+    fn test_relative_prefix() {
+        assert_eq!(relative_prefix("index"), "");
+        assert_eq!(relative_prefix("cars/mustang"), "../");
+        assert_eq!(relative_prefix("a/b/c"), "../../");
+    }
+
+    #[test]
+    fn test_page_list_item() {
+        assert_eq!(page_list_item("cars/mustang", "cars/beetle"),
+                   "<li><a href=\"../cars/beetle.html\">cars/beetle</a></li>\n");
+    }
+
+    #[test]
+    fn test_no_backlinks_section_for_self_links() {
+        let backend = Arc::new(InMemoryFileAbstraction::default());
+        let store   = Store::new_with_backend(PathBuf::from("/"), &None, backend).unwrap();
+        let wiki    = store.create_wiki("w").unwrap();
+        {
+            let mut a = wiki.create_entry("a").unwrap();
+            *a.get_content_mut() = String::from("[top](wiki/w/a) and [B](wiki/w/b)\n");
+            let _ = wiki.create_entry("b").unwrap();
+        }
+
+        let dir = TempDir::new("imag-wiki-export").unwrap();
+        assert_eq!(export_html(&wiki, dir.path()).unwrap(), 3);
+
+        let read = |name: &str| {
+            let mut buf = String::new();
+            File::open(dir.path().join(name)).unwrap().read_to_string(&mut buf).unwrap();
+            buf
+        };
+
+        assert!(!read("a.html").contains("Backlinks"));
+        assert!(read("b.html").contains("<h2>Backlinks</h2>"));
+    }
+}
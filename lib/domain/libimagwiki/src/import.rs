@@ -0,0 +1,485 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Importing a directory of Markdown notes into a wiki
+//!
+//! This is meant for note collections like Obsidian vaults or Zettelkasten folders:
+//!
+//! * Each `*.md` file becomes a page, named by its path relative to the directory, without the
+//!   extension and with whitespace replaced by `_`. Hidden files and directories (like
+//!   `.obsidian`) are skipped.
+//! * YAML front matter is removed from the content. `tags` are set with libimagentrytag, `date`
+//!   (or `created`) with libimagentrydatetime, all other fields are stored in the header in
+//!   `wiki.frontmatter`.
+//! * `[[wikilinks]]` (also `[[page|text]]` and `[[page#heading]]`) and links to other `.md` files
+//!   are translated into Markdown links to the pages, which are then autolinked. Relative links
+//!   are resolved against the directory of the file; links pointing outside of the imported
+//!   directory are left alone.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+
+use chrono::NaiveDate;
+use chrono::NaiveDateTime;
+use toml::Value;
+use toml_query::insert::TomlValueInsertExt;
+use url::percent_encoding::percent_decode;
+use walkdir::DirEntry;
+use walkdir::WalkDir;
+use yaml_rust::Yaml;
+use yaml_rust::YamlLoader;
+
+use libimagentrydatetime::datetime::EntryDate;
+use libimagentrylink::internal::InternalLinker;
+use libimagentrytag::tag::sanitize_tags;
+use libimagentrytag::tagable::Tagable;
+
+use entry::WikiEntry;
+use error::Result;
+use error::WikiError;
+use links::rewrite_links;
+use wiki::Wiki;
+
+/// What happened when importing a directory
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    /// Names of the imported pages
+    pub imported : Vec<String>,
+
+    /// Names of the pages which were not imported because they exist already
+    pub skipped  : Vec<String>,
+
+    /// The files which could not be imported or autolinked, with the reason why
+    pub failed   : Vec<(PathBuf, WikiError)>,
+}
+
+/// Import all Markdown files in `dir` into `wiki`
+///
+/// Pages which exist already are not overwritten. A file which cannot be imported is recorded in
+/// `ImportReport::failed` and the import continues with the next file. If a page was created
+/// already when the import of its file failed, it is removed again.
+pub fn import_directory(wiki: &Wiki, dir: &Path) -> Result<ImportReport> {
+    let mut files = Vec::new();
+    for entry in WalkDir::new(dir).min_depth(1).into_iter().filter_entry(|e| !is_hidden(e)) {
+        let entry = entry?;
+        let path  = entry.path();
+
+        if path.is_file() && path.extension().map(|e| e == "md").unwrap_or(false) {
+            let relative = path.strip_prefix(dir).map(PathBuf::from).unwrap_or_else(|_| PathBuf::from(path));
+            files.push((PathBuf::from(path), relative));
+        }
+    }
+
+    // Wikilinks only name the file, so we can resolve them by the file name
+    let mut by_file_name = BTreeMap::new();
+    for &(_, ref relative) in files.iter() {
+        if let Some(stem) = relative.file_stem().and_then(|s| s.to_str()) {
+            by_file_name.entry(stem.to_lowercase()).or_insert_with(|| page_name(relative));
+        }
+    }
+
+    let mut report   = ImportReport::default();
+    let mut imported = Vec::new();
+
+    for (path, relative) in files {
+        let name = page_name(&relative);
+        if wiki.get_entry(&name)?.is_some() {
+            warn!("Page '{}' exists already, not importing {}", name, path.display());
+            report.skipped.push(name);
+            continue;
+        }
+
+        match import_file(wiki, &path, &relative, &name, &by_file_name) {
+            Ok(())  => imported.push((name, path)),
+            Err(e)  => {
+                if let Err(e) = remove_page(wiki, &name) {
+                    warn!("Cannot remove partially imported page '{}': {}", name, e);
+                }
+                report.failed.push((path, e));
+            },
+        }
+    }
+
+    // autolink after all pages are created, so links between imported pages point to them
+    for (name, path) in imported {
+        let autolinked = match wiki.get_entry(&name) {
+            Ok(Some(mut entry)) => entry.autolink(wiki.store()),
+            Ok(None)            => Ok(()),
+            Err(e)              => Err(e),
+        };
+
+        match autolinked {
+            Ok(())  => report.imported.push(name),
+            Err(e)  => report.failed.push((path, e)),
+        }
+    }
+
+    Ok(report)
+}
+
+/// Import the file at `path` as page `name`
+fn import_file(wiki: &Wiki,
+               path: &Path,
+               relative: &Path,
+               name: &str,
+               by_file_name: &BTreeMap<String, String>)
+    -> Result<()>
+{
+    let mut buf = String::new();
+    let _ = File::open(path)?.read_to_string(&mut buf)?;
+
+    let (front_matter, content) = split_front_matter(&buf);
+    let front_matter = match front_matter {
+        Some(fm) => YamlLoader::load_from_str(fm)?.into_iter().next(),
+        None     => None,
+    };
+
+    let resolve_wikilink = |target: &str| -> String {
+        let target = target.trim_right_matches(".md");
+        let name   = by_file_name
+            .get(&target.to_lowercase())
+            .cloned()
+            .unwrap_or_else(|| page_name(Path::new(target)));
+        wiki.page_path(&name).display().to_string()
+    };
+
+    let content = translate_wikilinks(content, &resolve_wikilink);
+    let content = rewrite_links(&content, |link| {
+        resolve_file_link(relative, &link.link)
+            .map(|target| wiki.page_path(&page_name(&target)).display().to_string())
+    });
+
+    debug!("Importing {} as '{}'", path.display(), name);
+    let mut entry = wiki.create_entry(name)?;
+    *entry.get_content_mut() = content;
+
+    if let Some(Yaml::Hash(ref fields)) = front_matter {
+        for (key, value) in fields.iter() {
+            let key = match key.as_str() {
+                Some(key) => key,
+                None      => continue,
+            };
+
+            match key {
+                "tags" | "tag" => {
+                    let tags = yaml_tags(value);
+                    let _    = entry.set_tags(&tags)?;
+                },
+                "date" | "created" => match value.as_str().and_then(parse_date) {
+                    Some(date) => { let _ = entry.set_date(date)?; },
+                    None       => warn!("Cannot parse date '{:?}' of {}", value, path.display()),
+                },
+                _ => if !key.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-') {
+                    warn!("Not importing front matter field '{}' of {}", key, path.display());
+                } else if let Some(value) = yaml_to_toml(value) {
+                    let _ = entry.get_header_mut().insert(&format!("wiki.frontmatter.{}", key), value)?;
+                },
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Remove a page which was created by a failed import, if it exists
+fn remove_page(wiki: &Wiki, name: &str) -> Result<()> {
+    match wiki.get_entry(name)? {
+        Some(mut entry) => entry.unlink(wiki.store())?,
+        None            => return Ok(()),
+    }
+
+    wiki.delete_entry(name)
+}
+
+fn is_hidden(entry: &DirEntry) -> bool {
+    entry.file_name().to_str().map(|s| s.starts_with('.')).unwrap_or(false)
+}
+
+/// The name of the page for a file, relative to the imported directory
+pub fn page_name(relative: &Path) -> String {
+    relative
+        .with_extension("")
+        .to_string_lossy()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+/// Resolve a link to another `.md` file, relative to the file `relative` it is found in
+///
+/// The fragment (`#heading`) is removed and the link is percent-decoded. `.` and `..` are
+/// resolved, so the result is the path of the target relative to the imported directory. Links
+/// which are not relative links to `.md` files or which point outside of the imported directory
+/// result in `None`.
+pub fn resolve_file_link(relative: &Path, link: &str) -> Option<PathBuf> {
+    if link.contains("://") || link.starts_with("mailto:") {
+        return None;
+    }
+
+    let link = link.split('#').next().unwrap_or(link);
+    let link = match percent_decode(link.as_bytes()).decode_utf8() {
+        Ok(link) => link.into_owned(),
+        Err(_)   => return None,
+    };
+
+    if !link.ends_with(".md") {
+        return None;
+    }
+
+    let joined = relative
+        .parent()
+        .map(|p| p.join(&link))
+        .unwrap_or_else(|| PathBuf::from(&link));
+
+    let mut target = PathBuf::new();
+    for component in joined.components() {
+        match component {
+            Component::Normal(c)  => target.push(c),
+            Component::CurDir     => {},
+            Component::ParentDir  => if !target.pop() {
+                warn!("Link '{}' in {} points outside of the imported directory", link, relative.display());
+                return None;
+            },
+            Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+
+    Some(target)
+}
+
+/// Split YAML front matter, delimited by `---` lines, from the content
+pub fn split_front_matter(s: &str) -> (Option<&str>, &str) {
+    if !(s.starts_with("---\n") || s.starts_with("---\r\n")) {
+        return (None, s);
+    }
+
+    let start = s.find('\n').unwrap() + 1; // checked above
+    let mut pos = start;
+    for line in s[start..].split('\n') {
+        let next = pos + line.len() + 1;
+        if line.trim_right() == "---" || line.trim_right() == "..." {
+            let rest = if next > s.len() { "" } else { &s[next..] };
+            return (Some(&s[start..pos]), rest);
+        }
+        pos = next;
+    }
+
+    (None, s)
+}
+
+/// Translate `[[wikilinks]]` into Markdown links
+///
+/// `resolve` gets the target of a wikilink (without heading and alias) and returns the target of
+/// the Markdown link. Embeds (`![[...]]`) are not translated.
+pub fn translate_wikilinks<F>(content: &str, resolve: &F) -> String
+    where F: Fn(&str) -> String
+{
+    let mut result = String::with_capacity(content.len());
+    let mut rest   = content;
+
+    while let Some(start) = rest.find("[[") {
+        let end = match rest[start..].find("]]") {
+            Some(end) => start + end,
+            None      => break,
+        };
+
+        let inner    = &rest[start + 2..end];
+        let is_embed = rest[..start].ends_with('!');
+
+        if is_embed || inner.contains('\n') || inner.is_empty() {
+            result.push_str(&rest[..end + 2]);
+        } else {
+            let (target, text) = match inner.find('|') {
+                Some(idx) => (&inner[..idx], &inner[idx + 1..]),
+                None      => (inner, inner),
+            };
+            let target = target.split('#').next().unwrap_or(target).trim();
+
+            result.push_str(&rest[..start]);
+            result.push_str(&format!("[{}]({})", text.trim(), resolve(target)));
+        }
+
+        rest = &rest[end + 2..];
+    }
+
+    result.push_str(rest);
+    result
+}
+
+/// Parse a date from front matter, either a date or a date and a time
+fn parse_date(s: &str) -> Option<NaiveDateTime> {
+    let s = s.trim();
+    ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"]
+        .iter()
+        .filter_map(|fmt| NaiveDateTime::parse_from_str(s, fmt).ok())
+        .next()
+        .or_else(|| NaiveDate::parse_from_str(s, "%Y-%m-%d").ok().map(|d| d.and_hms(0, 0, 0)))
+}
+
+/// Get the tags from a front matter field, either a list or a string with comma or whitespace
+/// separated tags
+///
+/// Tags are made valid with `libimagentrytag::tag::sanitize_tags()`.
+fn yaml_tags(value: &Yaml) -> Vec<String> {
+    let raw : Vec<String> = match *value {
+        Yaml::Array(ref ary) => ary.iter().filter_map(|v| v.as_str().map(String::from)).collect(),
+        Yaml::String(ref s)  => s.split(|c: char| c == ',' || c.is_whitespace()).map(String::from).collect(),
+        _                    => vec![],
+    };
+
+    sanitize_tags(raw)
+}
+
+/// Convert a YAML value to a TOML value
+///
+/// Nulls are dropped. Arrays with elements of different types are converted to arrays of
+/// strings, as TOML does not allow mixed arrays.
+fn yaml_to_toml(value: &Yaml) -> Option<Value> {
+    match *value {
+        Yaml::Real(_)           => value.as_f64().map(Value::Float),
+        Yaml::Integer(i)        => Some(Value::Integer(i)),
+        Yaml::String(ref s)     => Some(Value::String(s.clone())),
+        Yaml::Boolean(b)        => Some(Value::Boolean(b)),
+        Yaml::Array(ref ary)    => {
+            let values : Vec<Value> = ary.iter().filter_map(yaml_to_toml).collect();
+            let mixed  = values.windows(2).any(|w| w[0].type_str() != w[1].type_str());
+
+            if mixed {
+                Some(Value::Array(values.into_iter().map(|v| match v {
+                    Value::String(s) => Value::String(s),
+                    other            => Value::String(other.to_string()),
+                }).collect()))
+            } else {
+                Some(Value::Array(values))
+            }
+        },
+        Yaml::Hash(ref hash)    => {
+            let table = hash
+                .iter()
+                .filter_map(|(k, v)| match (k.as_str(), yaml_to_toml(v)) {
+                    (Some(k), Some(v)) => Some((String::from(k), v)),
+                    _                  => None,
+                })
+                .collect();
+            Some(Value::Table(table))
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs::File;
+    use std::io::Write;
+    use std::sync::Arc;
+
+    use tempdir::TempDir;
+
+    use libimagstore::store::Store;
+    use libimagstore::file_abstraction::InMemoryFileAbstraction;
+
+    use store::WikiStore;
+
+    use super::*;
+
+    #[test]
+    fn test_split_front_matter() {
+        assert_eq!(split_front_matter("---\ntags: [a]\n---\n# Heading\n"), (Some("tags: [a]\n"), "# Heading\n"));
+        assert_eq!(split_front_matter("# Heading\n---\n"), (None, "# Heading\n---\n"));
+        assert_eq!(split_front_matter("---\nunterminated\n"), (None, "---\nunterminated\n"));
+    }
+
+    #[test]
+    fn test_translate_wikilinks() {
+        let resolve = |t: &str| format!("wiki/default/{}", t.replace(' ', "_"));
+        let content = "See [[Some Note]], [[other|the other]] and [[third#Section]]. ![[image.png]]";
+
+        assert_eq!(translate_wikilinks(content, &resolve),
+                   "See [Some Note](wiki/default/Some_Note), [the other](wiki/default/other) and [third#Section](wiki/default/third). ![[image.png]]");
+    }
+
+    #[test]
+    fn test_page_name() {
+        assert_eq!(page_name(Path::new("notes/My Note.md")), "notes/My_Note");
+    }
+
+    #[test]
+    fn test_resolve_file_link() {
+        let from = Path::new("notes/sub/note.md");
+
+        assert_eq!(resolve_file_link(from, "other.md"), Some(PathBuf::from("notes/sub/other.md")));
+        assert_eq!(resolve_file_link(from, "../other.md#Some%20Heading"), Some(PathBuf::from("notes/other.md")));
+        assert_eq!(resolve_file_link(from, "./../../My%20Note.md"), Some(PathBuf::from("My Note.md")));
+        assert_eq!(resolve_file_link(from, "../../../outside.md"), None);
+        assert_eq!(resolve_file_link(from, "/etc/passwd.md"), None);
+        assert_eq!(resolve_file_link(from, "https://example.com/file.md"), None);
+        assert_eq!(resolve_file_link(from, "image.png"), None);
+    }
+
+    #[test]
+    fn test_yaml_tags() {
+        let yaml = YamlLoader::load_from_str("[Rust, note-taking, '#pim']").unwrap().remove(0);
+        assert_eq!(yaml_tags(&yaml), vec!["notetaking", "pim", "rust"]);
+    }
+
+    #[test]
+    fn test_import_continues_after_failures() {
+        let backend = Arc::new(InMemoryFileAbstraction::default());
+        let store   = Store::new_with_backend(PathBuf::from("/"), &None, backend).unwrap();
+        let wiki    = store.create_wiki("w").unwrap();
+        let dir     = TempDir::new("imag-wiki-import").unwrap();
+
+        let write = |name: &str, content: &[u8]| {
+            File::create(dir.path().join(name)).unwrap().write_all(content).unwrap();
+        };
+        write("a.md", b"See [[d]]\n");
+        write("b.md", b"not utf-8: \xff\n");
+        write("c.md", b"---\ntags: [unterminated\n---\nbroken front matter\n");
+        write("d.md", b"# D\n");
+
+        let report = import_directory(&wiki, dir.path()).unwrap();
+
+        let mut imported = report.imported.clone();
+        imported.sort();
+        assert_eq!(imported, vec!["a", "d"]);
+
+        let mut failed = report.failed
+            .iter()
+            .map(|&(ref path, _)| path.file_name().unwrap().to_str().unwrap().to_owned())
+            .collect::<Vec<_>>();
+        failed.sort();
+        assert_eq!(failed, vec!["b.md", "c.md"]);
+
+        assert!(wiki.get_entry("b").unwrap().is_none());
+        assert!(wiki.get_entry("c").unwrap().is_none());
+
+        let links = wiki.get_entry("a")
+            .unwrap()
+            .unwrap()
+            .get_internal_links()
+            .unwrap()
+            .map(|link| link.get_store_id().local().display().to_string())
+            .collect::<Vec<_>>();
+        assert!(links.contains(&String::from("wiki/w/d")));
+    }
+}
@@ -36,8 +36,13 @@
 )]
 
 extern crate filters;
+extern crate chrono;
+extern crate url;
+extern crate walkdir;
+extern crate yaml_rust;
 extern crate toml;
 extern crate toml_query;
+#[cfg(test)] extern crate tempdir;
 #[macro_use] extern crate log;
 #[macro_use] extern crate error_chain;
 
@@ -45,11 +50,20 @@ extern crate toml_query;
 extern crate libimagerror;
 extern crate libimagentrylink;
 extern crate libimagentrymarkdown;
+extern crate libimagentrytag;
+extern crate libimagentrycategory;
+extern crate libimagentrydatetime;
+extern crate libimagutil;
 
 module_entry_path_mod!("wiki");
 
+pub mod diff;
 pub mod entry;
 pub mod error;
+pub mod export;
+pub mod import;
+pub mod links;
+pub mod revision;
 pub mod store;
 pub mod wiki;
 
@@ -0,0 +1,126 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Internal links in the Markdown content of wiki pages
+
+use std::path::PathBuf;
+
+use url::Url;
+use url::ParseError;
+
+use libimagentrymarkdown::link::extract_links;
+
+/// An internal link in the content of a page
+///
+/// The target is the path of the linked entry, relative to the store, as it is resolved by
+/// `WikiEntry::autolink`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageLink {
+    pub title  : String,
+    pub link   : String,
+    pub target : PathBuf,
+}
+
+/// A link from a page to an entry which does not exist
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BrokenLink {
+    pub page   : String,
+    pub title  : String,
+    pub target : PathBuf,
+}
+
+/// Get the internal links in a Markdown text
+///
+/// Links which parse as URLs (external links and refs) are not returned.
+pub fn internal_links(content: &str) -> Vec<PageLink> {
+    extract_links(content)
+        .into_iter()
+        .filter(|link| is_internal(&link.link))
+        .map(|link| PageLink {
+            target : PathBuf::from(link.link.trim_left_matches('/')),
+            title  : link.title,
+            link   : link.link,
+        })
+        .collect()
+}
+
+/// Rewrite the targets of the internal links in a Markdown text
+///
+/// `f` gets each internal link and returns the new target for it, or `None` if the link should
+/// not be changed. Inline links (`[text](target)`) and reference definitions (`[ref]: target`)
+/// are rewritten.
+pub fn rewrite_links<F>(content: &str, f: F) -> String
+    where F: Fn(&PageLink) -> Option<String>
+{
+    let mut result = String::from(content);
+
+    for link in internal_links(content) {
+        if let Some(new_target) = f(&link) {
+            result = result
+                .replace(&format!("]({})", link.link), &format!("]({})", new_target))
+                .replace(&format!("]: {}", link.link), &format!("]: {}", new_target));
+        }
+    }
+
+    result
+}
+
+fn is_internal(link: &str) -> bool {
+    match Url::parse(link) {
+        Err(ParseError::RelativeUrlWithoutBase) => !link.starts_with('#'),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_internal_links() {
+        let content = "[Mustang](wiki/default/cars/mustang), [imag](https://imag-pim.org) and [top](#top) [beetle](/wiki/default/cars/beetle)";
+
+        assert_eq!(internal_links(content), vec![
+            PageLink {
+                title  : String::from("Mustang"),
+                link   : String::from("wiki/default/cars/mustang"),
+                target : PathBuf::from("wiki/default/cars/mustang"),
+            },
+            PageLink {
+                title  : String::from("beetle"),
+                link   : String::from("/wiki/default/cars/beetle"),
+                target : PathBuf::from("wiki/default/cars/beetle"),
+            },
+        ]);
+    }
+
+    #[test]
+    fn test_rewrite_links() {
+        let content = "See [Mustang](wiki/default/cars/mustang) and [Beetle](wiki/default/cars/beetle).\n";
+        let result  = rewrite_links(content, |link| {
+            if link.target == PathBuf::from("wiki/default/cars/mustang") {
+                Some(String::from("wiki/default/cars/ford-mustang"))
+            } else {
+                None
+            }
+        });
+
+        assert_eq!(result, "See [Mustang](wiki/default/cars/ford-mustang) and [Beetle](wiki/default/cars/beetle).\n");
+    }
+}
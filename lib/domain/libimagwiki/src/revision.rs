@@ -0,0 +1,123 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Revisions of wiki pages
+//!
+//! Before a page is changed, its old content can be stored as a revision. Revisions are entries
+//! in the wiki, living in `wiki/<wiki>/.revisions/<page>/<number>`. They are linked to their
+//! page and are not listed as pages of the wiki.
+
+use std::path::PathBuf;
+
+use chrono::NaiveDateTime;
+use toml_query::read::TomlValueReadTypeExt;
+
+use libimagstore::store::Entry;
+use libimagstore::storeid::StoreId;
+
+use error::Result;
+
+/// The name of the collection in a wiki in which revisions are stored
+pub const REVISIONS_COLLECTION : &'static str = ".revisions";
+
+/// The format in which the date of a revision is stored in its header
+pub const REVISION_DATE_FORMAT : &'static str = "%Y-%m-%dT%H:%M:%S";
+
+/// A revision of a wiki page
+#[derive(Debug, Clone)]
+pub struct Revision {
+    number : usize,
+    date   : Option<NaiveDateTime>,
+    id     : StoreId,
+}
+
+impl Revision {
+
+    pub(crate) fn from_entry(number: usize, entry: &Entry) -> Result<Revision> {
+        let date = entry
+            .get_header()
+            .read_string("wiki.revision.date")?
+            .and_then(|s| NaiveDateTime::parse_from_str(&s, REVISION_DATE_FORMAT).ok());
+
+        Ok(Revision {
+            number : number,
+            date   : date,
+            id     : entry.get_location().clone(),
+        })
+    }
+
+    /// The number of the revision, starting at 1
+    pub fn number(&self) -> usize {
+        self.number
+    }
+
+    /// The time the revision was stored, that is when the page was changed
+    pub fn date(&self) -> Option<&NaiveDateTime> {
+        self.date.as_ref()
+    }
+
+    pub fn id(&self) -> &StoreId {
+        &self.id
+    }
+
+}
+
+/// The path of the directory holding the revisions of a page, relative to the module path
+pub(crate) fn revisions_path(wiki_name: &str, entry_name: &str) -> PathBuf {
+    PathBuf::from(format!("{}/{}/{}", wiki_name, REVISIONS_COLLECTION, entry_name))
+}
+
+/// The path of a revision of a page, relative to the module path
+pub(crate) fn revision_path(wiki_name: &str, entry_name: &str, number: usize) -> PathBuf {
+    revisions_path(wiki_name, entry_name).join(format!("{}", number))
+}
+
+/// Get the revision number from the id of a revision entry of the page `entry_name`
+pub(crate) fn revision_number(id: &StoreId, wiki_name: &str, entry_name: &str) -> Option<usize> {
+    let dir = PathBuf::from("wiki").join(revisions_path(wiki_name, entry_name));
+
+    if id.local().parent() != Some(dir.as_path()) {
+        return None;
+    }
+
+    id.local()
+        .file_name()
+        .and_then(|n| n.to_str())
+        .and_then(|n| n.parse::<usize>().ok())
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use libimagstore::storeid::StoreId;
+
+    use super::revision_number;
+
+    #[test]
+    fn test_revision_number() {
+        let id = |s: &str| StoreId::new_baseless(PathBuf::from(s)).unwrap();
+
+        assert_eq!(revision_number(&id("wiki/w/.revisions/page/3"), "w", "page"), Some(3));
+        assert_eq!(revision_number(&id("wiki/w/.revisions/sub/page/12"), "w", "sub/page"), Some(12));
+        assert_eq!(revision_number(&id("wiki/w/.revisions/sub/page/12"), "w", "sub"), None);
+        assert_eq!(revision_number(&id("wiki/w/.revisions/page/x"), "w", "page"), None);
+        assert_eq!(revision_number(&id("wiki/w/page"), "w", "page"), None);
+    }
+}
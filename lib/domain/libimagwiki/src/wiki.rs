@@ -17,9 +17,13 @@
 // Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
 //
 
+use std::path::Path;
 use std::path::PathBuf;
 
+use chrono::Local;
 use filters::filter::Filter;
+use toml::Value;
+use toml_query::insert::TomlValueInsertExt;
 
 use libimagstore::store::Store;
 use libimagstore::store::Entry;
@@ -32,6 +36,15 @@ use libimagentrylink::internal::InternalLinker;
 use error::WikiError as WE;
 use error::WikiErrorKind as WEK;
 use error::Result;
+use links::BrokenLink;
+use links::PageLink;
+use links::internal_links;
+use links::rewrite_links;
+use revision::Revision;
+use revision::REVISIONS_COLLECTION;
+use revision::REVISION_DATE_FORMAT;
+use revision::revision_number;
+use revision::revision_path;
 
 pub struct Wiki<'a, 'b>(&'a Store, &'b str);
 
@@ -50,6 +63,15 @@ impl<'a, 'b> Wiki<'a, 'b> {
         Wiki(store, name)
     }
 
+    /// The name of the wiki
+    pub fn name(&self) -> &'b str {
+        self.1
+    }
+
+    pub(crate) fn store(&self) -> &'a Store {
+        self.0
+    }
+
     pub(crate) fn create_index_page(&self) -> Result<FileLockEntry<'a>> {
         let path = PathBuf::from(format!("{}/index", self.1));
         let sid  = ::module_path::ModuleEntryPath::new(path).into_storeid()?;
@@ -98,11 +120,324 @@ impl<'a, 'b> Wiki<'a, 'b> {
         Ok(WikiIdIterator(self.0.entries()?, filter))
     }
 
+    /// Delete a page and all its revisions
     pub fn delete_entry<EN: AsRef<str>>(&self, entry_name: EN) -> Result<()> {
+        for revision in self.revisions(entry_name.as_ref())? {
+            debug!("Deleting revision {}", revision.id());
+            self.0.delete(revision.id().clone())?;
+        }
+
         let path  = PathBuf::from(format!("{}/{}", self.1, entry_name.as_ref()));
         let sid   = ::module_path::ModuleEntryPath::new(path).into_storeid()?;
         self.0.delete(sid).map_err(WE::from)
     }
+
+    /// Rename a page
+    ///
+    /// The page is moved with `Store::move_by_id()`, together with its revisions. Its links are
+    /// re-added after moving, the links to the page in the content of all pages of the wiki are
+    /// rewritten to the new name and these pages are autolinked again. The old content of the pages
+    /// with rewritten links is stored as a revision.
+    ///
+    /// The index page cannot be renamed. Nothing is changed if the page or one of its revisions
+    /// cannot be moved because the new name is taken already.
+    pub fn rename_entry<EN: AsRef<str>, NN: AsRef<str>>(&self, entry_name: EN, new_name: NN) -> Result<()> {
+        use entry::WikiEntry;
+
+        let old_name = entry_name.as_ref();
+        let new_name = new_name.as_ref();
+
+        if old_name == "index" {
+            return Err(WE::from_kind(WEK::IndexCannotBeRenamed));
+        }
+
+        let old_sid  = ::module_path::ModuleEntryPath::new(PathBuf::from(format!("{}/{}", self.1, old_name))).into_storeid()?;
+        let new_sid  = ::module_path::ModuleEntryPath::new(PathBuf::from(format!("{}/{}", self.1, new_name))).into_storeid()?;
+
+        // check that everything can be moved before touching anything
+        if self.get_entry(old_name)?.is_none() {
+            return Err(WE::from_kind(WEK::PageDoesNotExist(String::from(old_name))));
+        }
+
+        if self.get_entry(new_name)?.is_some() {
+            return Err(WE::from_kind(WEK::PageExists(String::from(new_name))));
+        }
+
+        let mut revisions = vec![];
+        for revision in self.revisions(old_name)? {
+            let new_rev_sid = ::module_path::ModuleEntryPath::new(revision_path(self.1, new_name, revision.number()))
+                .into_storeid()?;
+
+            if self.0.get(new_rev_sid.clone())?.is_some() {
+                return Err(WE::from_kind(WEK::RevisionExists(String::from(new_name), revision.number())));
+            }
+
+            revisions.push((revision.id().clone(), new_rev_sid));
+        }
+
+        // remove the links of the page and re-add them after moving
+        let mut linked : Vec<StoreId> = {
+            let mut page = self
+                .get_entry(old_name)?
+                .ok_or_else(|| WE::from_kind(WEK::PageDoesNotExist(String::from(old_name))))?;
+
+            let linked = page
+                .get_internal_links()?
+                .map(|link| link.get_store_id().clone())
+                .collect();
+
+            let _ = page.unlink(self.0)?;
+            linked
+        };
+
+        debug!("Moving '{}' to '{}'", old_sid, new_sid);
+        if let Err(e) = self.0.move_by_id(old_sid, new_sid) {
+            // the page was not moved, so it gets its links back
+            self.link_page(old_name, linked)?;
+            return Err(WE::from(e));
+        }
+
+        for (old_rev_sid, new_rev_sid) in revisions {
+            debug!("Moving revision '{}' to '{}'", old_rev_sid, new_rev_sid);
+            let _ = self.0.move_by_id(old_rev_sid.clone(), new_rev_sid.clone())?;
+
+            if let Some(mut entry) = self.0.get(new_rev_sid.clone())? {
+                let _ = entry.get_header_mut().insert("wiki.revision.page", Value::String(String::from(new_name)))?;
+            }
+
+            for id in linked.iter_mut() {
+                if id.local() == old_rev_sid.local() {
+                    *id = new_rev_sid.clone();
+                }
+            }
+        }
+
+        self.link_page(new_name, linked)?;
+
+        let old_path = self.page_path(old_name);
+        let new_link = self.page_path(new_name).display().to_string();
+
+        for id in self.all_ids()?.collect::<Result<Vec<StoreId>>>()? {
+            let mut entry = match self.0.get(id.clone())? {
+                Some(entry) => entry,
+                None        => continue,
+            };
+
+            let content = rewrite_links(entry.get_content(), |link| if link.target == old_path {
+                if link.link.starts_with('/') {
+                    Some(format!("/{}", new_link))
+                } else {
+                    Some(new_link.clone())
+                }
+            } else {
+                None
+            });
+
+            if content != *entry.get_content() {
+                debug!("Rewriting links to '{}' in {}", old_name, id);
+                let old_content = entry.get_content().clone();
+                let _           = self.add_revision(&mut entry, old_content)?;
+                *entry.get_content_mut() = content;
+                let _ = entry.autolink(self.0)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Link the page `entry_name` with all of `ids`
+    fn link_page(&self, entry_name: &str, ids: Vec<StoreId>) -> Result<()> {
+        let mut page = self
+            .get_entry(entry_name)?
+            .ok_or_else(|| WE::from_kind(WEK::PageDoesNotExist(String::from(entry_name))))?;
+
+        for id in ids {
+            match self.0.get(id.clone())? {
+                Some(mut other) => { let _ = page.add_internal_link(&mut other)?; },
+                None            => warn!("Linked entry {} does not exist, not linking", id),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get the name of a page of this wiki from its id
+    pub fn entry_name(&self, id: &StoreId) -> Option<String> {
+        if !IdIsInWikiFilter(self.1).filter(id) {
+            return None;
+        }
+
+        id.local()
+            .strip_prefix(PathBuf::from("wiki").join(self.1))
+            .ok()
+            .and_then(|p| p.to_str())
+            .map(String::from)
+    }
+
+    /// Get the name of a page of this wiki from its path relative to the store
+    pub fn entry_name_of_path(&self, path: &Path) -> Option<String> {
+        StoreId::new_baseless(PathBuf::from(path))
+            .ok()
+            .and_then(|id| self.entry_name(&id))
+    }
+
+    /// Store `content` as the newest revision of `page`
+    ///
+    /// This should be called with the old content of the page whenever the page is changed. The
+    /// revision is linked to the page.
+    pub fn add_revision(&self, page: &mut Entry, content: String) -> Result<FileLockEntry<'a>> {
+        let name = self
+            .entry_name(page.get_location())
+            .ok_or_else(|| WE::from_kind(WEK::NotAWikiPage(page.get_location().clone())))?;
+
+        let number = self.revisions(&name)?.last().map(|r| r.number() + 1).unwrap_or(1);
+        let sid    = ::module_path::ModuleEntryPath::new(revision_path(self.1, &name, number)).into_storeid()?;
+        let date   = Local::now().naive_local().format(REVISION_DATE_FORMAT).to_string();
+
+        debug!("Creating revision {} of '{}'", number, name);
+        let mut revision = self.0.create(sid)?;
+        {
+            let header = revision.get_header_mut();
+            let _      = header.insert("wiki.revision.page", Value::String(name))?;
+            let _      = header.insert("wiki.revision.number", Value::Integer(number as i64))?;
+            let _      = header.insert("wiki.revision.date", Value::String(date))?;
+        }
+        *revision.get_content_mut() = content;
+
+        revision.add_internal_link(page)
+            .map_err(WE::from)
+            .map(|_| revision)
+    }
+
+    /// Get all revisions of a page, the oldest first
+    pub fn revisions<EN: AsRef<str>>(&self, entry_name: EN) -> Result<Vec<Revision>> {
+        let mut revisions = Vec::new();
+
+        for id in self.0.entries()? {
+            let id = id?;
+            if !id.is_in_collection(&["wiki", self.1, REVISIONS_COLLECTION]) {
+                continue;
+            }
+
+            if let Some(number) = revision_number(&id, self.1, entry_name.as_ref()) {
+                match self.0.get(id.clone())? {
+                    Some(entry) => revisions.push(Revision::from_entry(number, &entry)?),
+                    None        => warn!("Revision {} vanished", id),
+                }
+            }
+        }
+
+        revisions.sort_by_key(Revision::number);
+        Ok(revisions)
+    }
+
+    /// Get a revision of a page
+    pub fn get_revision<EN: AsRef<str>>(&self, entry_name: EN, number: usize) -> Result<Option<FileLockEntry<'a>>> {
+        let path = revision_path(self.1, entry_name.as_ref(), number);
+        let sid  = ::module_path::ModuleEntryPath::new(path).into_storeid()?;
+        self.0.get(sid).map_err(WE::from)
+    }
+
+    /// Get the names of the pages which link to the page `entry_name` in their content
+    pub fn backlinks<EN: AsRef<str>>(&self, entry_name: EN) -> Result<Vec<String>> {
+        let target = self.page_path(entry_name.as_ref());
+
+        self.page_links()
+            .map(|pages| {
+                pages.into_iter()
+                    .filter(|&(_, ref links)| links.iter().any(|l| l.target == target))
+                    .map(|(name, _)| name)
+                    .collect()
+            })
+    }
+
+    /// Get the names of the pages which are not linked from the content of any other page
+    ///
+    /// The links every page has to the index page are not taken into account, and the index page
+    /// itself is never an orphan.
+    pub fn orphans(&self) -> Result<Vec<String>> {
+        let pages = self.page_links()?;
+
+        Ok(pages
+            .iter()
+            .filter(|&&(ref name, _)| name != "index")
+            .filter(|&&(ref name, _)| {
+                let target = self.page_path(name);
+                !pages.iter().any(|&(ref other, ref links)| {
+                    other != name && links.iter().any(|l| l.target == target)
+                })
+            })
+            .map(|&(ref name, _)| name.clone())
+            .collect())
+    }
+
+    /// Get the links in the content of the pages of this wiki which point to entries which do not
+    /// exist
+    pub fn broken_links(&self) -> Result<Vec<BrokenLink>> {
+        let mut broken = Vec::new();
+
+        for (name, links) in self.page_links()? {
+            for link in links {
+                let sid = StoreId::new(Some(self.0.path().clone()), link.target.clone())?;
+                if self.0.get(sid)?.is_none() {
+                    broken.push(BrokenLink {
+                        page   : name.clone(),
+                        title  : link.title,
+                        target : link.target,
+                    });
+                }
+            }
+        }
+
+        Ok(broken)
+    }
+
+    /// Get the internal links in the content of all pages, by page name
+    fn page_links(&self) -> Result<Vec<(String, Vec<PageLink>)>> {
+        let mut pages = Vec::new();
+
+        for id in self.all_ids()? {
+            let id   = id?;
+            let name = match self.entry_name(&id) {
+                Some(name) => name,
+                None       => continue,
+            };
+
+            match self.0.get(id.clone())? {
+                Some(entry) => pages.push((name, internal_links(entry.get_content()))),
+                None        => warn!("Page {} vanished", id),
+            }
+        }
+
+        Ok(pages)
+    }
+
+    /// The path of a page, relative to the store
+    pub(crate) fn page_path(&self, entry_name: &str) -> PathBuf {
+        PathBuf::from("wiki").join(self.1).join(entry_name)
+    }
+
+    /// Revert a page to the content of one of its revisions
+    ///
+    /// The current content of the page is stored as a new revision before, so reverting can be
+    /// undone.
+    pub fn revert<EN: AsRef<str>>(&self, entry_name: EN, number: usize) -> Result<FileLockEntry<'a>> {
+        let name    = entry_name.as_ref();
+        let content = self
+            .get_revision(name, number)?
+            .ok_or_else(|| WE::from_kind(WEK::RevisionDoesNotExist(String::from(name), number)))?
+            .get_content()
+            .clone();
+
+        let mut page = self
+            .get_entry(name)?
+            .ok_or_else(|| WE::from_kind(WEK::PageDoesNotExist(String::from(name))))?;
+
+        let old = page.get_content().clone();
+        let _   = self.add_revision(&mut page, old)?;
+        *page.get_content_mut() = content;
+        Ok(page)
+    }
 }
 
 pub struct WikiIdIterator<'a>(StoreIdIteratorWithStore<'a>, IdIsInWikiFilter<'a>);
@@ -132,16 +467,149 @@ impl<'a> IdIsInWikiFilter<'a> {
     }
 }
 
+/// Filter for the pages of a wiki, revisions of pages are not included
 impl<'a> Filter<StoreId> for IdIsInWikiFilter<'a> {
     fn filter(&self, id: &StoreId) -> bool {
-        id.is_in_collection(&["wiki", &self.0])
+        id.is_in_collection(&["wiki", &self.0]) &&
+            !id.is_in_collection(&["wiki", &self.0, REVISIONS_COLLECTION])
     }
 }
 
 impl<'a> Filter<Entry> for IdIsInWikiFilter<'a> {
     fn filter(&self, e: &Entry) -> bool {
-        e.get_location().is_in_collection(&["wiki", &self.0])
+        Filter::<StoreId>::filter(self, e.get_location())
     }
 }
 
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use libimagstore::store::Store;
+    use libimagstore::file_abstraction::InMemoryFileAbstraction;
+    use libimagentrylink::internal::InternalLinker;
+
+    use error::WikiErrorKind as WEK;
+    use store::WikiStore;
+
+    fn get_store() -> Store {
+        let backend = Arc::new(InMemoryFileAbstraction::default());
+        Store::new_with_backend(PathBuf::from("/"), &None, backend).unwrap()
+    }
+
+    fn links_of(store: &Store, id: &str) -> Vec<String> {
+        let mut links = store
+            .get(PathBuf::from(id))
+            .unwrap()
+            .unwrap()
+            .get_internal_links()
+            .unwrap()
+            .map(|link| link.get_store_id().local().display().to_string())
+            .collect::<Vec<_>>();
+        links.sort();
+        links
+    }
+
+    #[test]
+    fn test_rename_keeps_links() {
+        let store = get_store();
+        let wiki  = store.create_wiki("w").unwrap();
+        {
+            let mut a = wiki.create_entry("a").unwrap();
+            let mut b = wiki.create_entry("b").unwrap();
+            let _ = a.add_internal_link(&mut b).unwrap();
+        }
+
+        wiki.rename_entry("a", "c").unwrap();
+
+        assert_eq!(links_of(&store, "wiki/w/c"), vec!["wiki/w/b", "wiki/w/index"]);
+        assert_eq!(links_of(&store, "wiki/w/b"), vec!["wiki/w/c", "wiki/w/index"]);
+    }
+
+    #[test]
+    fn test_rename_onto_existing_page() {
+        let store = get_store();
+        let wiki  = store.create_wiki("w").unwrap();
+        let _     = wiki.create_entry("a").unwrap();
+        let _     = wiki.create_entry("b").unwrap();
 
+        match wiki.rename_entry("a", "b") {
+            Err(e) => match *e.kind() {
+                WEK::PageExists(ref name) => assert_eq!(name, "b"),
+                ref other                 => panic!("Expected PageExists, got {:?}", other),
+            },
+            Ok(_) => panic!("Renaming onto an existing page worked"),
+        }
+
+        assert_eq!(links_of(&store, "wiki/w/a"), vec!["wiki/w/index"]);
+    }
+
+    #[test]
+    fn test_rename_index() {
+        let store = get_store();
+        let wiki  = store.create_wiki("w").unwrap();
+        let _     = wiki.create_entry("a").unwrap();
+
+        match wiki.rename_entry("index", "start") {
+            Err(e) => match *e.kind() {
+                WEK::IndexCannotBeRenamed => {},
+                ref other                 => panic!("Expected IndexCannotBeRenamed, got {:?}", other),
+            },
+            Ok(_) => panic!("Renaming the index worked"),
+        }
+
+        assert_eq!(links_of(&store, "wiki/w/index"), vec!["wiki/w/a"]);
+    }
+
+    #[test]
+    fn test_backlinks_orphans_broken_links() {
+        let store = get_store();
+        let wiki  = store.create_wiki("w").unwrap();
+        {
+            let mut a = wiki.create_entry("a").unwrap();
+            *a.get_content_mut() = String::from("[B](wiki/w/b), [missing](wiki/w/missing) and [me](wiki/w/a)\n");
+
+            let mut b = wiki.create_entry("b").unwrap();
+            *b.get_content_mut() = String::from("[A](wiki/w/a)\n");
+
+            let mut c = wiki.create_entry("c").unwrap();
+            *c.get_content_mut() = String::from("[B](wiki/w/b)\n");
+        }
+
+        let mut backlinks = wiki.backlinks("b").unwrap();
+        backlinks.sort();
+        assert_eq!(backlinks, vec!["a", "c"]);
+        assert_eq!(wiki.backlinks("c").unwrap(), Vec::<String>::new());
+
+        // c is not linked from any other page
+        assert_eq!(wiki.orphans().unwrap(), vec!["c"]);
+
+        let broken = wiki.broken_links().unwrap();
+        assert_eq!(broken.len(), 1);
+        assert_eq!(broken[0].page, "a");
+        assert_eq!(broken[0].title, "missing");
+        assert_eq!(broken[0].target, PathBuf::from("wiki/w/missing"));
+    }
+
+    #[test]
+    fn test_rename_stores_revision_of_rewritten_pages() {
+        let store = get_store();
+        let wiki  = store.create_wiki("w").unwrap();
+        {
+            let _     = wiki.create_entry("a").unwrap();
+            let mut b = wiki.create_entry("b").unwrap();
+            *b.get_content_mut() = String::from("See [A](wiki/w/a)\n");
+        }
+
+        wiki.rename_entry("a", "c").unwrap();
+
+        let b = wiki.get_entry("b").unwrap().unwrap();
+        assert_eq!(b.get_content(), "See [A](wiki/w/c)\n");
+
+        assert_eq!(wiki.revisions("b").unwrap().len(), 1);
+        let revision = wiki.get_revision("b", 1).unwrap().unwrap();
+        assert_eq!(revision.get_content(), "See [A](wiki/w/a)\n");
+    }
+
+}
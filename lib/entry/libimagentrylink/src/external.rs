@@ -293,6 +293,16 @@ pub fn is_external_link_storeid<A: AsRef<StoreId> + Debug>(id: A) -> bool {
     id.as_ref().local().starts_with("links/external")
 }
 
+/// Get the `StoreId` of the entry which represents the external link `url`
+///
+/// The entry lives in `/links/external/<SHA of the URL>`.
+pub fn external_link_store_id(url: &Url) -> Result<StoreId> {
+    let hash = hex::encode(Sha1::digest(url.as_str().as_bytes()));
+    ModuleEntryPath::new(format!("external/{}", hash))
+        .into_storeid()
+        .map_err(From::from)
+}
+
 /// Implement `ExternalLinker` for `Entry`, hiding the fact that there is no such thing as an external
 /// link in an entry, but internal links to other entries which serve as external links, as one
 /// entry in the store can only have one external link.
@@ -318,16 +328,12 @@ impl ExternalLinker for Entry {
 
         debug!("Iterating {} links = {:?}", links.len(), links);
         for link in links { // for all links
-            let hash = hex::encode(Sha1::digest(&link.as_str().as_bytes()));
-            let file_id =
-                ModuleEntryPath::new(format!("external/{}", hash)).into_storeid()
-                    .map_dbg_err(|_| {
-                        format!("Failed to build StoreId for this hash '{:?}'", hash)
-                    })
-                ?;
+            let file_id = external_link_store_id(&link)
+                .map_dbg_err(|_| {
+                    format!("Failed to build StoreId for this link '{:?}'", link)
+                })?;
 
             debug!("Link    = '{:?}'", link);
-            debug!("Hash    = '{:?}'", hash);
             debug!("StoreId = '{:?}'", file_id);
 
             // retrieve the file from the store, which implicitely creates the entry if it does not
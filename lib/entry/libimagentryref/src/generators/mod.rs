@@ -189,8 +189,8 @@ macro_rules! make_sha_mod {
                         .open(path)
                         .map_err(RE::from)
                         .and_then(|mut file| {
-                            let mut buffer = String::new();
-                            let _ = file.read_to_string(&mut buffer)?;
+                            let mut buffer = vec![];
+                            let _ = file.read_to_end(&mut buffer)?;
                             $hashingimpl(buffer)
                         })
                 }
@@ -221,7 +221,6 @@ macro_rules! make_sha_mod {
                                 }
                             }?;
 
-                            let buffer = String::from_utf8(buffer)?;
                             $hashingimpl(buffer)
                         })
                 }
@@ -234,11 +233,11 @@ macro_rules! make_sha_mod {
 
 #[cfg(feature = "generators-sha1")]
 make_sha_mod! {
-    sha1, Sha1, |buffer: String| {
+    sha1, Sha1, |buffer: Vec<u8>| {
         use sha1::{Sha1, Digest};
 
         trace!("Hashing: '{:?}'", buffer);
-        let res = hex::encode(Sha1::digest(buffer.as_bytes()));
+        let res = hex::encode(Sha1::digest(&buffer));
         trace!("Hash => '{:?}'", res);
 
         Ok(res)
@@ -247,41 +246,41 @@ make_sha_mod! {
 
 #[cfg(feature = "generators-sha224")]
 make_sha_mod! {
-    sha224, Sha224, |buffer: String| {
+    sha224, Sha224, |buffer: Vec<u8>| {
         use sha2::{Sha224, Digest};
-        Ok(hex::encode(Sha224::digest(buffer.as_bytes())))
+        Ok(hex::encode(Sha224::digest(&buffer)))
     }
 }
 
 #[cfg(feature = "generators-sha256")]
 make_sha_mod! {
-    sha256, Sha256, |buffer: String| {
+    sha256, Sha256, |buffer: Vec<u8>| {
         use sha2::{Sha256, Digest};
-        Ok(hex::encode(Sha256::digest(buffer.as_bytes())))
+        Ok(hex::encode(Sha256::digest(&buffer)))
     }
 }
 
 #[cfg(feature = "generators-sha384")]
 make_sha_mod! {
-    sha384, Sha384, |buffer: String| {
+    sha384, Sha384, |buffer: Vec<u8>| {
         use sha2::{Sha384, Digest};
-        Ok(hex::encode(Sha384::digest(buffer.as_bytes())))
+        Ok(hex::encode(Sha384::digest(&buffer)))
     }
 }
 
 #[cfg(feature = "generators-sha512")]
 make_sha_mod! {
-    sha512, Sha512, |buffer: String| {
+    sha512, Sha512, |buffer: Vec<u8>| {
         use sha2::{Sha512, Digest};
-        Ok(hex::encode(Sha512::digest(buffer.as_bytes())))
+        Ok(hex::encode(Sha512::digest(&buffer)))
     }
 }
 
 #[cfg(feature = "generators-sha3")]
 make_sha_mod! {
-    sha3, Sha3, |buffer: String| {
+    sha3, Sha3, |buffer: Vec<u8>| {
         use sha3::{Sha3_256, Digest};
-        Ok(hex::encode(Sha3_256::digest(buffer.as_bytes())))
+        Ok(hex::encode(Sha3_256::digest(&buffer)))
     }
 }
 
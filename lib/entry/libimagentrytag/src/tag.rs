@@ -42,3 +42,37 @@ pub fn is_tag_str(s: &String) -> Result<(), String> {
     }
 }
 
+/// Turn arbitrary strings (e.g. tags from other applications) into valid tags
+///
+/// The strings are lowercased and all characters which are not alphanumeric are removed. Strings
+/// which are still not valid tags afterwards are dropped. The result is sorted and deduplicated.
+pub fn sanitize_tags<I, S>(tags: I) -> Vec<Tag>
+    where I: IntoIterator<Item = S>,
+          S: AsRef<str>
+{
+    let mut result : Vec<Tag> = tags
+        .into_iter()
+        .map(|t| t.as_ref().chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect::<String>())
+        .filter(|t| {
+            let valid = is_tag_str(t).is_ok();
+            if !valid && !t.is_empty() {
+                warn!("Dropping invalid tag '{}'", t);
+            }
+            valid
+        })
+        .collect();
+
+    result.sort();
+    result.dedup();
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::sanitize_tags;
+
+    #[test]
+    fn test_sanitize_tags() {
+        assert_eq!(sanitize_tags(vec!["Rust", "c++", "web2", "rust", "#pim"]), vec!["c", "pim", "rust"]);
+    }
+}
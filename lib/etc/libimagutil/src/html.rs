@@ -0,0 +1,74 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Helpers for writing plain HTML exports
+
+use std::fs::OpenOptions;
+use std::fs::create_dir_all;
+use std::io::Result;
+use std::io::Write;
+use std::path::Path;
+
+/// Escape the characters which are special in HTML text and attribute values
+pub fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Wrap an already rendered HTML body in a minimal standalone page with the (escaped) title
+pub fn html_page(title: &str, body: &str) -> String {
+    format!("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n</head>\n<body>\n{}</body>\n</html>\n",
+            escape_html(title),
+            body)
+}
+
+/// Write `content` to `path`, creating the parent directories and truncating an existing file
+pub fn write_file(path: &Path, content: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        create_dir_all(parent)?;
+    }
+
+    debug!("Writing {}", path.display());
+    OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(path)?
+        .write_all(content.as_bytes())
+}
+
+#[cfg(test)]
+mod test {
+    use super::escape_html;
+    use super::html_page;
+
+    #[test]
+    fn test_escape_html() {
+        assert_eq!("a &amp; &lt;b&gt; &quot;c&quot;", escape_html("a & <b> \"c\""));
+    }
+
+    #[test]
+    fn test_html_page_escapes_title_only() {
+        let page = html_page("<T>", "<p>body</p>\n");
+        assert!(page.contains("<title>&lt;T&gt;</title>"));
+        assert!(page.contains("<body>\n<p>body</p>\n</body>"));
+    }
+}
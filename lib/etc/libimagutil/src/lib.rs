@@ -46,6 +46,7 @@ pub mod cli_validators;
 pub mod date;
 pub mod debug_result;
 pub mod edit;
+pub mod html;
 pub mod info_result;
 pub mod key_value_split;
 pub mod variants;